@@ -26,8 +26,8 @@ fn replicate(function_name: &str, test: OldTest) -> Result<Test> {
 
     let input_call = WasmFunctionCall::new(
         function_name,
-        vec![FunctionValue::Char(ContainerVariant::Grid(input_grid))],
-        FunctionType::Char(ContainerVariantType::Grid),
+        vec![FunctionValue::Char(ContainerVariant::grid(input_grid))],
+        FunctionType::Char(ContainerVariantType::grid()),
     );
 
     let output_grid = test
@@ -36,7 +36,7 @@ fn replicate(function_name: &str, test: OldTest) -> Result<Test> {
         .map(|line| line.chars().collect::<Vec<char>>())
         .collect::<Vec<_>>();
 
-    let result = FunctionValue::Char(ContainerVariant::Grid(output_grid));
+    let result = FunctionValue::Char(ContainerVariant::grid(output_grid));
 
     Ok(Test {
         id: test.id,
@@ -131,7 +131,7 @@ fn vec_i32(function_name: &str, test: OldTest) -> Result<Test> {
 
     let input_call = WasmFunctionCall::new(
         function_name,
-        vec![FunctionValue::Int(ContainerVariant::List(nums))],
+        vec![FunctionValue::Int(ContainerVariant::list(nums))],
         FunctionType::Int(ContainerVariantType::Single),
     );
 
@@ -169,8 +169,8 @@ fn need_for_speed(function_name: &str, test: OldTest) -> Result<Test> {
         function_name,
         vec![
             FunctionValue::Int(ContainerVariant::Single(t)),
-            FunctionValue::Int(ContainerVariant::List(d)),
-            FunctionValue::Int(ContainerVariant::List(s)),
+            FunctionValue::Int(ContainerVariant::list(d)),
+            FunctionValue::Int(ContainerVariant::list(s)),
         ],
         FunctionType::Double(ContainerVariantType::Single),
     );
@@ -215,7 +215,7 @@ fn maximum_nonadjacent_sum(function_name: &str, test: OldTest) -> Result<Test> {
         function_name,
         vec![
             FunctionValue::Int(ContainerVariant::Graph(edges)),
-            FunctionValue::Int(ContainerVariant::List(values)),
+            FunctionValue::Int(ContainerVariant::list(values)),
         ],
         FunctionType::Int(ContainerVariantType::Single),
     );
@@ -296,14 +296,14 @@ fn rainbow_roads(function_name: &str, test: OldTest) -> Result<Test> {
         function_name,
         vec![
             FunctionValue::Int(ContainerVariant::Graph(edges)),
-            FunctionValue::Int(ContainerVariant::List(weights)),
+            FunctionValue::Int(ContainerVariant::list(weights)),
         ],
         FunctionType::Int(ContainerVariantType::Single),
     );
 
     let (_first, rest) = test.expected_output.split_once("\n").unwrap();
 
-    let result = FunctionValue::Int(ContainerVariant::List(
+    let result = FunctionValue::Int(ContainerVariant::list(
         rest.lines().map(|x| x.parse().unwrap()).collect(),
     ));
 
@@ -334,6 +334,19 @@ fn convert_raw_string(function_name: &str, test: OldTest) -> Result<Test> {
     })
 }
 
+/// Keeps the test as is, for problems that are judged through stdin/stdout. The problem's kind
+/// must also be set to `stdio`.
+#[allow(dead_code)]
+fn stdio(_function_name: &str, test: OldTest) -> Result<Test> {
+    Ok(Test {
+        id: test.id,
+        index: test.test_number,
+        max_fuel: test.max_runtime,
+        input: WasmFunctionCall::stdio(test.input),
+        expected_output: FunctionValue::String(ContainerVariant::Single(test.expected_output)),
+    })
+}
+
 fn int_int(function_name: &str, test: OldTest) -> Result<Test> {
     let input_call = WasmFunctionCall::new(
         function_name,
@@ -499,7 +512,7 @@ fn mission_improbable(function_name: &str, test: OldTest) -> Result<Test> {
 
     let input_call = WasmFunctionCall::new(
         function_name,
-        vec![FunctionValue::Long(ContainerVariant::Grid(input_grid))],
+        vec![FunctionValue::Long(ContainerVariant::grid(input_grid))],
         FunctionType::Long(ContainerVariantType::Single),
     );
 
@@ -598,7 +611,7 @@ fn rectangles(function_name: &str, test: OldTest) -> Result<Test> {
 
     let input_call = WasmFunctionCall::new(
         function_name,
-        vec![FunctionValue::Int(ContainerVariant::Grid(input_grid))],
+        vec![FunctionValue::Int(ContainerVariant::grid(input_grid))],
         FunctionType::Long(ContainerVariantType::Single),
     );
 
@@ -632,7 +645,7 @@ fn longest_common_subsequence(function_name: &str, test: OldTest) -> Result<Test
         function_name,
         vec![
             FunctionValue::Int(ContainerVariant::Single(k)),
-            FunctionValue::String(ContainerVariant::List(input_grid)),
+            FunctionValue::String(ContainerVariant::list(input_grid)),
         ],
         FunctionType::Int(ContainerVariantType::Single),
     );
@@ -675,7 +688,7 @@ fn meeting(function_name: &str, test: OldTest) -> Result<Test> {
             FunctionValue::Int(ContainerVariant::Single(n)),
             FunctionValue::Int(ContainerVariant::Single(k)),
             FunctionValue::Int(ContainerVariant::Single(t)),
-            FunctionValue::Int(ContainerVariant::List(a)),
+            FunctionValue::Int(ContainerVariant::list(a)),
         ],
         FunctionType::Int(ContainerVariantType::Single),
     );
@@ -703,7 +716,7 @@ fn poker_hand(function_name: &str, test: OldTest) -> Result<Test> {
 
     let input_call = WasmFunctionCall::new(
         function_name,
-        vec![FunctionValue::String(ContainerVariant::List(elements))],
+        vec![FunctionValue::String(ContainerVariant::list(elements))],
         FunctionType::Int(ContainerVariantType::Single),
     );
 
@@ -830,6 +843,9 @@ async fn main() -> Result<()> {
     // convert_problem_tests(30, "poker_hand", Box::new(poker_hand)).await?; // change function signature to take vector<string>
     // convert_problem_tests(32, "runes", Box::new(string_int)).await?;
     // convert_problem_tests(33, "star_arrangements", Box::new(star_arrangements)).await?;
+    //
+    // Classic problems can be imported without rewriting them into functions:
+    // convert_problem_tests(id, "main", Box::new(stdio)).await?;
 
     Ok(())
 }
@@ -1,74 +1,212 @@
-use std::time::Duration;
+use std::{future::Future, time::Duration};
 
+use futures::{stream, StreamExt};
+use serde::Deserialize;
 use shared::models::{
     forms::{CustomInputJob, GenerateTestsJob, SubmitJob},
-    runner::{CustomInputResponse, RunnerError, RunnerResponse},
+    runner::{CustomInputResponse, RunnerError, RunnerEvent, RunnerResponse},
     test::Test,
 };
+use tokio::sync::mpsc;
 
-use actix_web::{middleware::Logger, post, web, web::Json, App, HttpServer};
+use actix_web::{
+    delete,
+    error::{ErrorConflict, ErrorNotFound},
+    get,
+    middleware::Logger,
+    post,
+    web::{self, Bytes, Json},
+    App, HttpResponse, HttpServer,
+};
 
 mod runners;
 
 use clap::Parser;
-use runners::{CPlusPlus, Runner};
-
-#[post("/run/c++")]
-async fn cplusplus_run(form: Json<SubmitJob>) -> Json<Result<RunnerResponse, RunnerError>> {
-    let task = tokio::spawn(async {
-        let res = Json(CPlusPlus.run_tests(form.into_inner()).await);
-        log::info!("The task wasn't cancelled!!!!!");
-        res
+use runners::{
+    cache::{self, CacheStats},
+    cancellation::{cancel_job, CancelOnDrop, Job},
+    get_runner, Cancellation, Runner,
+};
+
+fn runner(language: &str) -> actix_web::Result<&'static dyn Runner> {
+    get_runner(language).ok_or_else(|| ErrorNotFound(format!("Unsupported language: {language}")))
+}
+
+/// Jobs sent by the server carry its job id, so they can be cancelled through [`cancel`].
+#[derive(Deserialize)]
+struct JobQuery {
+    id: Option<u64>,
+}
+
+fn register(query: &JobQuery) -> actix_web::Result<Job> {
+    Job::register(query.id).ok_or_else(|| {
+        ErrorConflict(format!(
+            "A job with id {} is already running",
+            query.id.unwrap_or_default()
+        ))
+    })
+}
+
+/// Runs a job on its own task, cancelling it once it runs for longer than `timeout`.
+async fn run_job<T, F, Fut>(job: Job, timeout: Duration, f: F) -> Result<T, RunnerError>
+where
+    F: FnOnce(Cancellation) -> Fut,
+    Fut: Future<Output = Result<T, RunnerError>> + Send + 'static,
+    T: Send + 'static,
+{
+    let cancellation = job.cancellation();
+
+    let future = f(job.cancellation());
+    let mut task = tokio::spawn(async move {
+        let _job = job;
+        future.await
     });
 
-    tokio::select! {
-        _ = tokio::time::sleep(Duration::from_secs(360)) => {
-            return Json(Err(RunnerError::TimeoutError { message: "The tests took too long to run. (process killed)".to_string() }));
+    match tokio::time::timeout(timeout, &mut task).await {
+        Ok(Ok(res)) => res,
+        Ok(Err(e)) => {
+            log::error!("caught error: {e}");
+            Err(RunnerError::InternalServerError {
+                message: "Failed to run job".to_string(),
+            })
         }
-        res = task => {
-            return res.unwrap();
+        Err(_) => {
+            // Stops any running tests, and kills the compiler if the job is still compiling
+            cancellation.cancel();
+            task.abort();
+
+            Err(RunnerError::TimeoutError {
+                message: "The tests took too long to run. (process killed)".to_string(),
+            })
         }
     }
 }
 
-#[post("/generate-tests/c++")]
-async fn cplusplus_generate_tests(
-    form: Json<GenerateTestsJob>,
-) -> Json<Result<Vec<Test>, RunnerError>> {
-    let task = tokio::spawn(async {
-        let res = Json(CPlusPlus.generate_tests(form.into_inner()).await);
-        log::info!("The task wasn't cancelled!!!!!");
-        res
+#[post("/run/{language}")]
+async fn run(
+    language: web::Path<String>,
+    query: web::Query<JobQuery>,
+    form: Json<SubmitJob>,
+) -> actix_web::Result<Json<Result<RunnerResponse, RunnerError>>> {
+    let runner = runner(&language)?;
+    let job = register(&query)?;
+    let form = form.into_inner();
+
+    Ok(Json(
+        run_job(job, Duration::from_secs(360), |cancellation| async move {
+            runner.run_tests(form, None, &cancellation).await
+        })
+        .await,
+    ))
+}
+
+/// Like [`run`], but responds with newline delimited [`RunnerEvent`]s, so results can be shown as
+/// each test finishes.
+#[post("/run/{language}/stream")]
+async fn run_stream(
+    language: web::Path<String>,
+    query: web::Query<JobQuery>,
+    form: Json<SubmitJob>,
+) -> actix_web::Result<HttpResponse> {
+    let runner = runner(&language)?;
+    let job = register(&query)?;
+    let form = form.into_inner();
+    let tests_total = form.tests.len();
+
+    // Stops the job if the client disconnects before it finishes, since dropping the response
+    // body doesn't stop the task running it.
+    let guard = CancelOnDrop(job.cancellation());
+
+    let (progress, receiver) = mpsc::unbounded_channel();
+    let task = tokio::spawn(run_job(
+        job,
+        Duration::from_secs(360),
+        move |cancellation| async move { runner.run_tests(form, Some(progress), &cancellation).await },
+    ));
+
+    // The progress channel closes once the tests finish or are cancelled, after which the result
+    // is sent.
+    let tests = stream::unfold(receiver, |mut receiver| async move {
+        let test = receiver.recv().await?;
+        Some((RunnerEvent::TestCompleted(Box::new(test)), receiver))
     });
 
-    tokio::select! {
-        _ = tokio::time::sleep(Duration::from_secs(120)) => {
-            return Json(Err(RunnerError::TimeoutError { message: "The tests took too long to run. (process killed)".to_string() }));
-        }
-        res = task => {
-            return res.unwrap();
-        }
+    let finished = stream::once(async move {
+        let _guard = guard;
+        let res = task.await.unwrap_or_else(|e| {
+            log::error!("caught error: {e}");
+            Err(RunnerError::InternalServerError {
+                message: "Failed to run tests".to_string(),
+            })
+        });
+
+        RunnerEvent::Finished(res)
+    });
+
+    let events = stream::once(async move { RunnerEvent::Started { tests_total } })
+        .chain(tests)
+        .chain(finished)
+        .map(|event| {
+            let mut line = serde_json::to_vec(&event)?;
+            line.push(b'\n');
+            Ok::<_, serde_json::Error>(Bytes::from(line))
+        });
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(events))
+}
+
+/// Cancels a job started with `?id={id}`, stopping any of its tests that are still running.
+#[delete("/run/{id}")]
+async fn cancel(id: web::Path<u64>) -> actix_web::Result<HttpResponse> {
+    if cancel_job(*id) {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        // The job may not have reached us yet, so it starts cancelled once it does
+        Ok(HttpResponse::Accepted().finish())
     }
 }
 
-#[post("/custom-input/c++")]
-async fn cplusplus_custom_input(
+#[post("/generate-tests/{language}")]
+async fn generate_tests(
+    language: web::Path<String>,
+    query: web::Query<JobQuery>,
+    form: Json<GenerateTestsJob>,
+) -> actix_web::Result<Json<Result<Vec<Test>, RunnerError>>> {
+    let runner = runner(&language)?;
+    let job = register(&query)?;
+    let form = form.into_inner();
+
+    Ok(Json(
+        run_job(job, Duration::from_secs(120), |cancellation| async move {
+            runner.generate_tests(form, &cancellation).await
+        })
+        .await,
+    ))
+}
+
+#[post("/custom-input/{language}")]
+async fn custom_input(
+    language: web::Path<String>,
+    query: web::Query<JobQuery>,
     form: Json<CustomInputJob>,
-) -> Json<Result<CustomInputResponse, RunnerError>> {
-    let task = tokio::spawn(async {
-        let res = Json(CPlusPlus.run_custom_input(form.into_inner()).await);
-        log::info!("The task wasn't cancelled!!!!!");
-        res
-    });
+) -> actix_web::Result<Json<Result<CustomInputResponse, RunnerError>>> {
+    let runner = runner(&language)?;
+    let job = register(&query)?;
+    let form = form.into_inner();
 
-    tokio::select! {
-        _ = tokio::time::sleep(Duration::from_secs(60)) => {
-            return Json(Err(RunnerError::TimeoutError { message: "The tests took too long to run. (process killed)".to_string() }));
-        }
-        res = task => {
-            return res.unwrap();
-        }
-    }
+    Ok(Json(
+        run_job(job, Duration::from_secs(60), |cancellation| async move {
+            runner.run_custom_input(form, &cancellation).await
+        })
+        .await,
+    ))
+}
+
+#[get("/cache")]
+async fn cache_stats() -> Json<CacheStats> {
+    Json(cache::cache().stats())
 }
 
 #[derive(Parser)]
@@ -79,6 +217,19 @@ struct Args {
 
     #[clap(short, long, env, default_value = "127.0.0.1")]
     hostname: String,
+
+    /// Where compiled modules are cached
+    #[clap(long, env, default_value = "/tmp/acm/cache")]
+    cache_directory: String,
+
+    /// Maximum size of the compile cache in megabytes
+    #[clap(long, env, default_value_t = 1024)]
+    cache_size: u64,
+
+    /// How much memory tests may use in total, in megabytes. Each test may use up to 512MB, so
+    /// this limits how many tests run in parallel.
+    #[clap(long, env, default_value_t = 4096)]
+    memory_budget: usize,
 }
 
 #[actix_web::main]
@@ -86,6 +237,9 @@ async fn main() -> std::io::Result<()> {
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("warn"));
     let args = Args::parse();
 
+    cache::init(&args.cache_directory, args.cache_size * 1024 * 1024)?;
+    runners::init_workers(args.memory_budget * 1024 * 1024);
+
     let json_cfg = web::JsonConfig::default()
         // 3mb limit
         .limit(100_000_000);
@@ -94,9 +248,12 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .wrap(Logger::default())
             .app_data(json_cfg.clone())
-            .service(cplusplus_run)
-            .service(cplusplus_generate_tests)
-            .service(cplusplus_custom_input)
+            .service(run)
+            .service(run_stream)
+            .service(cancel)
+            .service(generate_tests)
+            .service(custom_input)
+            .service(cache_stats)
     })
     .bind(&format!("{}:{}", args.hostname, args.port))?
     .run()
@@ -0,0 +1,139 @@
+use async_trait::async_trait;
+use shared::models::runner::{ProblemKind, RunnerError};
+use tokio::{
+    fs::{self, File},
+    io::AsyncWriteExt,
+    process::Command,
+};
+use wasm_memory::{Abi, WasmFunctionCall};
+
+use super::{
+    cache::{cache, compiler_version, CompileCache},
+    cplusplus::parse_clang_error,
+    Runner,
+};
+
+/// Compiles submissions written in C.
+///
+/// Since C has no containers, lists are passed as pointer and length pairs, following the same
+/// conventions as LeetCode, e.g. `int* twoSum(int* nums, int numsSize, int target, int* returnSize)`.
+pub struct C;
+
+const COMPILER: &str = "/opt/wasi-sdk/bin/clang";
+
+const FLAGS: [&str; 11] = [
+    "-O3",
+    "-Wl,--no-entry",
+    "-Wl,--export-all",
+    "-mexec-model=reactor",
+    "-msimd128",
+    "-Wall",
+    "-Wextra",
+    "-Wpedantic",
+    "-Werror=return-type",
+    "-fno-caret-diagnostics",
+    "-std=c17",
+];
+
+/// Flags for [`ProblemKind::Interactive`] problems, where functions which are declared but not
+/// defined are imported from the interactor.
+const INTERACTIVE_FLAGS: [&str; 12] = [
+    "-O3",
+    "-Wl,--no-entry",
+    "-Wl,--export-all",
+    "-Wl,--allow-undefined",
+    "-mexec-model=reactor",
+    "-msimd128",
+    "-Wall",
+    "-Wextra",
+    "-Wpedantic",
+    "-Werror=return-type",
+    "-fno-caret-diagnostics",
+    "-std=c17",
+];
+
+/// Flags for [`ProblemKind::Stdio`] problems, which are compiled to command modules with a `main`.
+const COMMAND_FLAGS: [&str; 8] = [
+    "-O3",
+    "-msimd128",
+    "-Wall",
+    "-Wextra",
+    "-Wpedantic",
+    "-Werror=return-type",
+    "-fno-caret-diagnostics",
+    "-std=c17",
+];
+
+#[async_trait]
+impl Runner for C {
+    fn name(&self) -> &'static str {
+        "c"
+    }
+
+    fn abi(&self) -> Abi {
+        Abi::C
+    }
+
+    async fn compile(
+        &self,
+        prefix: &str,
+        source: &str,
+        kind: ProblemKind,
+        _calls: &[&WasmFunctionCall],
+    ) -> Result<String, RunnerError> {
+        let header = include_str!("default_header_c.h");
+
+        let mut file = String::new();
+        file.push_str(header);
+        file.push_str(source);
+
+        let flags = match kind {
+            ProblemKind::Function => &FLAGS[..],
+            ProblemKind::Stdio => &COMMAND_FLAGS[..],
+            ProblemKind::Interactive => &INTERACTIVE_FLAGS[..],
+        };
+
+        compile_problem(prefix, &file, header.lines().count(), flags).await
+    }
+}
+
+async fn compile_problem(
+    prefix: &str,
+    implementation: &str,
+    header_lines: usize,
+    flags: &[&str],
+) -> Result<String, RunnerError> {
+    let wasm_filename = format!("{prefix}/out.wasm");
+    let implementation_filename = format!("{prefix}/implementation.c");
+
+    let version = compiler_version(COMPILER).await;
+    let key = CompileCache::key(&["c", &version, &flags.join(" "), implementation]);
+    if let Some(wasm_filename) = cache().get(&key, &wasm_filename) {
+        return Ok(wasm_filename);
+    }
+
+    fs::create_dir_all(prefix).await?;
+
+    File::create(&implementation_filename)
+        .await?
+        .write_all(implementation.as_bytes())
+        .await?;
+
+    let output = Command::new(COMPILER)
+        .args(flags)
+        .args([&implementation_filename, "-o", &wasm_filename, "-lm"])
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        fs::remove_file(&wasm_filename).await.ok();
+
+        return Err(parse_clang_error(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+            header_lines,
+        ));
+    }
+
+    cache().insert(&key, &wasm_filename).await
+}
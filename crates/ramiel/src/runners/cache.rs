@@ -0,0 +1,407 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use shared::models::runner::RunnerError;
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+};
+use tokio::{fs, process::Command};
+
+const DEFAULT_DIRECTORY: &str = "/tmp/acm/cache";
+const DEFAULT_MAX_SIZE: u64 = 1 << 30; // 1GB
+
+static CACHE: OnceLock<CompileCache> = OnceLock::new();
+
+/// Sets up the global cache, picking up modules compiled by previous runs in `directory`.
+///
+/// Must be called before the cache is first used, otherwise it has no effect.
+pub fn init(directory: impl Into<PathBuf>, max_size: u64) -> std::io::Result<()> {
+    let cache = CompileCache::new(directory.into(), max_size)?;
+    CACHE.set(cache).ok();
+    Ok(())
+}
+
+/// The cache shared by every runner.
+pub fn cache() -> &'static CompileCache {
+    CACHE.get_or_init(|| {
+        CompileCache::new(DEFAULT_DIRECTORY.into(), DEFAULT_MAX_SIZE)
+            .expect("Failed to create compile cache")
+    })
+}
+
+/// Returns the output of `compiler --version`, which is part of every key so that modules
+/// compiled by an older toolchain aren't used after an upgrade.
+pub async fn compiler_version(compiler: &'static str) -> String {
+    static VERSIONS: OnceLock<Mutex<HashMap<&'static str, String>>> = OnceLock::new();
+    let versions = VERSIONS.get_or_init(Default::default);
+
+    if let Some(version) = versions.lock().unwrap().get(compiler) {
+        return version.clone();
+    }
+
+    match Command::new(compiler).arg("--version").output().await {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout).to_string();
+            versions.lock().unwrap().insert(compiler, version.clone());
+            version
+        }
+        // compiling fails as well in this case, so nothing is cached under this version
+        _ => {
+            log::warn!("failed to find the version of {compiler}");
+            String::new()
+        }
+    }
+}
+
+/// Compiled modules, keyed by a hash of everything that went into compiling them.
+///
+/// Since keys only depend on the compiled source (including any header or generated glue), the
+/// compiler flags and the compiler's version, modules are shared between every user and problem,
+/// so e.g. rejudging a problem or two users submitting the template only compiles once. When the
+/// modules take up more than `max_size` bytes, the least recently used ones are removed.
+///
+/// Jobs never load modules from the cache directly. Instead, modules are linked into the job's
+/// own directory, so evicting a module doesn't affect jobs that are about to load it.
+pub struct CompileCache {
+    directory: PathBuf,
+    max_size: u64,
+    entries: Mutex<Entries>,
+
+    hits: AtomicU64,
+    misses: AtomicU64,
+
+    /// Used to name modules which are copied into the cache, see [`CompileCache::insert`]
+    temporaries: AtomicU64,
+}
+
+#[derive(Default)]
+struct Entries {
+    map: HashMap<String, Entry>,
+
+    /// Total size of every module in bytes
+    size: u64,
+
+    /// Incremented on every access, used to find the least recently used entry
+    clock: u64,
+}
+
+struct Entry {
+    size: u64,
+    last_used: u64,
+}
+
+#[derive(Serialize)]
+pub struct CacheStats {
+    hits: u64,
+    misses: u64,
+    entries: usize,
+    size: u64,
+    max_size: u64,
+}
+
+impl CompileCache {
+    fn new(directory: PathBuf, max_size: u64) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&directory)?;
+
+        // Modules left over from before a restart are treated as least recently used in the order
+        // they were last modified.
+        let mut existing = vec![];
+        for file in std::fs::read_dir(&directory)? {
+            let file = file?;
+            let metadata = file.metadata()?;
+            let name = file.file_name().to_string_lossy().to_string();
+
+            if let Some(key) = name.strip_suffix(".wasm") {
+                existing.push((metadata.modified()?, key.to_string(), metadata.len()));
+            } else if name.ends_with(".tmp") {
+                // interrupted while being copied into the cache
+                std::fs::remove_file(file.path())?;
+            }
+        }
+        existing.sort();
+
+        let mut entries = Entries::default();
+        for (_, key, size) in existing {
+            entries.clock += 1;
+            entries.size += size;
+            entries.map.insert(
+                key,
+                Entry {
+                    size,
+                    last_used: entries.clock,
+                },
+            );
+        }
+
+        Ok(CompileCache {
+            directory,
+            max_size,
+            entries: Mutex::new(entries),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            temporaries: AtomicU64::new(0),
+        })
+    }
+
+    /// Hashes everything that affects the compiled module.
+    pub fn key(parts: &[&str]) -> String {
+        let mut hasher = Sha256::new();
+
+        for part in parts {
+            // length prefixed so that e.g. ["ab", "c"] and ["a", "bc"] differ
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part.as_bytes());
+        }
+
+        format!("{:x}", hasher.finalize())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{key}.wasm"))
+    }
+
+    /// Links the module cached under `key` to `destination`, returning `destination` if there is
+    /// one.
+    ///
+    /// The module is linked while the cache is locked, so it can't be evicted in the meantime.
+    pub fn get(&self, key: &str, destination: &str) -> Option<String> {
+        let path = self.path(key);
+        let mut entries = self.entries.lock().unwrap();
+
+        entries.clock += 1;
+        let clock = entries.clock;
+
+        let found = match entries.map.get_mut(key) {
+            Some(entry) => match link(&path, Path::new(destination)) {
+                Ok(()) => {
+                    entry.last_used = clock;
+                    true
+                }
+                // e.g. removed from outside of the cache, in which case it's compiled again
+                Err(e) => {
+                    log::warn!("failed to link {}: {e}", path.display());
+
+                    let entry = entries.map.remove(key).unwrap();
+                    entries.size -= entry.size;
+                    false
+                }
+            },
+            None => false,
+        };
+
+        if found {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            Some(destination.to_string())
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+
+    /// Adds the module at `wasm_filename` to the cache, returning `wasm_filename`, which is left in
+    /// place for the job that compiled it.
+    pub async fn insert(&self, key: &str, wasm_filename: &str) -> Result<String, RunnerError> {
+        let path = self.path(key);
+
+        // The output directory isn't necessarily on the same filesystem as the cache, in which
+        // case the module is copied under a temporary name first, so that it's never linked while
+        // partially written.
+        if fs::hard_link(wasm_filename, &path).await.is_err() {
+            let temporary = self.directory.join(format!(
+                "{key}.{}.tmp",
+                self.temporaries.fetch_add(1, Ordering::Relaxed)
+            ));
+
+            fs::copy(wasm_filename, &temporary).await?;
+            fs::rename(&temporary, &path).await?;
+        }
+
+        let size = fs::metadata(&path).await?.len();
+
+        let evicted = {
+            let mut entries = self.entries.lock().unwrap();
+
+            entries.clock += 1;
+            let entry = Entry {
+                size,
+                last_used: entries.clock,
+            };
+
+            if let Some(previous) = entries.map.insert(key.to_string(), entry) {
+                entries.size -= previous.size;
+            }
+            entries.size += size;
+
+            let mut evicted = vec![];
+            while entries.size > self.max_size && entries.map.len() > 1 {
+                let oldest = entries
+                    .map
+                    .iter()
+                    .filter(|(k, _)| *k != key)
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(k, _)| k.clone())
+                    .unwrap();
+
+                let entry = entries.map.remove(&oldest).unwrap();
+                entries.size -= entry.size;
+                evicted.push(self.path(&oldest));
+            }
+
+            evicted
+        };
+
+        for path in evicted {
+            remove_module(&path).await;
+        }
+
+        Ok(wasm_filename.to_string())
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.entries.lock().unwrap();
+
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: entries.map.len(),
+            size: entries.size,
+            max_size: self.max_size,
+        }
+    }
+}
+
+/// Hard links `from` to `to`, replacing `to`, or copies it if they are on different filesystems.
+fn link(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    match std::fs::remove_file(to) {
+        Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+
+    if std::fs::hard_link(from, to).is_err() {
+        std::fs::copy(from, to)?;
+    }
+
+    Ok(())
+}
+
+async fn remove_module(path: &Path) {
+    if let Err(e) = fs::remove_file(path).await {
+        log::warn!("failed to evict {}: {e}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A cache in its own directory, with a directory for the jobs using it
+    fn setup(name: &str, max_size: u64) -> (CompileCache, PathBuf) {
+        let directory =
+            std::env::temp_dir().join(format!("acm-cache-{name}-{}", std::process::id()));
+        std::fs::remove_dir_all(&directory).ok();
+
+        let cache = CompileCache::new(directory.join("cache"), max_size).unwrap();
+        let jobs = directory.join("jobs");
+        std::fs::create_dir_all(&jobs).unwrap();
+
+        (cache, jobs)
+    }
+
+    /// Compiles a "module" containing `contents` and adds it to the cache
+    async fn compile(cache: &CompileCache, jobs: &Path, key: &str, contents: &str) -> String {
+        let wasm_filename = jobs.join(format!("{key}-compiled.wasm"));
+        std::fs::write(&wasm_filename, contents).unwrap();
+
+        cache
+            .insert(key, wasm_filename.to_str().unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn get(cache: &CompileCache, jobs: &Path, key: &str) -> Option<String> {
+        let destination = jobs.join(format!("{key}-loaded.wasm"));
+        let path = cache.get(key, destination.to_str().unwrap())?;
+
+        Some(std::fs::read_to_string(path).unwrap())
+    }
+
+    #[tokio::test]
+    async fn hits_and_misses() {
+        let (cache, jobs) = setup("hits", 1024);
+
+        assert_eq!(get(&cache, &jobs, "a"), None);
+        compile(&cache, &jobs, "a", "module a").await;
+        assert_eq!(get(&cache, &jobs, "a"), Some("module a".to_string()));
+        assert_eq!(get(&cache, &jobs, "a"), Some("module a".to_string()));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+        assert_eq!((stats.entries, stats.size), (1, 8));
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        let (cache, jobs) = setup("eviction", 20);
+
+        compile(&cache, &jobs, "a", "module a").await;
+        compile(&cache, &jobs, "b", "module b").await;
+        assert!(get(&cache, &jobs, "a").is_some());
+        compile(&cache, &jobs, "c", "module c").await;
+
+        assert_eq!(get(&cache, &jobs, "b"), None);
+        assert!(!cache.path("b").exists());
+        assert_eq!(get(&cache, &jobs, "a"), Some("module a".to_string()));
+        assert_eq!(get(&cache, &jobs, "c"), Some("module c".to_string()));
+        assert_eq!(cache.stats().size, 16);
+    }
+
+    #[tokio::test]
+    async fn evicted_modules_stay_loadable() {
+        let (cache, jobs) = setup("pinned", 10);
+
+        compile(&cache, &jobs, "a", "module a").await;
+        let destination = jobs.join("job.wasm");
+        let path = cache.get("a", destination.to_str().unwrap()).unwrap();
+
+        // evicts "a" before the job loads it
+        compile(&cache, &jobs, "b", "module b").await;
+        assert!(!cache.path("a").exists());
+
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "module a");
+    }
+
+    #[tokio::test]
+    async fn missing_modules_are_misses() {
+        let (cache, jobs) = setup("missing", 1024);
+
+        compile(&cache, &jobs, "a", "module a").await;
+        std::fs::remove_file(cache.path("a")).unwrap();
+
+        assert_eq!(get(&cache, &jobs, "a"), None);
+        assert_eq!(cache.stats().entries, 0);
+
+        compile(&cache, &jobs, "a", "module a").await;
+        assert_eq!(get(&cache, &jobs, "a"), Some("module a".to_string()));
+    }
+
+    #[test]
+    fn reloads_existing_modules() {
+        let (cache, jobs) = setup("reload", 1024);
+        std::fs::write(cache.path("a"), "module a").unwrap();
+        std::fs::write(cache.directory.join("b.0.tmp"), "partial").unwrap();
+
+        let cache = CompileCache::new(cache.directory.clone(), 1024).unwrap();
+
+        assert_eq!(get(&cache, &jobs, "a"), Some("module a".to_string()));
+        assert!(!cache.directory.join("b.0.tmp").exists());
+    }
+}
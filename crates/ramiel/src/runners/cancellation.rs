@@ -0,0 +1,171 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// How long a cancellation for a job which hasn't been registered yet is kept around.
+const EARLY_CANCELLATION_TTL: Duration = Duration::from_secs(300);
+
+/// Set when a job should stop. Running modules check it every epoch (see [`super::engine`]), and
+/// trap once it is set.
+#[derive(Clone, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Default)]
+struct Jobs {
+    running: HashMap<u64, Cancellation>,
+    /// Jobs cancelled before they were registered, by when they were cancelled. The server can
+    /// cancel a job between deciding to run it and the request reaching us.
+    cancelled_early: HashMap<u64, Instant>,
+}
+
+fn jobs() -> &'static Mutex<Jobs> {
+    static JOBS: OnceLock<Mutex<Jobs>> = OnceLock::new();
+    JOBS.get_or_init(Default::default)
+}
+
+/// Cancels a job once dropped, such as when the client a job's results are streamed to goes away.
+pub struct CancelOnDrop(pub Cancellation);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// A running job, which can be cancelled by its id until it is dropped.
+pub struct Job {
+    id: Option<u64>,
+    cancellation: Cancellation,
+}
+
+impl Job {
+    /// Registers a job, returning `None` if a job with the same id is still running. Jobs without
+    /// an id can only be cancelled through [`Job::cancellation`]. A job whose id was cancelled
+    /// before it was registered starts out cancelled.
+    pub fn register(id: Option<u64>) -> Option<Job> {
+        let cancellation = Cancellation::default();
+
+        if let Some(id) = id {
+            let mut jobs = jobs().lock().unwrap();
+            let Jobs {
+                running,
+                cancelled_early,
+            } = &mut *jobs;
+
+            match running.entry(id) {
+                Entry::Occupied(_) => return None,
+                Entry::Vacant(entry) => {
+                    if cancelled_early.remove(&id).is_some() {
+                        cancellation.cancel();
+                    }
+                    entry.insert(cancellation.clone());
+                }
+            }
+        }
+
+        Some(Job { id, cancellation })
+    }
+
+    pub fn cancellation(&self) -> Cancellation {
+        self.cancellation.clone()
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            jobs().lock().unwrap().running.remove(&id);
+        }
+    }
+}
+
+/// Cancels the job with the given id, returning false if there is no such job running. In that
+/// case the cancellation is remembered for a while, and applies to the job once it is registered.
+pub fn cancel_job(id: u64) -> bool {
+    let mut jobs = jobs().lock().unwrap();
+
+    if let Some(cancellation) = jobs.running.get(&id) {
+        cancellation.cancel();
+        return true;
+    }
+
+    jobs.cancelled_early
+        .retain(|_, cancelled| cancelled.elapsed() < EARLY_CANCELLATION_TTL);
+    jobs.cancelled_early.insert(id, Instant::now());
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancels_jobs_by_id() {
+        let job = Job::register(Some(1)).unwrap();
+
+        assert!(cancel_job(1));
+        assert!(job.cancellation().is_cancelled());
+
+        drop(job);
+        assert!(!cancel_job(1));
+    }
+
+    #[test]
+    fn remembers_cancellations_of_unregistered_jobs() {
+        assert!(!cancel_job(3));
+
+        let job = Job::register(Some(3)).unwrap();
+        assert!(job.cancellation().is_cancelled());
+
+        // The cancellation only applies to the first job registered afterwards
+        drop(job);
+        let job = Job::register(Some(3)).unwrap();
+        assert!(!job.cancellation().is_cancelled());
+    }
+
+    #[test]
+    fn rejects_duplicate_ids() {
+        let job = Job::register(Some(2)).unwrap();
+        assert!(Job::register(Some(2)).is_none());
+
+        // The rejected job must not unregister the running one
+        assert!(cancel_job(2));
+        assert!(job.cancellation().is_cancelled());
+
+        drop(job);
+        let job = Job::register(Some(2)).unwrap();
+        assert!(!job.cancellation().is_cancelled());
+    }
+
+    #[test]
+    fn jobs_without_ids_are_independent() {
+        let first = Job::register(None).unwrap();
+        let second = Job::register(None).unwrap();
+
+        first.cancellation().cancel();
+        assert!(!second.cancellation().is_cancelled());
+    }
+
+    #[test]
+    fn cancels_on_drop() {
+        let job = Job::register(None).unwrap();
+
+        drop(CancelOnDrop(job.cancellation()));
+        assert!(job.cancellation().is_cancelled());
+    }
+}
@@ -1,127 +1,90 @@
 use async_trait::async_trait;
-use shared::models::{
-    forms::{CustomInputJob, GenerateTestsJob, SubmitJob},
-    runner::{CustomInputResponse, Diagnostic, DiagnosticType, RunnerError, RunnerResponse},
-    test::Test,
-};
-use std::{collections::HashSet, iter::Peekable, path::Path, str::Chars};
+use shared::models::runner::{Diagnostic, DiagnosticType, ProblemKind, RunnerError};
+use std::{iter::Peekable, str::Chars};
 use tokio::{
     fs::{self, File},
-    io::{AsyncReadExt, AsyncWriteExt},
+    io::AsyncWriteExt,
     process::Command,
 };
+use wasm_memory::WasmFunctionCall;
 
-use super::{run_command, run_test_timed, Runner, TestResults};
+use super::{
+    cache::{cache, compiler_version, CompileCache},
+    Runner,
+};
 
 pub struct CPlusPlus;
 
+const COMPILER: &str = "/opt/wasi-sdk/bin/clang++";
+
+const FLAGS: [&str; 13] = [
+    "-O3",
+    "-Wl,--no-entry",
+    "-Wl,--demangle",
+    "-Wl,--export-all",
+    "-mexec-model=reactor",
+    "-msimd128",
+    "-Wall",
+    "-Wextra",
+    "-Wpedantic",
+    "-Werror=return-type",
+    "-fno-caret-diagnostics",
+    "-fno-exceptions",
+    "-std=c++20",
+];
+
+/// Flags for [`ProblemKind::Interactive`] problems, where functions which are declared but not
+/// defined are imported from the interactor.
+const INTERACTIVE_FLAGS: [&str; 14] = [
+    "-O3",
+    "-Wl,--no-entry",
+    "-Wl,--demangle",
+    "-Wl,--export-all",
+    "-Wl,--allow-undefined",
+    "-mexec-model=reactor",
+    "-msimd128",
+    "-Wall",
+    "-Wextra",
+    "-Wpedantic",
+    "-Werror=return-type",
+    "-fno-caret-diagnostics",
+    "-fno-exceptions",
+    "-std=c++20",
+];
+
+/// Flags for [`ProblemKind::Stdio`] problems, which are compiled to command modules with a `main`.
+const COMMAND_FLAGS: [&str; 9] = [
+    "-O3",
+    "-msimd128",
+    "-Wall",
+    "-Wextra",
+    "-Wpedantic",
+    "-Werror=return-type",
+    "-fno-caret-diagnostics",
+    "-fno-exceptions",
+    "-std=c++20",
+];
+
 #[async_trait]
 impl Runner for CPlusPlus {
-    async fn run_tests(&self, form: SubmitJob) -> Result<RunnerResponse, RunnerError> {
-        let prefix = format!("/tmp/acm/submissions/{}/{}", form.user_id, form.problem_id);
-
-        let implementation = process_file(&form.implementation);
-
-        let command = compile_problem(&prefix, &implementation).await?;
-
-        // MAYBE WHEN WE HAVE MORE RAM
-        // let tests = join_all(
-        //     form.tests
-        //         .into_iter()
-        //         .map(|test| async { run_test_timed(&command, test).await }),
-        // )
-        // .await;
-
-        // SAD SOLUTION FOR NOW
-        let mut tests = vec![];
-        for mut test in form.tests {
-            test.adjust_runtime(form.runtime_multiplier);
-            let (test, _) = run_test_timed(&command, test, 50).await?;
-            tests.push(test);
-        }
-
-        let mut total_runtime = 0;
-
-        let mut test_results = TestResults::new();
-
-        for test in tests {
-            total_runtime += test.fuel;
-            test_results.insert(test);
-        }
-
-        test_results.runtime = total_runtime;
-
-        Ok(test_results.into())
+    fn name(&self) -> &'static str {
+        "c++"
     }
 
-    async fn generate_tests(&self, form: GenerateTestsJob) -> Result<Vec<Test>, RunnerError> {
-        let prefix = format!("/tmp/acm/problem_editor/{}", form.user_id);
-
-        // TODO actually get unique function names from tests
-        let reference = process_file(&form.reference);
-        let command = compile_problem(&prefix, &reference).await?;
-
-        let mut outputs = Vec::new();
-        let mut i = 0;
-        for input in form.inputs.into_iter() {
-            let (output, _, fuel) = run_command(&command, input.clone(), None).await?;
-            outputs.push(Test {
-                id: 0,
-                index: i,
-                max_fuel: Some(fuel as i64),
-                input,
-                expected_output: output,
-            });
-
-            i += 1;
-        }
-
-        Ok(outputs)
-    }
-
-    async fn run_custom_input(
+    async fn compile(
         &self,
-        form: CustomInputJob,
-    ) -> Result<CustomInputResponse, RunnerError> {
-        let reference_prefix = format!(
-            "/tmp/acm/custom_input/{}/{}/reference",
-            form.user_id, form.problem_id
-        );
-        let implementation_prefix = format!(
-            "/tmp/acm/custom_input/{}/{}/implementation",
-            form.user_id, form.problem_id
-        );
-
-        let reference = process_file(&form.reference);
-        let implementation = process_file(&form.implementation);
-
-        // println!("REFERENCE: {reference}");
-        // println!("IMPLEMENTATION: {implementation}");
-
-        let reference_command = compile_problem(&reference_prefix, &reference).await?;
-        let implementation_command =
-            compile_problem(&implementation_prefix, &implementation).await?;
-
-        let (expected_output, _, fuel) =
-            run_command(&reference_command, form.input.clone(), None).await?;
-
-        let mut test = Test {
-            id: 0,
-            index: 0,
-            input: form.input,
-            expected_output,
-            max_fuel: Some(fuel as i64),
+        prefix: &str,
+        source: &str,
+        kind: ProblemKind,
+        _calls: &[&WasmFunctionCall],
+    ) -> Result<String, RunnerError> {
+        let flags = match kind {
+            ProblemKind::Function => &FLAGS[..],
+            ProblemKind::Stdio => &COMMAND_FLAGS[..],
+            ProblemKind::Interactive => &INTERACTIVE_FLAGS[..],
         };
 
-        test.adjust_runtime(form.runtime_multiplier);
-
-        // we add a lot of padding so they can potentially print a lot
-        let (test_result, stdout) = run_test_timed(&implementation_command, test, 500).await?;
-
-        Ok(CustomInputResponse {
-            result: test_result,
-            output: stdout,
-        })
+        compile_problem(prefix, &process_file(source), flags).await
     }
 }
 
@@ -137,26 +100,18 @@ fn process_file(file: &str) -> String {
     new_file
 }
 
-async fn compile_problem(prefix: &str, implementation: &str) -> Result<String, RunnerError> {
+async fn compile_problem(
+    prefix: &str,
+    implementation: &str,
+    flags: &[&str],
+) -> Result<String, RunnerError> {
     let wasm_filename = format!("{prefix}/out.wasm");
     let implementation_filename = format!("{prefix}/implementation.cpp");
 
-    // If the previous submission was successful and unchanged, go ahead without compiling.
-    if Path::new(&wasm_filename).exists() {
-        if let Some(mut file) = File::open(&implementation_filename).await.ok() {
-            let mut data: Vec<u8> = vec![];
-            file.read_buf(&mut data).await?;
-
-            // Using md5 here is fine since everything is scoped to the username of the account. The
-            // worst anyone could do is trick the server into not compiling their code. Main benefit of
-            // using it over something like sha256 is speed and hash length.
-            let old_hash = md5::compute(data);
-            let new_hash = md5::compute(implementation.as_bytes());
-
-            if old_hash == new_hash {
-                return Ok(wasm_filename);
-            }
-        }
+    let version = compiler_version(COMPILER).await;
+    let key = CompileCache::key(&["c++", &version, &flags.join(" "), implementation]);
+    if let Some(wasm_filename) = cache().get(&key, &wasm_filename) {
+        return Ok(wasm_filename);
     }
 
     fs::create_dir_all(prefix).await?;
@@ -166,37 +121,23 @@ async fn compile_problem(prefix: &str, implementation: &str) -> Result<String, R
         .write_all(implementation.as_bytes())
         .await?;
 
-    let output = Command::new("/opt/wasi-sdk/bin/clang++")
-        .args([
-            "-O3",
-            "-Wl,--no-entry",
-            "-Wl,--demangle",
-            "-Wl,--export-all",
-            "-mexec-model=reactor",
-            "-msimd128",
-            "-Wall",
-            "-Wextra",
-            "-Wpedantic",
-            "-Werror=return-type",
-            "-fno-caret-diagnostics",
-            "-fno-exceptions",
-            "-std=c++20",
-            &implementation_filename,
-            "-o",
-            &wasm_filename,
-        ])
+    let output = Command::new(COMPILER)
+        .args(flags)
+        .args([&implementation_filename, "-o", &wasm_filename])
+        .kill_on_drop(true)
         .output()
         .await?;
 
     if !output.status.success() {
         fs::remove_file(&wasm_filename).await.ok();
 
-        return Err(parse_cplusplus_error(
+        return Err(parse_clang_error(
             String::from_utf8_lossy(&output.stderr).to_string(),
+            include_str!("default_header.h").lines().count(),
         ));
     }
 
-    Ok(wasm_filename)
+    cache().insert(&key, &wasm_filename).await
 }
 
 fn parse_number(iter: &mut Peekable<Chars>) -> usize {
@@ -213,12 +154,12 @@ fn parse_number(iter: &mut Peekable<Chars>) -> usize {
     num
 }
 
-/// Returns `None` if the diagnostic is not in the "implementation.cpp" file
+/// Returns `None` if the diagnostic is not in the "implementation.cpp" (or "implementation.c") file
 ///
 /// Example format (except we don't actually do the brackets thus far):
 /// /tmp/acm/submissions/1/41/implementation.cpp:50:12:{50:16-50:17}: error: no viable conversion from 'int' to 'std::string' (aka 'basic_string<char, char_traits<char>, allocator<char>>')
-fn diagnostic_from_str(s: &str) -> Result<Option<Diagnostic>, RunnerError> {
-    if s.find(".cpp").is_none() || !s.starts_with("/") {
+fn diagnostic_from_str(s: &str, header_lines: usize) -> Result<Option<Diagnostic>, RunnerError> {
+    if s.find("/implementation.c").is_none() || !s.starts_with("/") {
         return Ok(None);
     }
 
@@ -231,13 +172,13 @@ fn diagnostic_from_str(s: &str) -> Result<Option<Diagnostic>, RunnerError> {
         }
     }
 
-    // this number comes from the length of the "default_header.h" file
+    // the header is prepended to the file, so we offset by its length
     let mut line = parse_number(&mut iter);
-    if line < 39 {
+    if line < header_lines {
         return Ok(None);
     }
 
-    line -= 39;
+    line -= header_lines;
 
     let col = parse_number(&mut iter);
 
@@ -271,13 +212,15 @@ fn diagnostic_from_str(s: &str) -> Result<Option<Diagnostic>, RunnerError> {
     }))
 }
 
-fn parse_cplusplus_error(err: String) -> RunnerError {
+/// Parses clang's diagnostics, where `header_lines` is the length of the header prepended to the
+/// user's code.
+pub(super) fn parse_clang_error(err: String, header_lines: usize) -> RunnerError {
     let mut diagnostics = vec![];
 
     println!("{err}");
 
     for line in err.lines() {
-        match diagnostic_from_str(&line) {
+        match diagnostic_from_str(&line, header_lines) {
             Ok(Some(diagnostic)) => diagnostics.push(diagnostic),
             Ok(None) => {}
             Err(e) => {
@@ -0,0 +1,439 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use shared::models::runner::{ProblemKind, RunnerError};
+use wasm_memory::{function_name, Abi, WasmFunctionCall};
+use wasmtime::{
+    AsContext, AsContextMut, Caller, ExternType, Func, FuncType, Instance, InstancePre, Linker,
+    Module, Store, ValType,
+};
+
+use super::{linker, CPlusPlus, Cancellation, MyState, Program, Runner};
+
+/// The state of the interactor while a test of a [`ProblemKind::Interactive`] problem runs.
+///
+/// Interactive submissions declare functions without defining them, e.g.
+///
+/// ```cpp
+/// int query(int guess);
+/// ```
+///
+/// which are imported by the submission's module, and provided by the interactor, an officer
+/// written C++ module instantiated in the same store. Before each test, the interactor's
+/// `void setup(...)` is called with the arguments of the test, and the submission's function is
+/// then called without any arguments, so the arguments stay hidden from it. Functions shared
+/// between the two may only take and return numbers. Once the submission returns, the
+/// interactor's `bool accept()` is called if it defines one, and the test fails if it returns
+/// false, e.g. when the submission answered without asking enough queries to be sure.
+///
+/// The interactor runs on its own fuel, so a slow interactor can't make the submission exceed its
+/// fuel limit, and the memory it uses isn't counted as the submission's.
+pub struct Interaction {
+    instance: Instance,
+    functions: HashMap<String, Func>,
+    queries: u64,
+    /// The fuel the interactor may still consume during this test
+    fuel_left: u64,
+    /// The fuel the interactor consumed so far
+    fuel: u64,
+    /// Why the interactor crashed, if it did
+    error: Option<String>,
+}
+
+/// Compiles the interactor of a [`ProblemKind::Interactive`] problem inside of `prefix`.
+pub async fn load(
+    prefix: &str,
+    kind: ProblemKind,
+    interactor: Option<&str>,
+    cancellation: &Cancellation,
+) -> Result<Option<Program>, RunnerError> {
+    if kind != ProblemKind::Interactive {
+        return Ok(None);
+    }
+
+    let Some(interactor) = interactor else {
+        return Err(RunnerError::InternalServerError {
+            message: "This interactive problem has no interactor".to_string(),
+        });
+    };
+
+    let command = CPlusPlus
+        .compile(prefix, interactor, ProblemKind::Function, &[])
+        .await?;
+
+    Ok(Some(
+        CPlusPlus
+            .load(&command, ProblemKind::Function, cancellation)
+            .await?,
+    ))
+}
+
+/// Interactors are written by officers, so their errors shouldn't be reported as if they were in
+/// the submission.
+pub fn hide_interactor_diagnostics(e: RunnerError) -> RunnerError {
+    match e {
+        RunnerError::CompilationError { .. } => RunnerError::InternalServerError {
+            message: "The interactor of this problem failed to compile".to_string(),
+        },
+        e => e,
+    }
+}
+
+/// Links the functions `module` imports from `env` to the interactor. Each call is counted as a
+/// query, and runs as the interactor, so only the submission is timed and measured.
+pub(super) fn link(module: &Module) -> anyhow::Result<Linker<MyState>> {
+    let mut linker = linker().clone();
+
+    for import in module.imports().filter(|import| import.module() == "env") {
+        let ExternType::Func(ty) = import.ty() else {
+            continue;
+        };
+
+        let name = import.name().to_string();
+        linker.func_new(
+            "env",
+            import.name(),
+            ty.clone(),
+            move |mut caller, params, results| {
+                let func = resolve(&mut caller, &name, &ty)?;
+                as_interactor(&mut caller, |caller| func.call(caller, params, results))?;
+
+                if let Some(interaction) = caller.data_mut().interaction.as_mut() {
+                    interaction.queries += 1;
+                }
+
+                Ok(())
+            },
+        )?;
+    }
+
+    Ok(linker)
+}
+
+/// Finds the interactor's definition of `name`, which must have the wasm signature `ty` the
+/// submission imports it with. C++ names are mangled, so the interactor may define the function
+/// with a different signature or language linkage, in which case it won't be found.
+fn resolve(caller: &mut Caller<'_, MyState>, name: &str, ty: &FuncType) -> anyhow::Result<Func> {
+    let interaction = caller
+        .data()
+        .interaction
+        .as_ref()
+        .ok_or_else(|| anyhow!("The interactor is not running"))?;
+
+    if let Some(func) = interaction.functions.get(name) {
+        return Ok(*func);
+    }
+
+    let instance = interaction.instance;
+    let function = function_name(name);
+    let func = find_export(&mut *caller, instance, &function, ty).ok_or_else(|| {
+        anyhow!("The interactor does not define `{function}` with the declared signature")
+    })?;
+
+    if let Some(interaction) = caller.data_mut().interaction.as_mut() {
+        interaction.functions.insert(name.to_string(), func);
+    }
+
+    Ok(func)
+}
+
+/// Finds the export of `instance` called `function` once demangled, with the wasm signature `ty`.
+fn find_export(
+    mut store: impl AsContextMut,
+    instance: Instance,
+    function: &str,
+    ty: &FuncType,
+) -> Option<Func> {
+    let candidates = instance
+        .exports(&mut store)
+        .filter(|export| function_name(export.name()) == function)
+        .filter_map(|export| export.into_func())
+        .collect::<Vec<_>>();
+
+    candidates.into_iter().find(|func| func.ty(&store) == *ty)
+}
+
+/// Runs `f` as the interactor, on its own fuel with the submission's fuel put aside in the
+/// meantime, so that the interactor neither uses up nor counts towards the submission's fuel. The
+/// memory it grows isn't counted as the submission's either. If `f` fails, the error is kept,
+/// since it reaches the submission as a trap of its own.
+fn as_interactor<C, T>(
+    store: &mut C,
+    f: impl FnOnce(&mut C) -> anyhow::Result<T>,
+) -> anyhow::Result<T>
+where
+    C: AsContextMut<Data = MyState>,
+{
+    let fuel_left = store
+        .as_context()
+        .data()
+        .interaction
+        .as_ref()
+        .map(|interaction| interaction.fuel_left)
+        .ok_or_else(|| anyhow!("The interactor is not running"))?;
+
+    let submission_fuel = swap_fuel(&mut *store, fuel_left)?;
+    store.as_context_mut().data_mut().limits.measure(false);
+    let result = f(store);
+    store.as_context_mut().data_mut().limits.measure(true);
+    let fuel_left = swap_fuel(&mut *store, submission_fuel)?;
+
+    if let Some(interaction) = store.as_context_mut().data_mut().interaction.as_mut() {
+        interaction.fuel += interaction.fuel_left - fuel_left;
+        interaction.fuel_left = fuel_left;
+
+        if let Err(e) = &result {
+            interaction
+                .error
+                .get_or_insert_with(|| e.root_cause().to_string());
+        }
+    }
+
+    result
+}
+
+/// Replaces the fuel remaining in `store` with `fuel`, returning what was left.
+fn swap_fuel(mut store: impl AsContextMut, fuel: u64) -> anyhow::Result<u64> {
+    let mut store = store.as_context_mut();
+
+    // Running out of fuel may overdraw it, in which case nothing is left
+    let remaining = store.consume_fuel(0).unwrap_or(0);
+    if remaining > 0 {
+        store.consume_fuel(remaining)?;
+    }
+    store.add_fuel(fuel)?;
+
+    Ok(remaining)
+}
+
+/// Instantiates the interactor and passes it the arguments of `input`, returning the call the
+/// submission should receive instead. The interactor may consume up to `fuel` during the test.
+pub(super) fn start(
+    store: &mut Store<MyState>,
+    interactor: &InstancePre<MyState>,
+    input: WasmFunctionCall,
+    fuel: u64,
+) -> Result<WasmFunctionCall, RunnerError> {
+    store.data_mut().limits.measure(false);
+    let instance = interactor.instantiate(&mut *store);
+    store.data_mut().limits.measure(true);
+
+    let instance = instance.map_err(|e| {
+        log::error!("{e:?}");
+        RunnerError::InternalServerError {
+            message: format!("Failed to create interactor:\n{}", e.root_cause()),
+        }
+    })?;
+
+    store.data_mut().interaction = Some(Interaction {
+        instance,
+        functions: HashMap::new(),
+        queries: 0,
+        fuel_left: fuel,
+        fuel: 0,
+        error: None,
+    });
+
+    let init = WasmFunctionCall::new("setup", input.arguments, input.return_type.clone());
+    as_interactor(store, |store| {
+        init.call_void(store, &instance, Abi::CPlusPlus)
+    })
+    .map_err(|e| crashed(e.root_cause()))?;
+
+    Ok(WasmFunctionCall::new(
+        &input.name,
+        vec![],
+        input.return_type,
+    ))
+}
+
+/// Asks the interactor whether to accept the submission's answer, returning `None` if it doesn't
+/// define `bool accept()`.
+pub(super) fn accepted(store: &mut Store<MyState>) -> Result<Option<bool>, RunnerError> {
+    let Some(instance) = store
+        .data()
+        .interaction
+        .as_ref()
+        .map(|interaction| interaction.instance)
+    else {
+        return Ok(None);
+    };
+
+    let ty = FuncType::new([], [ValType::I32]);
+    let Some(accept) = find_export(&mut *store, instance, "accept", &ty) else {
+        return Ok(None);
+    };
+
+    let accepted = as_interactor(store, |store| {
+        accept.typed::<(), i32>(&*store)?.call(store, ())
+    })
+    .map_err(|e| crashed(e.root_cause()))?;
+
+    Ok(Some(accepted != 0))
+}
+
+/// Returns why the interactor crashed while the submission called it, if it did. The
+/// submission's call fails as well, but the interactor is to blame.
+pub(super) fn error(store: &Store<MyState>) -> Option<RunnerError> {
+    let interaction = store.data().interaction.as_ref()?;
+    interaction.error.as_ref().map(crashed)
+}
+
+fn crashed(e: impl std::fmt::Display) -> RunnerError {
+    RunnerError::InternalServerError {
+        message: format!("The interactor crashed:\n{e}"),
+    }
+}
+
+/// How many times the submission called the interactor.
+pub(super) fn queries(store: &Store<MyState>) -> Option<u64> {
+    store
+        .data()
+        .interaction
+        .as_ref()
+        .map(|interaction| interaction.queries)
+}
+
+/// How much fuel the interactor consumed, which doesn't count towards the submission's fuel.
+pub(super) fn fuel(store: &Store<MyState>) -> Option<u64> {
+    store
+        .data()
+        .interaction
+        .as_ref()
+        .map(|interaction| interaction.fuel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runners::{create_store, engine};
+    use wasmtime_wasi::sync::WasiCtxBuilder;
+
+    /// Links `submission` to a running `interactor`, where the submission may consume `fuel` and
+    /// the interactor `interactor_fuel`.
+    fn interact(
+        interactor: &str,
+        submission: &str,
+        fuel: i64,
+        interactor_fuel: u64,
+    ) -> anyhow::Result<(Store<MyState>, Instance)> {
+        let interactor = Module::new(engine(), interactor)?;
+        let submission = Module::new(engine(), submission)?;
+
+        let mut store = create_store(
+            WasiCtxBuilder::new().build(),
+            Some(fuel),
+            Cancellation::default(),
+        );
+        let instance = Instance::new(&mut store, &interactor, &[])?;
+        store.data_mut().interaction = Some(Interaction {
+            instance,
+            functions: HashMap::new(),
+            queries: 0,
+            fuel_left: interactor_fuel,
+            fuel: 0,
+            error: None,
+        });
+
+        let submission = link(&submission)?.instantiate(&mut store, &submission)?;
+
+        Ok((store, submission))
+    }
+
+    #[test]
+    fn resolves_overloads_by_signature() -> anyhow::Result<()> {
+        let (mut store, submission) = interact(
+            r#"(module
+                (func (export "_Z5queryx") (param i64) (result i64) i64.const 1)
+                (func (export "_Z5queryi") (param i32) (result i32) i32.const 2))"#,
+            r#"(module
+                (import "env" "_Z5queryi" (func $query (param i32) (result i32)))
+                (func (export "solve") (result i32) i32.const 0 call $query))"#,
+            1000,
+            1000,
+        )?;
+        let solve = submission.get_typed_func::<(), i32>(&mut store, "solve")?;
+
+        assert_eq!(solve.call(&mut store, ())?, 2);
+        assert_eq!(queries(&store), Some(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn interactor_runs_on_its_own_fuel() -> anyhow::Result<()> {
+        // counts to the argument, which costs far more fuel than the submission has
+        let interactor = r#"(module
+            (func (export "_Z5queryi") (param i32) (result i32) (local i32)
+                (loop $count
+                    (local.set 1 (i32.add (local.get 1) (i32.const 1)))
+                    (br_if $count (i32.lt_u (local.get 1) (local.get 0))))
+                local.get 1))"#;
+        let submission = r#"(module
+            (import "env" "_Z5queryi" (func $query (param i32) (result i32)))
+            (func (export "solve") (result i32) i32.const 10000 call $query))"#;
+
+        let (mut store, instance) = interact(interactor, submission, 1000, 1_000_000)?;
+        let solve = instance.get_typed_func::<(), i32>(&mut store, "solve")?;
+
+        assert_eq!(solve.call(&mut store, ())?, 10000);
+        assert!(fuel(&store).unwrap() > 10000);
+        assert!(store.consume_fuel(0)? > 990);
+
+        // the interactor can't fall back on the submission's fuel either
+        let (mut store, instance) = interact(interactor, submission, 1_000_000, 1000)?;
+        let solve = instance.get_typed_func::<(), i32>(&mut store, "solve")?;
+
+        assert!(solve.call(&mut store, ()).is_err());
+        assert_eq!(fuel(&store), Some(1000));
+
+        Ok(())
+    }
+
+    #[test]
+    fn interactor_crashes_are_internal_errors() -> anyhow::Result<()> {
+        let (mut store, instance) = interact(
+            r#"(module (func (export "_Z5queryi") (param i32) (result i32) unreachable))"#,
+            r#"(module
+                (import "env" "_Z5queryi" (func $query (param i32) (result i32)))
+                (func (export "solve") (result i32) i32.const 0 call $query))"#,
+            1000,
+            1000,
+        )?;
+        let solve = instance.get_typed_func::<(), i32>(&mut store, "solve")?;
+
+        assert!(solve.call(&mut store, ()).is_err());
+        assert!(matches!(
+            error(&store),
+            Some(RunnerError::InternalServerError { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn interactor_judges_answers_through_accept() -> anyhow::Result<()> {
+        let submission = r#"(module (func (export "solve") (result i32) i32.const 0))"#;
+
+        let (mut store, _) = interact(
+            r#"(module (func (export "_Z6acceptv") (result i32) i32.const 0))"#,
+            submission,
+            1000,
+            1000,
+        )?;
+        assert_eq!(accepted(&mut store)?, Some(false));
+
+        let (mut store, _) = interact(
+            r#"(module (func (export "_Z6acceptv") (result i32) i32.const 1))"#,
+            submission,
+            1000,
+            1000,
+        )?;
+        assert_eq!(accepted(&mut store)?, Some(true));
+
+        let (mut store, _) = interact("(module)", submission, 1000, 1000)?;
+        assert_eq!(accepted(&mut store)?, None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use shared::models::{
+    runner::{OutputNormalization, ProblemKind, RunnerError},
+    test::TestResult,
+};
+use wasm_memory::{
+    ComparisonMode, ContainerVariant, ContainerVariantType, FloatTolerance, FunctionType,
+    FunctionValue, WasmFunctionCall,
+};
+
+use super::{CPlusPlus, Cancellation, Execution, Program, Runner};
+
+/// Decides whether the output of a test is correct.
+///
+/// By default the output must equal the expected output, with floating point values compared
+/// using the problem's [`FloatTolerance`], and containers reordered as allowed by its
+/// [`ComparisonMode`]. Problems with many valid answers can
+/// instead have a checker, which is a C++ function taking the arguments of the test followed by the
+/// expected output and the submission's output, e.g.
+///
+/// ```cpp
+/// std::string check(int n, vector<vector<int>> edges, vector<int> expected, vector<int> output);
+/// ```
+///
+/// The output is accepted if the checker returns an empty string, and is otherwise rejected with
+/// the returned message. Checkers of [`ProblemKind::Stdio`] problems take the input, expected
+/// output and output as strings.
+#[derive(Default)]
+pub struct Judge {
+    normalization: OutputNormalization,
+    tolerance: FloatTolerance,
+    comparison: ComparisonMode,
+    checker: Option<Program>,
+}
+
+impl Judge {
+    /// Compiles the checker inside of `prefix`, if there is one.
+    pub async fn load(
+        prefix: &str,
+        normalization: OutputNormalization,
+        tolerance: FloatTolerance,
+        comparison: ComparisonMode,
+        checker: Option<&str>,
+        cancellation: &Cancellation,
+    ) -> Result<Judge, RunnerError> {
+        let checker = match checker {
+            Some(checker) => {
+                let command = CPlusPlus
+                    .compile(prefix, checker, ProblemKind::Function, &[])
+                    .await?;
+                Some(
+                    CPlusPlus
+                        .load(&command, ProblemKind::Function, cancellation)
+                        .await?,
+                )
+            }
+            None => None,
+        };
+
+        Ok(Judge {
+            normalization,
+            tolerance,
+            comparison,
+            checker,
+        })
+    }
+
+    /// Decides whether the output of `test`, which was produced by a program of type `kind`, is
+    /// correct. Rejections by the checker are stored in the test's error.
+    pub async fn judge(&self, test: &mut TestResult, kind: ProblemKind) -> Result<(), RunnerError> {
+        let (Some(checker), Some(output)) = (&self.checker, &test.output) else {
+            match (kind, &test.output) {
+                (ProblemKind::Stdio, _) => test.success = stdout_matches(test, self.normalization),
+                (_, Some(output)) => {
+                    let expected = test.expected_output.clone().sorted(self.comparison);
+                    let output = output.clone().sorted(self.comparison);
+                    test.success = expected.matches(&output, &self.tolerance)
+                }
+                (_, None) => {}
+            }
+
+            return Ok(());
+        };
+
+        let mut arguments = test.input.arguments.clone();
+        arguments.push(test.expected_output.clone());
+        arguments.push(output.clone());
+
+        let call = WasmFunctionCall::new(
+            "check",
+            arguments,
+            FunctionType::String(ContainerVariantType::Single),
+        );
+
+        apply_verdict(test, CPlusPlus.execute(checker, call, None).await)
+    }
+}
+
+/// Accepts or rejects `test` by the message the checker returned.
+fn apply_verdict(
+    test: &mut TestResult,
+    verdict: Result<Execution, RunnerError>,
+) -> Result<(), RunnerError> {
+    match verdict {
+        Ok(Execution {
+            result: FunctionValue::String(ContainerVariant::Single(message)),
+            ..
+        }) => {
+            test.success = message.is_empty();
+            if !message.is_empty() {
+                test.error = Some(message);
+            }
+        }
+        Ok(_) => unreachable!("checkers always return a string"),
+        Err(RunnerError::RuntimeError { message }) => {
+            test.success = false;
+            test.error = Some(format!("The checker crashed:\n{message}"));
+        }
+        Err(e) => return Err(e),
+    }
+
+    Ok(())
+}
+
+/// Checkers are written by officers, so their errors shouldn't be reported as if they were in the
+/// submission.
+pub fn hide_checker_diagnostics(e: RunnerError) -> RunnerError {
+    match e {
+        RunnerError::CompilationError { .. } => RunnerError::InternalServerError {
+            message: "The checker of this problem failed to compile".to_string(),
+        },
+        e => e,
+    }
+}
+
+/// Compares the output of a [`ProblemKind::Stdio`] test to its expected output.
+fn stdout_matches(test: &TestResult, normalization: OutputNormalization) -> bool {
+    match (&test.expected_output, &test.output) {
+        (
+            FunctionValue::String(ContainerVariant::Single(expected)),
+            Some(FunctionValue::String(ContainerVariant::Single(output))),
+        ) => normalization.matches(expected, output),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runners::MemoryUsage;
+
+    fn test_result(expected_output: FunctionValue, output: FunctionValue) -> TestResult {
+        TestResult {
+            id: 0,
+            index: 0,
+            success: false,
+            input: WasmFunctionCall::new("solve", vec![], expected_output.function_type()),
+            expected_output,
+            output: Some(output),
+            fuel: 0,
+            error: None,
+            max_fuel: None,
+            queries: None,
+            interactor_fuel: None,
+            memory: None,
+            heap: None,
+            hidden: false,
+        }
+    }
+
+    fn checker_returned(message: &str) -> Result<Execution, RunnerError> {
+        Ok(Execution {
+            result: FunctionValue::String(ContainerVariant::Single(message.to_string())),
+            output: String::new(),
+            fuel: 0,
+            queries: None,
+            interactor_fuel: None,
+            accepted: None,
+            memory: MemoryUsage::default(),
+        })
+    }
+
+    fn ints(values: &[i32]) -> FunctionValue {
+        FunctionValue::Int(ContainerVariant::list(values.to_vec()))
+    }
+
+    #[test]
+    fn checker_rejections_are_reported() {
+        let mut test = test_result(ints(&[1, 2]), ints(&[2, 1]));
+        apply_verdict(&mut test, checker_returned("2 must come after 1")).unwrap();
+
+        assert!(!test.success);
+        assert_eq!(test.error.as_deref(), Some("2 must come after 1"));
+
+        let mut test = test_result(ints(&[1, 2]), ints(&[2, 1]));
+        apply_verdict(&mut test, checker_returned("")).unwrap();
+
+        assert!(test.success);
+        assert_eq!(test.error, None);
+    }
+
+    #[test]
+    fn checker_crashes_reject() {
+        let mut test = test_result(ints(&[1]), ints(&[1]));
+        let crash = Err(RunnerError::RuntimeError {
+            message: "unreachable".to_string(),
+        });
+        apply_verdict(&mut test, crash).unwrap();
+
+        assert!(!test.success);
+        assert!(test.error.unwrap().contains("checker crashed"));
+
+        let mut test = test_result(ints(&[1]), ints(&[1]));
+        let result = apply_verdict(&mut test, Err(RunnerError::Cancelled));
+        assert!(matches!(result, Err(RunnerError::Cancelled)));
+    }
+
+    #[tokio::test]
+    async fn tolerance_boundary() {
+        let judge = Judge {
+            tolerance: FloatTolerance {
+                absolute: 0.5,
+                relative: 0.0,
+            },
+            ..Default::default()
+        };
+        let double = |value| FunctionValue::Double(ContainerVariant::Single(value));
+
+        let mut test = test_result(double(1.0), double(1.5));
+        judge.judge(&mut test, ProblemKind::Function).await.unwrap();
+        assert!(test.success);
+
+        let mut test = test_result(double(1.0), double(1.5000001));
+        judge.judge(&mut test, ProblemKind::Function).await.unwrap();
+        assert!(!test.success);
+    }
+
+    #[tokio::test]
+    async fn unordered_comparison() {
+        let judge = |comparison| Judge {
+            comparison,
+            ..Default::default()
+        };
+
+        let mut test = test_result(ints(&[1, 2, 2]), ints(&[2, 1, 2]));
+        judge(ComparisonMode::Ordered)
+            .judge(&mut test, ProblemKind::Function)
+            .await
+            .unwrap();
+        assert!(!test.success);
+
+        judge(ComparisonMode::UnorderedList)
+            .judge(&mut test, ProblemKind::Function)
+            .await
+            .unwrap();
+        assert!(test.success);
+
+        let mut test = test_result(ints(&[1, 2, 2]), ints(&[2, 1, 1]));
+        judge(ComparisonMode::Unordered)
+            .judge(&mut test, ProblemKind::Function)
+            .await
+            .unwrap();
+        assert!(!test.success);
+    }
+}
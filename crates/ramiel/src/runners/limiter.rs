@@ -0,0 +1,122 @@
+use wasmtime::{ResourceLimiter, StoreLimits};
+
+/// How much memory a single run used, in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// The largest size the program's linear memory reached
+    pub peak: u64,
+    /// How far memory grew once the program's arguments were in place. Memory grows in whole
+    /// 64 KiB pages whenever malloc runs out of heap, and never shrinks, so this is how much the
+    /// heap grew rounded up to pages, rather than how much was allocated at once.
+    pub heap: u64,
+}
+
+/// Enforces [`StoreLimits`] and limits the total size of the store's linear memories, while
+/// recording how large the program's memory grows.
+///
+/// Every memory of the store counts towards the limit, but memory grown while the program isn't
+/// being measured, such as by the interactor of a
+/// [`shared::models::runner::ProblemKind::Interactive`] problem, isn't part of its usage.
+pub struct MemoryLimiter {
+    limits: StoreLimits,
+    /// The most memory every memory of the store may use together
+    max_size: usize,
+    /// The total size of every memory in the store
+    size: usize,
+    /// The size of the program's memory, see [`Self::measure`]
+    measured: usize,
+    /// Whether memory grown now belongs to the program
+    measuring: bool,
+    /// The largest measured size before the last growth, which may still fail
+    peak: usize,
+    /// The size of memory before the program started allocating, see [`Self::set_baseline`]
+    baseline: usize,
+    /// How much the last allowed growth added to `size` and `measured`, which is undone if it
+    /// then failed
+    pending: (usize, usize),
+}
+
+impl MemoryLimiter {
+    pub fn new(limits: StoreLimits, max_size: usize) -> Self {
+        Self {
+            limits,
+            max_size,
+            size: 0,
+            measured: 0,
+            measuring: true,
+            peak: 0,
+            baseline: 0,
+            pending: (0, 0),
+        }
+    }
+
+    /// Sets whether memory grown from now on belongs to the program. Memory grown by anything
+    /// else, e.g. an interactor, still counts towards the limit.
+    pub fn measure(&mut self, measuring: bool) {
+        self.measuring = measuring;
+    }
+
+    /// Counts the memory used so far as the static data, stack and arguments of the program,
+    /// rather than its heap.
+    pub fn set_baseline(&mut self) {
+        self.baseline = self.measured;
+    }
+
+    pub fn usage(&self) -> MemoryUsage {
+        let peak = self.peak.max(self.measured);
+
+        MemoryUsage {
+            peak: peak as u64,
+            heap: peak.saturating_sub(self.baseline) as u64,
+        }
+    }
+}
+
+impl ResourceLimiter for MemoryLimiter {
+    fn memory_growing(&mut self, current: usize, desired: usize, maximum: Option<usize>) -> bool {
+        let growth = desired.saturating_sub(current);
+        if self.size + growth > self.max_size {
+            return false;
+        }
+
+        let allowed = self.limits.memory_growing(current, desired, maximum);
+
+        if allowed {
+            let measured = if self.measuring { growth } else { 0 };
+
+            self.peak = self.peak.max(self.measured);
+            self.pending = (growth, measured);
+            self.size += growth;
+            self.measured += measured;
+        }
+
+        allowed
+    }
+
+    fn memory_grow_failed(&mut self, error: &anyhow::Error) {
+        let (growth, measured) = std::mem::take(&mut self.pending);
+        self.size -= growth;
+        self.measured -= measured;
+        self.limits.memory_grow_failed(error);
+    }
+
+    fn table_growing(&mut self, current: u32, desired: u32, maximum: Option<u32>) -> bool {
+        self.limits.table_growing(current, desired, maximum)
+    }
+
+    fn table_grow_failed(&mut self, error: &anyhow::Error) {
+        self.limits.table_grow_failed(error);
+    }
+
+    fn instances(&self) -> usize {
+        self.limits.instances()
+    }
+
+    fn tables(&self) -> usize {
+        self.limits.tables()
+    }
+
+    fn memories(&self) -> usize {
+        self.limits.memories()
+    }
+}
@@ -1,29 +1,440 @@
 use actix_web::rt::task;
 use async_trait::async_trait;
+use futures::future::join_all;
 use shared::models::{
     forms::{CustomInputJob, GenerateTestsJob, SubmitJob},
-    runner::{CustomInputResponse, RunnerError, RunnerResponse},
+    runner::{CustomInputResponse, ProblemKind, RunnerError, RunnerResponse},
     test::{Test, TestResult},
 };
-use std::collections::BTreeSet;
-use wasi_common::pipe::WritePipe;
-use wasm_memory::{FunctionValue, WasmFunctionCall};
+use std::{collections::BTreeSet, sync::OnceLock, thread, time::Duration};
+use tokio::sync::{mpsc::UnboundedSender, Semaphore};
+use wasi_common::{
+    pipe::{ReadPipe, WritePipe},
+    I32Exit,
+};
+use wasm_memory::{Abi, ContainerVariant, FunctionValue, WasmFunctionCall};
 
-use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+use wasmtime::{Config, Engine, InstancePre, Linker, Module, Store, StoreLimitsBuilder};
 use wasmtime_wasi::{sync::WasiCtxBuilder, WasiCtx};
 
+mod c;
+pub mod cache;
+pub mod cancellation;
 mod cplusplus;
+mod interactor;
+mod judge;
+mod limiter;
+mod python;
+mod rust;
 
+pub use c::C;
+pub use cancellation::Cancellation;
 pub use cplusplus::CPlusPlus;
+pub use judge::Judge;
+pub use limiter::MemoryUsage;
+pub use python::Python;
+pub use rust::Rust;
+
+/// Looks up the runner for a language, as it appears in the url (e.g. `/run/c++`)
+pub fn get_runner(language: &str) -> Option<&'static dyn Runner> {
+    match language {
+        "c" => Some(&C),
+        "c++" => Some(&CPlusPlus),
+        "python" => Some(&Python),
+        "rust" => Some(&Rust),
+        _ => None,
+    }
+}
 
 #[async_trait]
-pub trait Runner {
-    async fn run_tests(&self, form: SubmitJob) -> Result<RunnerResponse, RunnerError>;
-    async fn generate_tests(&self, form: GenerateTestsJob) -> Result<Vec<Test>, RunnerError>;
+pub trait Runner: Send + Sync {
+    /// The name of the language, used to keep build directories of different languages apart.
+    fn name(&self) -> &'static str;
+
+    /// How the compiled module expects arguments to be passed.
+    fn abi(&self) -> Abi {
+        Abi::CPlusPlus
+    }
+
+    /// How much slower this language is expected to be than the C++ reference solution, which
+    /// the fuel limits of tests are based on.
+    fn fuel_multiplier(&self) -> f64 {
+        1.0
+    }
+
+    /// Compiles `source` to a wasm module inside of `prefix`, returning the path to the module.
+    ///
+    /// `calls` are the function calls the module will be expected to handle, which runners use to
+    /// generate glue code between the user's functions and the judge. [`ProblemKind::Stdio`]
+    /// problems are instead compiled to command modules, which are run through `_start`.
+    async fn compile(
+        &self,
+        prefix: &str,
+        source: &str,
+        kind: ProblemKind,
+        calls: &[&WasmFunctionCall],
+    ) -> Result<String, RunnerError>;
+
+    /// Loads the output of [`Runner::compile`], so it can be instantiated once per test without
+    /// being compiled again. Every instance of the program stops once `cancellation` is cancelled.
+    async fn load(
+        &self,
+        command: &str,
+        kind: ProblemKind,
+        cancellation: &Cancellation,
+    ) -> Result<Program, RunnerError> {
+        let command = command.to_string();
+        let cancellation = cancellation.clone();
+        task::spawn_blocking(move || {
+            let instance_pre = load_module(&command, kind)?;
+            Ok(Program {
+                command,
+                kind,
+                instance_pre,
+                interactor: None,
+                cancellation,
+            })
+        })
+        .await
+        .map_err(|e| {
+            log::error!("caught error: {e}");
+            RunnerError::InternalServerError {
+                message: "Failed to create thread".to_string(),
+            }
+        })?
+    }
+
+    /// Runs a single function call against a loaded program.
+    ///
+    /// For [`ProblemKind::Stdio`] problems, the result is everything written to stdout, and
+    /// anything written to stderr is returned as printed instead.
+    async fn execute(
+        &self,
+        program: &Program,
+        input: WasmFunctionCall,
+        fuel: Option<i64>,
+    ) -> Result<Execution, RunnerError> {
+        match program.kind {
+            ProblemKind::Function | ProblemKind::Interactive => {
+                run_command(program.clone(), self.abi(), input, fuel).await
+            }
+            ProblemKind::Stdio => {
+                let stdin = stdin(&input)?;
+                let (stdout, stderr, fuel, memory) =
+                    run_stdio(program.clone(), stdin, fuel).await?;
+
+                Ok(Execution {
+                    result: FunctionValue::String(ContainerVariant::Single(stdout)),
+                    output: stderr,
+                    fuel,
+                    queries: None,
+                    interactor_fuel: None,
+                    accepted: None,
+                    memory,
+                })
+            }
+        }
+    }
+
+    /// Runs a test, returning a failed test result if the submission errors or runs out of fuel.
+    ///
+    /// Padding dictates how much extra fuel should be allotted before we force stop their function.
+    /// e.g. A padding value of 10 means it can be 10x slower before we force stop it
+    async fn run_test_timed(
+        &self,
+        program: &Program,
+        mut test: Test,
+        padding: i64,
+        judge: &Judge,
+    ) -> Result<(TestResult, String), RunnerError> {
+        test.max_fuel = test
+            .max_fuel
+            .map(|fuel| (fuel as f64 * self.fuel_multiplier()) as i64);
+
+        let max_runtime = test.max_fuel.map(|x| x * padding);
+
+        match self.execute(program, test.input.clone(), max_runtime).await {
+            Ok(execution) => {
+                let mut test_result = test.make_result(execution.result, execution.fuel);
+                test_result.queries = execution.queries.map(|queries| queries as i64);
+                test_result.interactor_fuel = execution.interactor_fuel.map(|fuel| fuel as i64);
+                test_result.memory = Some(execution.memory.peak as i64);
+                test_result.heap = Some(execution.memory.heap as i64);
+                judge.judge(&mut test_result, program.kind).await?;
+
+                if execution.accepted == Some(false) {
+                    test_result.success = false;
+                    test_result.error = Some("The interactor rejected the answer".to_string());
+                }
+
+                if execution.fuel > test_result.max_fuel.unwrap_or(MAX_FUEL) as u64 {
+                    test_result.success = false;
+                    test_result.error = Some("Fuel limit exceeded".to_string())
+                }
+
+                Ok((test_result, execution.output))
+            }
+            Err(RunnerError::RuntimeError { message }) => Ok((
+                test.make_result_error(message, max_runtime.unwrap_or(MAX_FUEL) as u64),
+                String::new(),
+            )),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Runs every test of a submission. If `progress` is given, each test result is also sent
+    /// through it as soon as the test finishes.
+    async fn run_tests(
+        &self,
+        form: SubmitJob,
+        progress: Option<UnboundedSender<TestResult>>,
+        cancellation: &Cancellation,
+    ) -> Result<RunnerResponse, RunnerError> {
+        let prefix = format!(
+            "/tmp/acm/submissions/{}/{}/{}",
+            form.user_id,
+            form.problem_id,
+            self.name()
+        );
+
+        let calls = form
+            .tests
+            .iter()
+            .map(|test| &test.input)
+            .collect::<Vec<_>>();
+        let command = self
+            .compile(&prefix, &form.implementation, form.kind, &calls)
+            .await?;
+        let interactor = interactor::load(
+            &format!(
+                "/tmp/acm/submissions/{}/{}/interactor",
+                form.user_id, form.problem_id
+            ),
+            form.kind,
+            form.interactor.as_deref(),
+            cancellation,
+        )
+        .await
+        .map_err(interactor::hide_interactor_diagnostics)?;
+        let program = self
+            .load(&command, form.kind, cancellation)
+            .await?
+            .with_interactor(interactor);
+
+        let judge = Judge::load(
+            &format!(
+                "/tmp/acm/submissions/{}/{}/checker",
+                form.user_id, form.problem_id
+            ),
+            form.normalization,
+            form.tolerance,
+            form.comparison,
+            form.checker.as_deref(),
+            cancellation,
+        )
+        .await
+        .map_err(judge::hide_checker_diagnostics)?;
+
+        // Tests run concurrently, bounded by the worker pool
+        let tests = join_all(form.tests.into_iter().map(|mut test| {
+            test.adjust_runtime(form.runtime_multiplier);
+
+            let progress = progress.clone();
+            let program = &program;
+            let judge = &judge;
+            async move {
+                let res = self.run_test_timed(program, test, 50, judge).await;
+
+                if let (Ok((test, _)), Some(progress)) = (&res, progress) {
+                    progress.send(test.clone()).ok();
+                }
+
+                res
+            }
+        }))
+        .await
+        .into_iter()
+        .map(|res| res.map(|(test, _)| test))
+        .collect::<Result<Vec<_>, _>>()?;
+
+        let mut total_runtime = 0;
+        let mut peak_memory = 0;
+        let mut peak_heap = 0;
+
+        let mut test_results = TestResults::new();
+
+        for test in tests {
+            total_runtime += test.fuel;
+            peak_memory = peak_memory.max(test.memory.unwrap_or(0));
+            peak_heap = peak_heap.max(test.heap.unwrap_or(0));
+            test_results.insert(test);
+        }
+
+        test_results.runtime = total_runtime;
+        test_results.memory = peak_memory;
+        test_results.heap = peak_heap;
+
+        Ok(test_results.into())
+    }
+
+    async fn generate_tests(
+        &self,
+        form: GenerateTestsJob,
+        cancellation: &Cancellation,
+    ) -> Result<Vec<Test>, RunnerError> {
+        let prefix = format!("/tmp/acm/problem_editor/{}/{}", form.user_id, self.name());
+
+        let calls = form.inputs.iter().collect::<Vec<_>>();
+        let command = self
+            .compile(&prefix, &form.reference, form.kind, &calls)
+            .await?;
+        let interactor = interactor::load(
+            &format!("/tmp/acm/problem_editor/{}/interactor", form.user_id),
+            form.kind,
+            form.interactor.as_deref(),
+            cancellation,
+        )
+        .await?;
+        let program = self
+            .load(&command, form.kind, cancellation)
+            .await?
+            .with_interactor(interactor);
+
+        // Only compiled so that errors in the checker are reported before the problem is created
+        Judge::load(
+            &format!("/tmp/acm/problem_editor/{}/checker", form.user_id),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            form.checker.as_deref(),
+            cancellation,
+        )
+        .await?;
+
+        let outputs = join_all(
+            form.inputs
+                .iter()
+                .map(|input| self.execute(&program, input.clone(), None)),
+        )
+        .await;
+
+        form.inputs
+            .into_iter()
+            .zip(outputs)
+            .enumerate()
+            .map(|(i, (input, execution))| {
+                let execution = execution?;
+                Ok(Test {
+                    id: 0,
+                    index: i as i64,
+                    max_fuel: Some(execution.fuel as i64),
+                    input,
+                    expected_output: execution.result,
+                })
+            })
+            .collect()
+    }
+
+    /// Reference solutions are always written in C++, so only the implementation is compiled with
+    /// this runner.
     async fn run_custom_input(
         &self,
         form: CustomInputJob,
-    ) -> Result<CustomInputResponse, RunnerError>;
+        cancellation: &Cancellation,
+    ) -> Result<CustomInputResponse, RunnerError> {
+        let reference_prefix = format!(
+            "/tmp/acm/custom_input/{}/{}/reference",
+            form.user_id, form.problem_id
+        );
+        let implementation_prefix = format!(
+            "/tmp/acm/custom_input/{}/{}/implementation/{}",
+            form.user_id,
+            form.problem_id,
+            self.name()
+        );
+
+        let calls = [&form.input];
+        let reference_command = CPlusPlus
+            .compile(&reference_prefix, &form.reference, form.kind, &calls)
+            .await?;
+        let implementation_command = self
+            .compile(
+                &implementation_prefix,
+                &form.implementation,
+                form.kind,
+                &calls,
+            )
+            .await?;
+
+        let interactor = interactor::load(
+            &format!(
+                "/tmp/acm/custom_input/{}/{}/interactor",
+                form.user_id, form.problem_id
+            ),
+            form.kind,
+            form.interactor.as_deref(),
+            cancellation,
+        )
+        .await
+        .map_err(interactor::hide_interactor_diagnostics)?;
+
+        let reference = CPlusPlus
+            .load(&reference_command, form.kind, cancellation)
+            .await?
+            .with_interactor(interactor.clone());
+        let implementation = self
+            .load(&implementation_command, form.kind, cancellation)
+            .await?
+            .with_interactor(interactor);
+
+        let judge = Judge::load(
+            &format!(
+                "/tmp/acm/custom_input/{}/{}/checker",
+                form.user_id, form.problem_id
+            ),
+            form.normalization,
+            form.tolerance,
+            form.comparison,
+            form.checker.as_deref(),
+            cancellation,
+        )
+        .await
+        .map_err(judge::hide_checker_diagnostics)?;
+
+        let reference = CPlusPlus
+            .execute(&reference, form.input.clone(), None)
+            .await?;
+
+        let mut test = Test {
+            id: 0,
+            index: 0,
+            input: form.input,
+            expected_output: reference.result,
+            max_fuel: Some(reference.fuel as i64),
+        };
+
+        test.adjust_runtime(form.runtime_multiplier);
+
+        // we add a lot of padding so they can potentially print a lot
+        let (test_result, stdout) = self
+            .run_test_timed(&implementation, test, 500, &judge)
+            .await?;
+
+        Ok(CustomInputResponse {
+            result: test_result,
+            output: stdout,
+        })
+    }
+}
+
+/// Returns the stdin of a [`ProblemKind::Stdio`] test.
+fn stdin(input: &WasmFunctionCall) -> Result<String, RunnerError> {
+    input
+        .stdin()
+        .map(str::to_string)
+        .ok_or_else(|| RunnerError::InternalServerError {
+            message: "Expected the input of a stdin/stdout problem".to_string(),
+        })
 }
 
 struct TestResults {
@@ -31,6 +442,8 @@ struct TestResults {
     passed_tests: BTreeSet<TestResult>,
 
     runtime: i64,
+    memory: i64,
+    heap: i64,
 }
 
 impl TestResults {
@@ -39,6 +452,8 @@ impl TestResults {
             failed_tests: BTreeSet::new(),
             passed_tests: BTreeSet::new(),
             runtime: 0,
+            memory: 0,
+            heap: 0,
         }
     }
 
@@ -51,123 +466,244 @@ impl TestResults {
     }
 }
 
-impl Into<RunnerResponse> for TestResults {
-    fn into(self) -> RunnerResponse {
-        let mut tests = Vec::with_capacity(self.failed_tests.len() + self.passed_tests.len());
-        let passed = self.failed_tests.is_empty();
-        tests.extend(self.failed_tests);
-        tests.extend(self.passed_tests);
+impl From<TestResults> for RunnerResponse {
+    fn from(results: TestResults) -> Self {
+        let mut tests = Vec::with_capacity(results.failed_tests.len() + results.passed_tests.len());
+        let passed = results.failed_tests.is_empty();
+        tests.extend(results.failed_tests);
+        tests.extend(results.passed_tests);
 
         RunnerResponse {
             tests,
-            runtime: self.runtime,
+            runtime: results.runtime,
+            memory: results.memory,
+            heap: results.heap,
             passed,
         }
     }
 }
 
+/// The result of [`Runner::execute`]
+pub struct Execution {
+    pub result: FunctionValue,
+    /// Anything the program printed
+    pub output: String,
+    pub fuel: u64,
+    /// How many times a [`ProblemKind::Interactive`] program called the interactor
+    pub queries: Option<u64>,
+    /// The fuel the interactor consumed, which isn't part of [`Execution::fuel`]
+    pub interactor_fuel: Option<u64>,
+    /// Whether the interactor accepted the answer, if it judges answers itself
+    pub accepted: Option<bool>,
+    pub memory: MemoryUsage,
+}
+
 struct MyState {
-    limits: StoreLimits,
+    limits: limiter::MemoryLimiter,
     wasi: WasiCtx,
+    cancellation: Cancellation,
+    interaction: Option<interactor::Interaction>,
 }
 
-/// Runs a command with a specified input, returning a RuntimeError if the process returns an
-/// error, otherwise returns the output and the duration
+const MAX_MEMORY: usize = 1 << 29; // 512MB
+const MAX_FUEL: i64 = 1 << 48;
+
+const DEFAULT_MEMORY_BUDGET: usize = 8 * MAX_MEMORY;
+
+static WORKERS: OnceLock<Semaphore> = OnceLock::new();
+
+/// Sets how much memory running tests may use in total. Since every test may use up to
+/// [`MAX_MEMORY`], this determines how many tests run at the same time across every submission.
 ///
-/// Padding dictates how much extra fuel should be allotted before we force stop their function.
-/// e.g. A padding value of 10 means it can be 10x slower before we force stop it
-async fn run_test_timed(
-    command: &str,
-    test: Test,
-    padding: i64,
-) -> Result<(TestResult, String), RunnerError> {
-    let max_runtime = test.max_fuel.map(|x| x * padding);
-
-    match run_command(command, test.input.clone(), max_runtime).await {
-        Ok((result, output, fuel)) => {
-            let mut test_result = test.make_result(result, fuel);
-
-            if fuel > test_result.max_fuel.unwrap_or(MAX_FUEL) as u64 {
-                test_result.success = false;
-                test_result.error = Some("Fuel limit exceeded".to_string())
-            }
+/// Must be called before any tests are run, otherwise it has no effect.
+pub fn init_workers(memory_budget: usize) {
+    WORKERS
+        .set(Semaphore::new(worker_count(memory_budget)))
+        .ok();
+}
+
+fn worker_count(memory_budget: usize) -> usize {
+    (memory_budget / MAX_MEMORY).max(1)
+}
 
-            Ok((test_result, output))
+fn workers() -> &'static Semaphore {
+    WORKERS.get_or_init(|| Semaphore::new(worker_count(DEFAULT_MEMORY_BUDGET)))
+}
+
+/// Runs `f`, which instantiates a module, on a blocking thread once a worker is available.
+///
+/// Jobs cancelled while waiting for a worker don't run at all.
+async fn run_in_worker<T, F>(cancellation: &Cancellation, f: F) -> Result<T, RunnerError>
+where
+    F: FnOnce() -> Result<T, RunnerError> + Send + 'static,
+    T: Send + 'static,
+{
+    let _permit = workers()
+        .acquire()
+        .await
+        .expect("Worker pool should never be closed");
+
+    if cancellation.is_cancelled() {
+        return Err(RunnerError::Cancelled);
+    }
+
+    task::spawn_blocking(f).await.map_err(|e| {
+        log::error!("caught error: {e}");
+        RunnerError::InternalServerError {
+            message: "Failed to create thread".to_string(),
+        }
+    })?
+}
+
+/// A compiled submission, ready to be instantiated for each test.
+#[derive(Clone)]
+pub struct Program {
+    /// The output of [`Runner::compile`]
+    command: String,
+    kind: ProblemKind,
+    instance_pre: InstancePre<MyState>,
+    /// The interactor of a [`ProblemKind::Interactive`] problem
+    interactor: Option<InstancePre<MyState>>,
+    cancellation: Cancellation,
+}
+
+impl Program {
+    /// Runs the program against `interactor`, which is loaded by [`interactor::load`].
+    fn with_interactor(self, interactor: Option<Program>) -> Program {
+        Program {
+            interactor: interactor.map(|interactor| interactor.instance_pre),
+            ..self
         }
-        Err(RunnerError::RuntimeError { message }) => Ok((
-            test.make_result_error(message, max_runtime.unwrap_or(MAX_FUEL) as u64),
-            String::new(),
-        )),
-        Err(e) => Err(e),
     }
 }
 
-const MAX_MEMORY: usize = 1 << 29; // 512MB
-const MAX_FUEL: i64 = 1 << 48;
+/// How often running modules check whether they have been cancelled
+const EPOCH_INTERVAL: Duration = Duration::from_millis(10);
 
-async fn run_command(
-    command: &str,
-    input: WasmFunctionCall,
-    fuel: Option<i64>,
-) -> Result<(FunctionValue, String, u64), RunnerError> {
-    let command = command.to_string();
-    task::spawn_blocking(move || {
+/// The engine shared by every module. Sharing it lets wasmtime reuse compiled code.
+///
+/// The engine's epoch is incremented every [`EPOCH_INTERVAL`], at which point every running
+/// module yields to [`create_store`]'s deadline callback.
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+
+    ENGINE.get_or_init(|| {
         let mut config = Config::default();
         config.consume_fuel(true);
-        config
-            .cache_config_load("./wasmtime-cache.toml")
-            .expect("Failed to load cache configuration");
+        config.epoch_interruption(true);
+        // only speeds up compiling modules, so it's not required, e.g. when running tests
+        if let Err(e) = config.cache_config_load("./wasmtime-cache.toml") {
+            log::warn!("failed to load cache configuration: {e}");
+        }
 
         let engine = Engine::new(&config).expect("Failed to create engine");
 
-        let mut linker = Linker::new(&engine);
-        wasmtime_wasi::add_to_linker(&mut linker, |state: &mut MyState| &mut state.wasi).map_err(
-            |e| {
-                log::error!("add_to_linker: {e}");
-                RunnerError::InternalServerError {
-                    message: "Failed to add wasi runtime to linker".to_string(),
-                }
-            },
-        )?;
+        let ticker = engine.clone();
+        thread::spawn(move || loop {
+            thread::sleep(EPOCH_INTERVAL);
+            ticker.increment_epoch();
+        });
 
-        let stdout = WritePipe::new_in_memory();
+        engine
+    })
+}
 
-        let mut store = Store::new(
-            &engine,
-            MyState {
-                wasi: WasiCtxBuilder::new()
-                    .stdout(Box::new(stdout.clone()))
-                    .build(),
-                limits: StoreLimitsBuilder::new()
-                    .memory_size(MAX_MEMORY)
-                    .instances(2)
-                    .build(),
-            },
-        );
+/// A linker providing the wasi runtime.
+fn linker() -> &'static Linker<MyState> {
+    static LINKER: OnceLock<Linker<MyState>> = OnceLock::new();
 
-        store
-            .add_fuel(fuel.unwrap_or(MAX_FUEL) as u64)
-            .expect("Failed to add fuel");
-        store.limiter(|state| &mut state.limits);
+    LINKER.get_or_init(|| {
+        let mut linker = Linker::new(engine());
+        wasmtime_wasi::add_to_linker(&mut linker, |state: &mut MyState| &mut state.wasi)
+            .expect("Failed to add wasi runtime to linker");
+
+        linker
+    })
+}
+
+/// Creates a store limited to `fuel` (or [`MAX_FUEL`]) and [`MAX_MEMORY`], which traps once
+/// `cancellation` is cancelled.
+///
+/// The memory limit applies to every instance of the store together, e.g. a submission and its
+/// interactor, so that a worker never uses more than [`MAX_MEMORY`].
+fn create_store(wasi: WasiCtx, fuel: Option<i64>, cancellation: Cancellation) -> Store<MyState> {
+    let mut store = Store::new(
+        engine(),
+        MyState {
+            wasi,
+            limits: limiter::MemoryLimiter::new(
+                StoreLimitsBuilder::new().instances(2).build(),
+                MAX_MEMORY,
+            ),
+            cancellation,
+            interaction: None,
+        },
+    );
+
+    store.set_epoch_deadline(1);
+    store.epoch_deadline_callback(|state| {
+        if state.cancellation.is_cancelled() {
+            anyhow::bail!("The job was cancelled");
+        }
+
+        Ok(1)
+    });
+
+    store
+        .add_fuel(fuel.unwrap_or(MAX_FUEL) as u64)
+        .expect("Failed to add fuel");
+    store.limiter(|state| &mut state.limits);
+
+    store
+}
+
+/// Compiles the module at `filename` and resolves its imports. The imports of
+/// [`ProblemKind::Interactive`] modules are resolved by the interactor.
+fn load_module(filename: &str, kind: ProblemKind) -> Result<InstancePre<MyState>, RunnerError> {
+    let module = Module::from_file(engine(), filename).map_err(|e| {
+        log::error!("opening: {e}");
+        RunnerError::InternalServerError {
+            message: "Failed to open file".to_string(),
+        }
+    })?;
 
-        // Instantiate our module with the imports we've created, and run it.
-        let module = Module::from_file(&engine, command).map_err(|e| {
-            log::error!("opening: {e}");
+    let linker = match kind {
+        ProblemKind::Interactive => interactor::link(&module).map_err(|e| {
+            log::error!("{e:?}");
             RunnerError::InternalServerError {
-                message: "Failed to open file".to_string(),
+                message: format!("Failed to link module:\n{}", e.root_cause()),
             }
-        })?;
+        })?,
+        _ => linker().clone(),
+    };
+
+    linker.instantiate_pre(&module).map_err(|e| {
+        log::error!("{e:?}");
+        RunnerError::InternalServerError {
+            message: format!("Failed to link module:\n{}", e.root_cause()),
+        }
+    })
+}
+
+async fn run_command(
+    program: Program,
+    abi: Abi,
+    input: WasmFunctionCall,
+    fuel: Option<i64>,
+) -> Result<Execution, RunnerError> {
+    let cancellation = program.cancellation.clone();
+    run_in_worker(&cancellation, move || {
+        let stdout = WritePipe::new_in_memory();
+        let wasi = WasiCtxBuilder::new()
+            .stdout(Box::new(stdout.clone()))
+            .build();
+
+        let mut store = create_store(wasi, fuel, program.cancellation.clone());
 
         const FUEL_DEFAULT: u64 = 100_000_000_000;
         store.add_fuel(FUEL_DEFAULT).expect("Failed to add fuel");
 
-        linker
-            .module(&mut store, "", &module)
-            .map_err(|e| RunnerError::InternalServerError {
-                message: format!("Failed to initialize module:\n{}", e.root_cause()),
-            })?;
-
-        let instance = linker.instantiate(&mut store, &module).map_err(|e| {
+        let instance = program.instance_pre.instantiate(&mut store).map_err(|e| {
             log::error!("{e:?}");
             RunnerError::InternalServerError {
                 message: format!("Failed to create instance:\n{}", e.root_cause()),
@@ -179,7 +715,25 @@ async fn run_command(
             .consume_fuel(FUEL_DEFAULT - consumed_for_initialize)
             .expect("Failed consuming fuel");
 
-        let result = input.call(&mut store, &instance);
+        let input = match &program.interactor {
+            Some(interactor) => {
+                let interactor_fuel = fuel.unwrap_or(MAX_FUEL) as u64;
+                interactor::start(&mut store, interactor, input, interactor_fuel)?
+            }
+            None => input,
+        };
+
+        let result = input.call_with(&mut store, &instance, abi, |store| {
+            store.data_mut().limits.set_baseline()
+        });
+        let accepted = match &result {
+            Ok(_) => interactor::accepted(&mut store)?,
+            Err(_) => None,
+        };
+        let interactor_error = interactor::error(&store);
+        let queries = interactor::queries(&store);
+        let interactor_fuel = interactor::fuel(&store);
+        let memory = store.data().limits.usage();
 
         drop(store);
 
@@ -187,17 +741,189 @@ async fn run_command(
         let output = String::from_utf8_lossy(&bytes).to_string();
 
         match result {
-            Ok((res, fuel)) => Ok((res, output, fuel)),
-            Err(e) => Err(RunnerError::RuntimeError {
-                message: e.root_cause().to_string(),
+            Ok((result, fuel)) => Ok(Execution {
+                result,
+                output,
+                fuel,
+                queries,
+                interactor_fuel,
+                accepted,
+                memory,
             }),
+            Err(_) if program.cancellation.is_cancelled() => Err(RunnerError::Cancelled),
+            // The interactor's crash reaches the submission as a trap of its own
+            Err(e) => Err(
+                interactor_error.unwrap_or_else(|| RunnerError::RuntimeError {
+                    message: e.root_cause().to_string(),
+                }),
+            ),
         }
     })
     .await
-    .map_err(|e| {
-        log::error!("caught error: {e}");
-        return RunnerError::InternalServerError {
-            message: "Failed to create thread".to_string(),
-        };
-    })?
+}
+
+/// Runs a command module with `stdin`, returning what it wrote to stdout and stderr, the fuel
+/// consumed and the memory used.
+async fn run_stdio(
+    program: Program,
+    stdin: String,
+    fuel: Option<i64>,
+) -> Result<(String, String, u64, MemoryUsage), RunnerError> {
+    let cancellation = program.cancellation.clone();
+    run_in_worker(&cancellation, move || {
+        let stdout = WritePipe::new_in_memory();
+        let stderr = WritePipe::new_in_memory();
+        let wasi = WasiCtxBuilder::new()
+            .stdin(Box::new(ReadPipe::from(stdin)))
+            .stdout(Box::new(stdout.clone()))
+            .stderr(Box::new(stderr.clone()))
+            .build();
+
+        let mut store = create_store(wasi, fuel, program.cancellation.clone());
+
+        let instance = program.instance_pre.instantiate(&mut store).map_err(|e| {
+            log::error!("{e:?}");
+            RunnerError::InternalServerError {
+                message: format!("Failed to create instance:\n{}", e.root_cause()),
+            }
+        })?;
+
+        let start = instance
+            .get_typed_func::<(), ()>(&mut store, "_start")
+            .map_err(|_| RunnerError::RuntimeError {
+                message: "Expected a main function, but it was not found.".to_string(),
+            })?;
+        store.data_mut().limits.set_baseline();
+
+        let result = start.call(&mut store, ());
+        let fuel = store.fuel_consumed().unwrap_or(0);
+        let memory = store.data().limits.usage();
+
+        drop(store);
+
+        let stdout = stdout.try_into_inner().unwrap().into_inner();
+        let stderr = stderr.try_into_inner().unwrap().into_inner();
+
+        check_exit(result, &stderr, &program.cancellation)?;
+
+        Ok((
+            String::from_utf8_lossy(&stdout).to_string(),
+            String::from_utf8_lossy(&stderr).to_string(),
+            fuel,
+            memory,
+        ))
+    })
+    .await
+}
+
+/// Checks the result of calling `_start`, where exiting with status 0 counts as success. Anything
+/// written to `stderr` is included in the error, e.g. a Python traceback.
+fn check_exit(
+    result: anyhow::Result<()>,
+    stderr: &[u8],
+    cancellation: &Cancellation,
+) -> Result<(), RunnerError> {
+    match result {
+        Ok(()) => Ok(()),
+        Err(e) if matches!(e.downcast_ref::<I32Exit>(), Some(I32Exit(0))) => Ok(()),
+        Err(_) if cancellation.is_cancelled() => Err(RunnerError::Cancelled),
+        Err(e) => {
+            let message = format!("{}\n{}", e.root_cause(), String::from_utf8_lossy(stderr));
+
+            Err(RunnerError::RuntimeError {
+                message: message.trim_end().to_string(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use wasmtime_wasi::sync::WasiCtxBuilder;
+
+    #[test]
+    fn worker_count_divides_budget() {
+        assert_eq!(worker_count(4 * MAX_MEMORY), 4);
+        assert_eq!(worker_count(4 * MAX_MEMORY + MAX_MEMORY / 2), 4);
+        assert_eq!(worker_count(0), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn workers_bound_concurrency() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let most_running = Arc::new(AtomicUsize::new(0));
+        let cancellation = Cancellation::default();
+
+        let jobs = (0..3 * worker_count(DEFAULT_MEMORY_BUDGET)).map(|_| {
+            let running = running.clone();
+            let most_running = most_running.clone();
+            run_in_worker(&cancellation, move || {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most_running.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(20));
+                running.fetch_sub(1, Ordering::SeqCst);
+
+                Ok(())
+            })
+        });
+
+        let results = join_all(jobs).await;
+
+        assert!(results.iter().all(Result::is_ok));
+        assert!(most_running.load(Ordering::SeqCst) <= worker_count(DEFAULT_MEMORY_BUDGET));
+    }
+
+    #[tokio::test]
+    async fn cancelled_jobs_dont_run() {
+        let cancellation = Cancellation::default();
+        cancellation.cancel();
+
+        let result = run_in_worker(&cancellation, || -> Result<(), RunnerError> {
+            panic!("cancelled jobs shouldn't run")
+        })
+        .await;
+
+        assert!(matches!(result, Err(RunnerError::Cancelled)));
+    }
+
+    #[test]
+    fn instances_share_memory_limit() -> anyhow::Result<()> {
+        let module = Module::new(
+            engine(),
+            r#"
+            (module
+                (memory 1)
+                (func (export "grow") (param i32) (result i32)
+                    (memory.grow (local.get 0))))
+            "#,
+        )?;
+        let mut store = create_store(WasiCtxBuilder::new().build(), None, Cancellation::default());
+
+        // e.g. a submission and its interactor, which may use most of the limit each
+        let pages = (MAX_MEMORY / 65536 * 3 / 4) as i32;
+        let linker = Linker::new(engine());
+        let submission = linker.instantiate(&mut store, &module)?;
+
+        // only the submission's memory is measured
+        store.data_mut().limits.measure(false);
+        let interactor = linker.instantiate(&mut store, &module)?;
+
+        let grow = interactor.get_typed_func::<i32, i32>(&mut store, "grow")?;
+        assert_eq!(grow.call(&mut store, pages)?, 1);
+        store.data_mut().limits.measure(true);
+
+        let grow = submission.get_typed_func::<i32, i32>(&mut store, "grow")?;
+        assert_eq!(grow.call(&mut store, pages)?, -1);
+        assert_eq!(grow.call(&mut store, 1)?, 1);
+
+        let usage = store.data().limits.usage();
+        assert_eq!(usage.peak, 2 * 65536);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,503 @@
+use actix_web::rt::task;
+use async_trait::async_trait;
+use shared::models::runner::{Diagnostic, DiagnosticType, ProblemKind, RunnerError};
+use std::sync::OnceLock;
+use tokio::{
+    fs::{self, File},
+    io::AsyncWriteExt,
+};
+use wasi_common::{
+    dir::DirCaps,
+    file::FileCaps,
+    pipe::{ReadPipe, WritePipe},
+    WasiCtx,
+};
+use wasm_memory::{ContainerVariant, FunctionValue, WasmFunctionCall};
+use wasmtime::InstancePre;
+use wasmtime_wasi::sync::{ambient_authority, dir, Dir, WasiCtxBuilder};
+
+use super::{
+    check_exit, create_store, load_module, run_in_worker, stdin, Cancellation, Execution,
+    MemoryUsage, MyState, Program, Runner,
+};
+
+/// The driver, which calls the submission's functions
+const DRIVER: &str = "/sandbox/main.py";
+
+/// The submission itself, which is run directly for [`ProblemKind::Stdio`] problems
+const SOLUTION: &str = "/sandbox/solution.py";
+
+/// Where CPython for WASI is installed, with the interpreter at `python.wasm` and the standard
+/// library under `usr/local/lib`.
+const PYTHON_PREFIX: &str = "/opt/python-wasi";
+
+/// Fuel it takes to start the interpreter, which is subtracted from the fuel of every call.
+static STARTUP_FUEL: OnceLock<u64> = OnceLock::new();
+
+/// The interpreter is the same for every submission, so it is only loaded once.
+static INTERPRETER: OnceLock<InstancePre<MyState>> = OnceLock::new();
+
+/// Runs submissions written in Python on CPython compiled to WASI.
+///
+/// Nothing is compiled ahead of time. Instead, the submission is written next to a driver script
+/// (`python_driver.py`), which receives the function call as json through stdin and writes the
+/// result back through stderr, after a nonce only it and ramiel know. [`ProblemKind::Stdio`]
+/// submissions are run directly instead.
+pub struct Python;
+
+#[async_trait]
+impl Runner for Python {
+    fn name(&self) -> &'static str {
+        "python"
+    }
+
+    fn fuel_multiplier(&self) -> f64 {
+        50.0
+    }
+
+    /// Returns the directory containing the submission. Syntax errors are only found once it is
+    /// loaded.
+    ///
+    /// The interpreter can't import functions from an interactor, so [`ProblemKind::Interactive`]
+    /// problems can't be solved in Python.
+    async fn compile(
+        &self,
+        prefix: &str,
+        source: &str,
+        kind: ProblemKind,
+        _calls: &[&WasmFunctionCall],
+    ) -> Result<String, RunnerError> {
+        if kind == ProblemKind::Interactive {
+            return Err(RunnerError::CompilationError {
+                diagnostics: vec![Diagnostic {
+                    line: 1,
+                    col: 1,
+                    diagnostic_type: DiagnosticType::Error,
+                    message: "Interactive problems can't be solved in Python".to_string(),
+                }],
+            });
+        }
+
+        fs::create_dir_all(prefix).await?;
+
+        File::create(format!("{prefix}/solution.py"))
+            .await?
+            .write_all(source.as_bytes())
+            .await?;
+        File::create(format!("{prefix}/main.py"))
+            .await?
+            .write_all(include_bytes!("python_driver.py"))
+            .await?;
+
+        Ok(prefix.to_string())
+    }
+
+    /// `command` is the directory containing the submission, which is run by the interpreter.
+    ///
+    /// The submission is imported once to check it for syntax errors, which are reported as
+    /// compilation errors. [`ProblemKind::Stdio`] submissions are only compiled, since running
+    /// them would wait for input.
+    async fn load(
+        &self,
+        command: &str,
+        kind: ProblemKind,
+        cancellation: &Cancellation,
+    ) -> Result<Program, RunnerError> {
+        let instance_pre = match INTERPRETER.get() {
+            Some(interpreter) => interpreter.clone(),
+            None => {
+                let interpreter = task::spawn_blocking(|| {
+                    load_module(
+                        &format!("{PYTHON_PREFIX}/python.wasm"),
+                        ProblemKind::Function,
+                    )
+                })
+                .await
+                .map_err(|e| {
+                    log::error!("caught error: {e}");
+                    RunnerError::InternalServerError {
+                        message: "Failed to create thread".to_string(),
+                    }
+                })??;
+
+                INTERPRETER.get_or_init(|| interpreter).clone()
+            }
+        };
+
+        let program = Program {
+            command: command.to_string(),
+            kind,
+            instance_pre,
+            interactor: None,
+            cancellation: cancellation.clone(),
+        };
+
+        let mode = match kind {
+            ProblemKind::Function | ProblemKind::Interactive => "\"check\"",
+            ProblemKind::Stdio => "\"compile\"",
+        };
+        let output = run_driver(&program, mode.to_string(), None).await?;
+
+        match serde_json::from_slice::<Result<(), Diagnostic>>(&output.result) {
+            Ok(Ok(())) => Ok(program),
+            Ok(Err(diagnostic)) => Err(RunnerError::CompilationError {
+                diagnostics: vec![diagnostic],
+            }),
+            Err(_) => Err(RunnerError::CompilationError {
+                diagnostics: vec![Diagnostic {
+                    line: 1,
+                    col: 1,
+                    diagnostic_type: DiagnosticType::Error,
+                    message: "Your code exited while it was being imported".to_string(),
+                }],
+            }),
+        }
+    }
+
+    async fn execute(
+        &self,
+        program: &Program,
+        input: WasmFunctionCall,
+        fuel: Option<i64>,
+    ) -> Result<Execution, RunnerError> {
+        let startup_fuel = startup_fuel(program).await?;
+        let fuel = fuel.map(|fuel| fuel + startup_fuel as i64);
+
+        if program.kind == ProblemKind::Stdio {
+            let output = run_python(program, SOLUTION, stdin(&input)?, fuel).await?;
+
+            return Ok(Execution {
+                result: FunctionValue::String(ContainerVariant::Single(output.stdout)),
+                output: String::from_utf8_lossy(&output.result).to_string(),
+                fuel: output.fuel.saturating_sub(startup_fuel),
+                queries: None,
+                interactor_fuel: None,
+                accepted: None,
+                memory: output.memory,
+            });
+        }
+
+        let input =
+            serde_json::to_string(&input).map_err(|e| RunnerError::InternalServerError {
+                message: format!("Failed to serialize input: {e}"),
+            })?;
+
+        let output = run_driver(program, input, fuel).await?;
+        let fuel = output.fuel.saturating_sub(startup_fuel);
+
+        match serde_json::from_slice::<Result<FunctionValue, String>>(&output.result) {
+            Ok(Ok(result)) => Ok(Execution {
+                result,
+                output: output.stdout,
+                fuel,
+                queries: None,
+                interactor_fuel: None,
+                accepted: None,
+                memory: output.memory,
+            }),
+            Ok(Err(message)) => Err(RunnerError::RuntimeError { message }),
+            Err(_) => Err(RunnerError::RuntimeError {
+                message: "Your code exited without returning a value".to_string(),
+            }),
+        }
+    }
+}
+
+struct PythonOutput {
+    stdout: String,
+    /// The result written by the driver, or everything written to stderr if the submission was
+    /// run directly
+    result: Vec<u8>,
+    fuel: u64,
+    memory: MemoryUsage,
+}
+
+/// Measures the fuel it takes for the interpreter to start, using the driver of `program`.
+async fn startup_fuel(program: &Program) -> Result<u64, RunnerError> {
+    if let Some(fuel) = STARTUP_FUEL.get() {
+        return Ok(*fuel);
+    }
+
+    let output = run_driver(program, "null".to_string(), None).await?;
+
+    Ok(*STARTUP_FUEL.get_or_init(|| output.fuel))
+}
+
+/// Runs the driver with `input`, keeping only the result it wrote after the nonce of this run.
+/// Anything else written to stderr, e.g. a result forged by the submission through
+/// `sys.__stderr__`, is ignored.
+async fn run_driver(
+    program: &Program,
+    input: String,
+    fuel: Option<i64>,
+) -> Result<PythonOutput, RunnerError> {
+    let nonce = format!("{:032x}", rand::random::<u128>());
+
+    let mut output = run_python(program, DRIVER, format!("{nonce}\n{input}"), fuel).await?;
+    output.result = driver_result(&output.result, &nonce).to_vec();
+
+    Ok(output)
+}
+
+/// Finds the line the driver wrote to stderr after `nonce`, which is empty if it wrote none.
+fn driver_result<'a>(stderr: &'a [u8], nonce: &str) -> &'a [u8] {
+    let Some(start) = stderr
+        .windows(nonce.len())
+        .position(|window| window == nonce.as_bytes())
+    else {
+        return &[];
+    };
+
+    let result = &stderr[start + nonce.len()..];
+    let end = result
+        .iter()
+        .position(|&b| b == b'\n')
+        .unwrap_or(result.len());
+
+    &result[..end]
+}
+
+fn internal_error<E: std::fmt::Display>(message: &'static str) -> impl FnOnce(E) -> RunnerError {
+    move |e| {
+        log::error!("{message}: {e}");
+        RunnerError::InternalServerError {
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Preopens `host_path` as `guest_path`, which unlike [`WasiCtxBuilder::preopened_dir`] only lets
+/// the guest read files, and not create, modify or remove anything.
+fn read_only_dir(wasi: &WasiCtx, host_path: &str, guest_path: &str) -> anyhow::Result<()> {
+    let dir = Dir::open_ambient_dir(host_path, ambient_authority())?;
+
+    wasi.push_dir(
+        Box::new(dir::Dir::from_cap_std(dir)),
+        DirCaps::OPEN
+            | DirCaps::READDIR
+            | DirCaps::READLINK
+            | DirCaps::PATH_FILESTAT_GET
+            | DirCaps::FILESTAT_GET,
+        FileCaps::READ
+            | FileCaps::SEEK
+            | FileCaps::TELL
+            | FileCaps::ADVISE
+            | FileCaps::FILESTAT_GET
+            | FileCaps::POLL_READWRITE,
+        guest_path.into(),
+    )?;
+
+    Ok(())
+}
+
+/// Runs `script` inside the program's directory, which is mounted at `/sandbox`.
+async fn run_python(
+    program: &Program,
+    script: &'static str,
+    stdin: String,
+    fuel: Option<i64>,
+) -> Result<PythonOutput, RunnerError> {
+    let program = program.clone();
+    let cancellation = program.cancellation.clone();
+    run_in_worker(&cancellation, move || {
+        let stdout = WritePipe::new_in_memory();
+        let stderr = WritePipe::new_in_memory();
+
+        let wasi = WasiCtxBuilder::new()
+            .stdin(Box::new(ReadPipe::from(stdin)))
+            .stdout(Box::new(stdout.clone()))
+            .stderr(Box::new(stderr.clone()))
+            .args(&["python".to_string(), script.to_string()])
+            .map_err(internal_error("Failed to set arguments"))?
+            .env("PYTHONDONTWRITEBYTECODE", "1")
+            .map_err(internal_error("Failed to set environment"))?
+            .build();
+
+        // Both are shared by every run, so a submission must not be able to replace the standard
+        // library or the driver
+        read_only_dir(&wasi, &format!("{PYTHON_PREFIX}/usr"), "/usr")
+            .map_err(internal_error("Failed to open standard library"))?;
+        read_only_dir(&wasi, &program.command, "/sandbox")
+            .map_err(internal_error("Failed to open submission"))?;
+
+        let mut store = create_store(wasi, fuel, program.cancellation.clone());
+
+        let instance = program.instance_pre.instantiate(&mut store).map_err(|e| {
+            log::error!("{e:?}");
+            RunnerError::InternalServerError {
+                message: format!("Failed to create instance:\n{}", e.root_cause()),
+            }
+        })?;
+
+        let start = instance
+            .get_typed_func::<(), ()>(&mut store, "_start")
+            .map_err(|e| RunnerError::InternalServerError {
+                message: format!("Failed to find interpreter entry point:\n{e}"),
+            })?;
+        store.data_mut().limits.set_baseline();
+
+        let result = start.call(&mut store, ());
+        let fuel = store.fuel_consumed().unwrap_or(0);
+        let memory = store.data().limits.usage();
+
+        drop(store);
+
+        let stdout = stdout.try_into_inner().unwrap().into_inner();
+        let stderr = stderr.try_into_inner().unwrap().into_inner();
+
+        check_exit(result, &stderr, &program.cancellation)?;
+
+        Ok(PythonOutput {
+            stdout: String::from_utf8_lossy(&stdout).to_string(),
+            result: stderr,
+            fuel,
+            memory,
+        })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_memory::{ContainerVariantType, FunctionType};
+    use wasmtime::{Engine, Instance, Linker, Module, Store};
+
+    /// Calls wasi functions on the first preopened directory, returning their errno
+    const GUEST: &str = r#"
+        (module
+            (import "wasi_snapshot_preview1" "path_open"
+                (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "path_unlink_file"
+                (func $path_unlink_file (param i32 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "fd_write"
+                (func $fd_write (param i32 i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "main.py")
+            (data (i32.const 16) "new.py")
+
+            ;; opens main.py with `oflags` and `rights`, storing the fd at 32
+            (func $open (param $path i32) (param $len i32) (param $oflags i32) (param $rights i64)
+                (result i32)
+                (call $path_open (i32.const 3) (i32.const 0) (local.get $path) (local.get $len)
+                    (local.get $oflags) (local.get $rights) (i64.const 0) (i32.const 0)
+                    (i32.const 32)))
+
+            (func (export "read") (result i32)
+                (call $open (i32.const 0) (i32.const 7) (i32.const 0) (i64.const 2)))
+            (func (export "create") (result i32)
+                (call $open (i32.const 16) (i32.const 6) (i32.const 1) (i64.const 64)))
+            (func (export "truncate") (result i32)
+                (call $open (i32.const 0) (i32.const 7) (i32.const 8) (i64.const 64)))
+            (func (export "unlink") (result i32)
+                (call $path_unlink_file (i32.const 3) (i32.const 0) (i32.const 7)))
+
+            ;; opens main.py for writing and writes a single byte to it
+            (func (export "write") (result i32)
+                (local $errno i32)
+                (local.set $errno
+                    (call $open (i32.const 0) (i32.const 7) (i32.const 0) (i64.const 64)))
+                (if (local.get $errno) (then (return (local.get $errno))))
+                (i32.store (i32.const 40) (i32.const 0))
+                (i32.store (i32.const 44) (i32.const 1))
+                (call $fd_write (i32.load (i32.const 32)) (i32.const 40) (i32.const 1)
+                    (i32.const 48)))
+        )
+    "#;
+
+    #[test]
+    fn read_only_dir_rejects_writes() -> anyhow::Result<()> {
+        let host = std::env::temp_dir().join(format!("acm-read-only-{}", std::process::id()));
+        std::fs::create_dir_all(&host)?;
+        std::fs::write(host.join("main.py"), "driver")?;
+
+        let engine = Engine::default();
+        let mut linker = Linker::new(&engine);
+        wasmtime_wasi::add_to_linker(&mut linker, |wasi: &mut WasiCtx| wasi)?;
+
+        let wasi = WasiCtxBuilder::new().build();
+        read_only_dir(&wasi, host.to_str().unwrap(), "/sandbox")?;
+
+        let mut store = Store::new(&engine, wasi);
+        let module = Module::new(&engine, GUEST)?;
+        let instance: Instance = linker.instantiate(&mut store, &module)?;
+        let mut call = |name: &str| -> anyhow::Result<i32> {
+            let func = instance.get_typed_func::<(), i32>(&mut store, name)?;
+            func.call(&mut store, ())
+        };
+
+        assert_eq!(call("read")?, 0);
+        assert_ne!(call("create")?, 0);
+        assert_ne!(call("truncate")?, 0);
+        assert_ne!(call("write")?, 0);
+        assert_ne!(call("unlink")?, 0);
+
+        assert_eq!(std::fs::read_to_string(host.join("main.py"))?, "driver");
+        assert!(!host.join("new.py").exists());
+
+        std::fs::remove_dir_all(&host)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn driver_results_follow_the_nonce() {
+        let nonce = "0123abcd";
+        let stderr = b"{\"Ok\": 1}\nnoise0123abcd{\"Ok\": 2}\n{\"Ok\": 3}\n";
+
+        assert_eq!(driver_result(stderr, nonce), b"{\"Ok\": 2}");
+        assert_eq!(driver_result(b"{\"Ok\": 1}\n", nonce), b"");
+    }
+
+    /// Submissions which write a result to the real stderr and exit before the driver responds
+    #[tokio::test]
+    async fn forged_results_are_rejected() -> anyhow::Result<()> {
+        if !std::path::Path::new(&format!("{PYTHON_PREFIX}/python.wasm")).exists() {
+            eprintln!("skipping, CPython for WASI isn't installed at {PYTHON_PREFIX}");
+            return Ok(());
+        }
+
+        let prefix = std::env::temp_dir().join(format!("acm-forged-{}", std::process::id()));
+        let prefix = prefix.to_str().unwrap();
+        let cancellation = Cancellation::default();
+
+        let forge_import = r#"
+import os, sys
+sys.__stderr__.write('{"Ok": null}\n')
+sys.__stderr__.flush()
+os._exit(0)
+"#;
+        let command = Python
+            .compile(prefix, forge_import, ProblemKind::Function, &[])
+            .await?;
+        let loaded = Python
+            .load(&command, ProblemKind::Function, &cancellation)
+            .await;
+        assert!(matches!(loaded, Err(RunnerError::CompilationError { .. })));
+
+        let forge_result = r#"
+import os, sys
+def solve():
+    sys.__stderr__.write('{"Ok": {"Int": {"Single": 42}}}\n')
+    sys.__stderr__.flush()
+    os._exit(0)
+"#;
+        let command = Python
+            .compile(prefix, forge_result, ProblemKind::Function, &[])
+            .await?;
+        let program = Python
+            .load(&command, ProblemKind::Function, &cancellation)
+            .await?;
+        let call = WasmFunctionCall::new(
+            "solve",
+            vec![],
+            FunctionType::Int(ContainerVariantType::Single),
+        );
+        let executed = Python.execute(&program, call, None).await;
+        assert!(matches!(executed, Err(RunnerError::RuntimeError { .. })));
+
+        std::fs::remove_dir_all(prefix)?;
+
+        Ok(())
+    }
+}
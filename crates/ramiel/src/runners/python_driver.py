@@ -0,0 +1,314 @@
+# Runs a single function call from a Python submission.
+#
+# The first line of stdin is a nonce chosen by ramiel for every run, and the rest holds either
+# `null`, which exits immediately and is used to measure how much fuel starting the interpreter
+# takes, `"check"`, which only imports the submission, `"compile"`, which only checks the
+# submission for syntax errors without running it, or a `WasmFunctionCall`.
+# The result is written to stderr as a serialized `Result` after the nonce, so the submission is
+# free to print, but can't forge a result by writing to stderr itself.
+import builtins
+import collections
+import json
+import os
+import sys
+import traceback
+
+
+def responder(nonce, result_file):
+    def respond(result):
+        result_file.write(nonce + json.dumps(result) + "\n")
+        result_file.flush()
+
+    return respond
+
+
+respond = responder(sys.stdin.readline().strip(), sys.stderr)
+sys.stderr = sys.stdout
+
+call = json.load(sys.stdin)
+if call is None:
+    sys.exit(0)
+
+
+def diagnostic(line, col, message):
+    return {"line": line, "col": col, "diagnostic_type": "Error", "message": message}
+
+
+if call == "compile":
+    path = os.path.join(os.path.dirname(__file__), "solution.py")
+    try:
+        with open(path) as f:
+            compile(f.read(), "solution.py", "exec")
+    except SyntaxError as e:
+        respond({"Err": diagnostic(e.lineno or 1, e.offset or 1, e.msg)})
+    else:
+        respond({"Ok": None})
+    sys.exit(0)
+
+class ListNode:
+    def __init__(self, val=0, next=None):
+        self.val = val
+        self.next = next
+
+
+class TreeNode:
+    def __init__(self, val=0, left=None, right=None):
+        self.val = val
+        self.left = left
+        self.right = right
+
+
+# LeetCode style submissions use these without defining them
+builtins.ListNode = ListNode
+builtins.TreeNode = TreeNode
+
+try:
+    import solution
+except SyntaxError as e:
+    respond({"Err": diagnostic(e.lineno or 1, e.offset or 1, e.msg)})
+    sys.exit(0)
+except Exception:
+    respond({"Err": diagnostic(1, 1, traceback.format_exc())})
+    sys.exit(0)
+
+if call == "check":
+    respond({"Ok": None})
+    sys.exit(0)
+
+LIMITS = {
+    "Int": (-(1 << 31), (1 << 31) - 1),
+    "Long": (-(1 << 63), (1 << 63) - 1),
+    "UInt": (0, (1 << 32) - 1),
+    "ULong": (0, (1 << 64) - 1),
+    "Short": (-(1 << 15), (1 << 15) - 1),
+    "Byte": (0, (1 << 8) - 1),
+    "Int128": (-(1 << 127), (1 << 127) - 1),
+}
+
+
+def convert(kind, value):
+    if kind in LIMITS:
+        low, high = LIMITS[kind]
+        if isinstance(value, bool) or not isinstance(value, int) or not low <= value <= high:
+            raise TypeError(f"expected {kind.lower()}, got {value!r}")
+        return value
+    if kind in ("Float", "Double"):
+        if isinstance(value, bool) or not isinstance(value, (int, float)):
+            raise TypeError(f"expected {kind.lower()}, got {value!r}")
+        return float(value)
+    if kind == "Char":
+        if not isinstance(value, str) or len(value) != 1:
+            raise TypeError(f"expected a single character, got {value!r}")
+        return value
+    if kind == "String":
+        if not isinstance(value, str):
+            raise TypeError(f"expected str, got {value!r}")
+        return value
+    if kind == "Bool":
+        if not isinstance(value, bool):
+            raise TypeError(f"expected bool, got {value!r}")
+        return value
+    raise TypeError(f"unsupported type {kind}")
+
+
+def map_container(container, data, f):
+    if container == "Single":
+        return f(data)
+    if container == "List":
+        return [f(x) for x in data]
+    return [[f(x) for x in row] for row in data]
+
+
+def from_container(variant, f):
+    # {"List": [1, 2]} -> [f(1), f(2)], where a nested vector holds its element type and elements,
+    # e.g. {"Vector": ["List", [{"List": [1]}, {"List": [2, 3]}]]}
+    ((container, data),) = variant.items()
+    if container == "Vector":
+        _, elements = data
+        return [from_container(element, f) for element in elements]
+    return map_container(container, data, f)
+
+
+def into_container(container, result, f):
+    # the inverse of from_container, where nested vectors have the type {"Vector": inner}
+    if isinstance(container, dict):
+        inner = container["Vector"]
+        return {"Vector": [inner, [into_container(inner, x, f) for x in result]]}
+    return {container: map_container(container, result, f)}
+
+
+STRUCT_CLASSES = {}
+
+
+def from_struct(struct_type, fields):
+    # pairs and tuples become tuples, and named structs become namedtuples
+    values = tuple(from_value(field) for field in fields)
+    kind = struct_type["kind"]
+    if not isinstance(kind, dict):
+        return values
+
+    name, field_names = kind["Named"]["name"], kind["Named"]["field_names"]
+    if name not in STRUCT_CLASSES:
+        STRUCT_CLASSES[name] = collections.namedtuple(name, field_names)
+    return STRUCT_CLASSES[name](*values)
+
+
+def into_struct(struct_type, result):
+    kind = struct_type["kind"]
+    if isinstance(result, (tuple, list)):
+        values = list(result)
+    elif isinstance(kind, dict):
+        field_names = kind["Named"]["field_names"]
+        if isinstance(result, dict):
+            values = [result[name] for name in field_names]
+        else:
+            values = [getattr(result, name) for name in field_names]
+    else:
+        raise TypeError(f"expected a tuple, got {result!r}")
+
+    if len(values) != len(struct_type["fields"]):
+        raise TypeError(f"expected {len(struct_type['fields'])} fields, got {result!r}")
+    return [into_value(value, ty) for value, ty in zip(values, struct_type["fields"])]
+
+
+def from_set(keys):
+    return {from_value(key) for key in keys}
+
+
+def from_map(entries):
+    return {from_value(key): from_value(value) for key, value in entries}
+
+
+def into_set(set_type, result):
+    if not isinstance(result, (set, frozenset, list, tuple)):
+        raise TypeError(f"expected a set, got {result!r}")
+    return [into_value(key, set_type["key"]) for key in result]
+
+
+def into_map(map_type, result):
+    if not isinstance(result, dict):
+        raise TypeError(f"expected a dict, got {result!r}")
+    return [
+        [into_value(key, map_type["key"]), into_value(value, map_type["value"])]
+        for key, value in result.items()
+    ]
+
+
+def from_list(values):
+    head = None
+    for val in reversed(values):
+        head = ListNode(val, head)
+    return head
+
+
+def from_tree(values):
+    # level order, where each node takes the next two values as its children
+    if not values or values[0] is None:
+        return None
+
+    root = TreeNode(values[0])
+    queue = collections.deque([root])
+    children = iter(values[1:])
+    while queue:
+        node = queue.popleft()
+        for side in ("left", "right"):
+            val = next(children, None)
+            if val is not None:
+                child = TreeNode(val)
+                setattr(node, side, child)
+                queue.append(child)
+    return root
+
+
+def into_list(head):
+    values = []
+    visited = set()
+    while head is not None:
+        if id(head) in visited:
+            raise ValueError("the returned linked list has a cycle")
+        visited.add(id(head))
+        values.append(convert("Int", head.val))
+        head = head.next
+    return values
+
+
+def into_tree(root):
+    values = []
+    visited = set()
+    queue = collections.deque([root])
+    while queue:
+        node = queue.popleft()
+        if node is None:
+            values.append(None)
+            continue
+        if id(node) in visited:
+            raise ValueError("the returned tree has a cycle or a node with more than one parent")
+        visited.add(id(node))
+        values.append(convert("Int", node.val))
+        queue.extend((node.left, node.right))
+
+    while values and values[-1] is None:
+        values.pop()
+    return values
+
+
+NODES = {
+    "LinkedList": (from_list, into_list),
+    "BinaryTree": (from_tree, into_tree),
+}
+
+
+def from_value(value):
+    # {"Int": {"List": [1, 2]}} -> [1, 2]
+    ((kind, variant),) = value.items()
+    if kind == "Struct":
+        struct_type, variant = variant
+        return from_container(variant, lambda fields: from_struct(struct_type, fields))
+    if kind in ("Set", "Map"):
+        _, variant = variant
+        return from_container(variant, from_set if kind == "Set" else from_map)
+    if kind in NODES:
+        return from_container(variant, NODES[kind][0])
+
+    return from_container(variant, lambda x: x)
+
+
+def into_value(result, return_type):
+    ((kind, variant),) = return_type.items()
+
+    if kind == "Struct":
+        struct_type, container = variant
+        into = lambda x: into_struct(struct_type, x)
+        return {kind: [struct_type, into_container(container, result, into)]}
+    if kind in ("Set", "Map"):
+        ty, container = variant
+        into = into_set if kind == "Set" else into_map
+        return {kind: [ty, into_container(container, result, lambda x: into(ty, x))]}
+    if kind in NODES:
+        return {kind: into_container(variant, result, NODES[kind][1])}
+
+    return {kind: into_container(variant, result, lambda x: convert(kind, x))}
+
+
+def find_function(name):
+    if hasattr(solution, name):
+        return getattr(solution, name)
+
+    # LeetCode style submissions wrap their functions in a class
+    if hasattr(solution, "Solution") and hasattr(solution.Solution, name):
+        return getattr(solution.Solution(), name)
+
+    raise NameError(f'Expected a function with name "{name}", but it was not found.')
+
+
+try:
+    function = find_function(call["name"])
+except NameError as e:
+    respond({"Err": str(e)})
+    sys.exit(0)
+
+try:
+    result = function(*[from_value(argument) for argument in call["arguments"]])
+    respond({"Ok": into_value(result, call["return_type"])})
+except Exception:
+    respond({"Err": traceback.format_exc(limit=-1)})
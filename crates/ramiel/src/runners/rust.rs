@@ -0,0 +1,462 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use shared::models::runner::{Diagnostic, DiagnosticType, ProblemKind, RunnerError};
+use std::collections::BTreeMap;
+use tokio::{
+    fs::{self, File},
+    io::AsyncWriteExt,
+    process::Command,
+};
+use wasm_memory::{ContainerVariantType, FunctionType, StructKind, StructType, WasmFunctionCall};
+
+use super::{
+    cache::{cache, compiler_version, CompileCache},
+    Runner,
+};
+
+/// Compiles submissions written in Rust.
+///
+/// The judge passes arguments using libc++'s memory layout, so for every function that is called
+/// we generate an exported wrapper which converts its arguments from `std::vector`/`std::string`
+/// into `Vec<T>`/`String` before calling the user's function, and converts the result back.
+pub struct Rust;
+
+const COMPILER: &str = "rustc";
+
+const FLAGS: [&str; 8] = [
+    "--edition=2021",
+    "--crate-type=cdylib",
+    "--target=wasm32-wasi",
+    "--error-format=json",
+    "-C",
+    "opt-level=3",
+    // the judge allocates arguments through `malloc`, which is otherwise not exported
+    "-C",
+    "link-arg=--export=malloc",
+];
+
+/// Flags for [`ProblemKind::Interactive`] problems, where functions declared in `extern "C"`
+/// blocks are imported from the interactor.
+const INTERACTIVE_FLAGS: [&str; 10] = [
+    "--edition=2021",
+    "--crate-type=cdylib",
+    "--target=wasm32-wasi",
+    "--error-format=json",
+    "-C",
+    "opt-level=3",
+    "-C",
+    "link-arg=--export=malloc",
+    "-C",
+    "link-arg=--allow-undefined",
+];
+
+/// Flags for [`ProblemKind::Stdio`] problems, which are compiled to command modules with a `main`.
+const COMMAND_FLAGS: [&str; 6] = [
+    "--edition=2021",
+    "--crate-type=bin",
+    "--target=wasm32-wasi",
+    "--error-format=json",
+    "-C",
+    "opt-level=3",
+];
+
+#[async_trait]
+impl Runner for Rust {
+    fn name(&self) -> &'static str {
+        "rust"
+    }
+
+    async fn compile(
+        &self,
+        prefix: &str,
+        source: &str,
+        kind: ProblemKind,
+        calls: &[&WasmFunctionCall],
+    ) -> Result<String, RunnerError> {
+        // Programs read their input themselves, so there is nothing to convert
+        if kind == ProblemKind::Stdio {
+            let user_lines = 1..=source.lines().count();
+            return compile_problem(prefix, source, user_lines, &COMMAND_FLAGS).await;
+        }
+
+        // The arguments of interactive problems are only given to the interactor
+        let interactive_calls = calls
+            .iter()
+            .map(|call| WasmFunctionCall::new(&call.name, vec![], call.return_type.clone()))
+            .collect::<Vec<_>>();
+        let (calls, flags) = match kind {
+            ProblemKind::Interactive => (
+                interactive_calls.iter().collect::<Vec<_>>(),
+                &INTERACTIVE_FLAGS[..],
+            ),
+            _ => (calls.to_vec(), &FLAGS[..]),
+        };
+
+        let prelude = include_str!("rust_prelude.rs");
+
+        let mut file = String::new();
+        file.push_str(prelude);
+        file.push_str(source);
+        file.push('\n');
+        file.push_str(&generate_exports(&calls));
+
+        let offset = prelude.lines().count();
+        let user_lines = offset + 1..=offset + source.lines().count();
+
+        compile_problem(prefix, &file, user_lines, flags).await
+    }
+}
+
+/// Returns the Rust type a value of type `ty` is converted to, e.g. `Vec<i32>`.
+fn rust_type(ty: &FunctionType) -> String {
+    let (inner, variant) = match ty {
+        FunctionType::String(v) => ("String".to_string(), v),
+        FunctionType::Int(v) => ("i32".to_string(), v),
+        FunctionType::Long(v) => ("i64".to_string(), v),
+        FunctionType::Float(v) => ("f32".to_string(), v),
+        FunctionType::Double(v) => ("f64".to_string(), v),
+        FunctionType::Char(v) => ("char".to_string(), v),
+        FunctionType::Bool(v) => ("bool".to_string(), v),
+        FunctionType::UInt(v) => ("u32".to_string(), v),
+        FunctionType::ULong(v) => ("u64".to_string(), v),
+        FunctionType::Short(v) => ("i16".to_string(), v),
+        FunctionType::Byte(v) => ("u8".to_string(), v),
+        FunctionType::Int128(v) => ("i128".to_string(), v),
+        FunctionType::Struct(ty, v) => (struct_type(ty), v),
+        FunctionType::Set(ty, v) if ty.unordered => (
+            format!("std::collections::HashSet<{}>", rust_type(&ty.key)),
+            v,
+        ),
+        FunctionType::Set(ty, v) => (
+            format!("std::collections::BTreeSet<{}>", rust_type(&ty.key)),
+            v,
+        ),
+        FunctionType::Map(ty, v) => (
+            format!(
+                "std::collections::{}<{}, {}>",
+                if ty.unordered { "HashMap" } else { "BTreeMap" },
+                rust_type(&ty.key),
+                rust_type(&ty.value)
+            ),
+            v,
+        ),
+        FunctionType::LinkedList(v) => ("Option<Box<super::ListNode>>".to_string(), v),
+        FunctionType::BinaryTree(v) => (
+            "Option<std::rc::Rc<std::cell::RefCell<super::TreeNode>>>".to_string(),
+            v,
+        ),
+    };
+
+    container_type(inner, variant)
+}
+
+/// Wraps `inner` in the `Vec`s of `variant`, e.g. `Vec<Vec<Vec<i32>>>` for a vector of grids.
+fn container_type(inner: String, variant: &ContainerVariantType) -> String {
+    match variant {
+        ContainerVariantType::Single => inner,
+        ContainerVariantType::Graph => format!("Vec<Vec<{inner}>>"),
+        ContainerVariantType::Vector(ty) => format!("Vec<{}>", container_type(inner, ty)),
+    }
+}
+
+/// Pairs and tuples become Rust tuples, and named structs refer to a struct with the same name
+/// declared by the submission, e.g. `super::Point`.
+fn struct_type(ty: &StructType) -> String {
+    match &ty.kind {
+        StructKind::Named { name, .. } => format!("super::{name}"),
+        StructKind::Pair | StructKind::Tuple => tuple_type(&ty.fields),
+    }
+}
+
+fn tuple_type(fields: &[FunctionType]) -> String {
+    match fields {
+        [field] => format!("({},)", rust_type(field)),
+        fields => format!(
+            "({})",
+            fields.iter().map(rust_type).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+/// Collects every named struct used by `ty`, including the ones nested in other structs, sets
+/// and maps.
+fn named_structs(ty: &FunctionType, structs: &mut BTreeMap<String, StructType>) {
+    match ty {
+        FunctionType::Struct(struct_type, _) => {
+            if let StructKind::Named { name, .. } = &struct_type.kind {
+                structs.insert(name.clone(), (**struct_type).clone());
+            }
+
+            for field in &struct_type.fields {
+                named_structs(field, structs);
+            }
+        }
+        FunctionType::Set(set, _) => named_structs(&set.key, structs),
+        FunctionType::Map(map, _) => {
+            named_structs(&map.key, structs);
+            named_structs(&map.value, structs);
+        }
+        _ => {}
+    }
+}
+
+/// Implements `Abi` for a struct declared by the submission by converting it to and from a tuple
+/// of its fields, which has the same layout.
+fn generate_struct_abi(ty: &StructType) -> String {
+    let StructKind::Named { name, field_names } = &ty.kind else {
+        return String::new();
+    };
+
+    let tuple = tuple_type(&ty.fields);
+    let fields = field_names.join(", ");
+    let self_fields = field_names
+        .iter()
+        .map(|field| format!("self.{field}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let trailing_comma = if field_names.len() == 1 { "," } else { "" };
+
+    format!(
+        "
+    impl Abi for super::{name} {{
+        type Repr = <{tuple} as Abi>::Repr;
+
+        unsafe fn from_repr(repr: *const Self::Repr) -> Self {{
+            let ({fields}{trailing_comma}) = <{tuple} as Abi>::from_repr(repr);
+            super::{name} {{ {fields} }}
+        }}
+
+        fn into_repr(self) -> Self::Repr {{
+            ({self_fields}{trailing_comma}).into_repr()
+        }}
+    }}
+"
+    )
+}
+
+/// Returns the wasm type used to pass a value of type `ty` directly, or `None` if it is passed by
+/// pointer instead.
+fn scalar_type(ty: &FunctionType) -> Option<&'static str> {
+    match ty {
+        FunctionType::Int(ContainerVariantType::Single)
+        | FunctionType::Char(ContainerVariantType::Single)
+        | FunctionType::Bool(ContainerVariantType::Single)
+        | FunctionType::UInt(ContainerVariantType::Single)
+        | FunctionType::Short(ContainerVariantType::Single)
+        | FunctionType::Byte(ContainerVariantType::Single) => Some("i32"),
+        FunctionType::Long(ContainerVariantType::Single)
+        | FunctionType::ULong(ContainerVariantType::Single) => Some("i64"),
+        FunctionType::Float(ContainerVariantType::Single) => Some("f32"),
+        FunctionType::Double(ContainerVariantType::Single) => Some("f64"),
+        _ => None,
+    }
+}
+
+/// Whether a value of type `ty` is passed as the pointer that represents it, e.g. a `ListNode*`.
+fn is_node(ty: &FunctionType) -> bool {
+    matches!(
+        ty,
+        FunctionType::LinkedList(ContainerVariantType::Single)
+            | FunctionType::BinaryTree(ContainerVariantType::Single)
+    )
+}
+
+/// Generates an exported wrapper around every distinct function in `calls`.
+fn generate_exports(calls: &[&WasmFunctionCall]) -> String {
+    let functions = calls
+        .iter()
+        .map(|call| (call.name.as_str(), *call))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut exports = String::new();
+    exports.push_str("mod __acm_exports {\n");
+    exports.push_str("    use super::__acm::Abi;\n");
+
+    let mut structs = BTreeMap::new();
+    for call in functions.values() {
+        named_structs(&call.return_type, &mut structs);
+        for argument in &call.arguments {
+            named_structs(&argument.function_type(), &mut structs);
+        }
+    }
+    for ty in structs.values() {
+        exports.push_str(&generate_struct_abi(ty));
+    }
+
+    for (name, call) in functions {
+        let mut params = vec![];
+        let mut args = vec![];
+
+        let return_type = rust_type(&call.return_type);
+        let return_scalar = scalar_type(&call.return_type);
+
+        if return_scalar.is_none() && !is_node(&call.return_type) {
+            params.push(format!("__ret: *mut <{return_type} as Abi>::Repr"));
+        }
+
+        for (i, argument) in call.arguments.iter().enumerate() {
+            let ty = argument.function_type();
+            let rust_ty = rust_type(&ty);
+
+            match (&ty, scalar_type(&ty)) {
+                (FunctionType::Char(_), Some(scalar)) => {
+                    params.push(format!("arg{i}: {scalar}"));
+                    args.push(format!("arg{i} as u8 as char"));
+                }
+                (FunctionType::Bool(_), Some(scalar)) => {
+                    params.push(format!("arg{i}: {scalar}"));
+                    args.push(format!("arg{i} != 0"));
+                }
+                (_, Some(scalar)) if scalar == rust_ty => {
+                    params.push(format!("arg{i}: {scalar}"));
+                    args.push(format!("arg{i}"));
+                }
+                // e.g. a `u8` is passed as an `i32`
+                (_, Some(scalar)) => {
+                    params.push(format!("arg{i}: {scalar}"));
+                    args.push(format!("arg{i} as {rust_ty}"));
+                }
+                // `__int128`s are passed as their low and high halves
+                (FunctionType::Int128(ContainerVariantType::Single), None) => {
+                    params.push(format!("arg{i}_low: i64, arg{i}_high: i64"));
+                    args.push(format!(
+                        "((arg{i}_high as i128) << 64 | arg{i}_low as u64 as i128)"
+                    ));
+                }
+                (ty, None) if is_node(ty) => {
+                    params.push(format!("arg{i}: <{rust_ty} as Abi>::Repr"));
+                    args.push(format!("<{rust_ty} as Abi>::from_repr(&arg{i})"));
+                }
+                (_, None) => {
+                    params.push(format!("arg{i}: *const <{rust_ty} as Abi>::Repr"));
+                    args.push(format!("<{rust_ty} as Abi>::from_repr(arg{i})"));
+                }
+            }
+        }
+
+        let invocation = format!("super::{name}({})", args.join(", "));
+        let (result, body) = match (&call.return_type, return_scalar) {
+            (_, Some(scalar)) if scalar == return_type => (format!(" -> {scalar}"), invocation),
+            (_, Some(scalar)) => (format!(" -> {scalar}"), format!("{invocation} as {scalar}")),
+            (ty, None) if is_node(ty) => (
+                format!(" -> <{return_type} as Abi>::Repr"),
+                format!("let result: {return_type} = {invocation};\n        result.into_repr()"),
+            ),
+            (_, None) => (
+                String::new(),
+                format!(
+                    "let result: {return_type} = {invocation};\n        __ret.write(result.into_repr());"
+                ),
+            ),
+        };
+
+        exports.push_str(&format!(
+            "\n    #[no_mangle]\n    pub unsafe extern \"C\" fn {name}({}){result} {{\n        {body}\n    }}\n",
+            params.join(", ")
+        ));
+    }
+
+    exports.push_str("}\n");
+    exports
+}
+
+async fn compile_problem(
+    prefix: &str,
+    implementation: &str,
+    user_lines: std::ops::RangeInclusive<usize>,
+    flags: &[&str],
+) -> Result<String, RunnerError> {
+    let wasm_filename = format!("{prefix}/out.wasm");
+    let implementation_filename = format!("{prefix}/implementation.rs");
+
+    let version = compiler_version(COMPILER).await;
+    let key = CompileCache::key(&["rust", &version, &flags.join(" "), implementation]);
+    if let Some(wasm_filename) = cache().get(&key, &wasm_filename) {
+        return Ok(wasm_filename);
+    }
+
+    fs::create_dir_all(prefix).await?;
+
+    File::create(&implementation_filename)
+        .await?
+        .write_all(implementation.as_bytes())
+        .await?;
+
+    let output = Command::new(COMPILER)
+        .args(flags)
+        .args([&implementation_filename, "-o", &wasm_filename])
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        fs::remove_file(&wasm_filename).await.ok();
+
+        return Err(parse_rust_error(
+            &String::from_utf8_lossy(&output.stderr),
+            user_lines,
+        ));
+    }
+
+    cache().insert(&key, &wasm_filename).await
+}
+
+#[derive(Deserialize)]
+struct RustcDiagnostic {
+    message: String,
+    level: String,
+    spans: Vec<RustcSpan>,
+}
+
+#[derive(Deserialize)]
+struct RustcSpan {
+    line_start: usize,
+    column_start: usize,
+    is_primary: bool,
+}
+
+/// Parses rustc's `--error-format=json` output. Lines are offset so they are relative to the
+/// user's code. Errors in the generated exports almost always mean the user's function has the
+/// wrong signature, so those are reported on the first line.
+fn parse_rust_error(err: &str, user_lines: std::ops::RangeInclusive<usize>) -> RunnerError {
+    let mut diagnostics = vec![];
+
+    for line in err.lines() {
+        let diagnostic: RustcDiagnostic = match serde_json::from_str(line) {
+            Ok(diagnostic) => diagnostic,
+            Err(_) => continue,
+        };
+
+        let span = match diagnostic.spans.iter().find(|span| span.is_primary) {
+            Some(span) => span,
+            None => continue,
+        };
+
+        let diagnostic_type = match diagnostic.level.as_str() {
+            "error" => DiagnosticType::Error,
+            "warning" => DiagnosticType::Warning,
+            _ => DiagnosticType::Note,
+        };
+
+        if user_lines.contains(&span.line_start) {
+            diagnostics.push(Diagnostic {
+                line: span.line_start - user_lines.start() + 1,
+                col: span.column_start,
+                message: diagnostic.message,
+                diagnostic_type,
+            });
+        } else if span.line_start > *user_lines.end() {
+            diagnostics.push(Diagnostic {
+                line: 1,
+                col: 1,
+                message: format!(
+                    "{} (while calling your function, check that its signature matches the problem)",
+                    diagnostic.message
+                ),
+                diagnostic_type,
+            });
+        }
+    }
+
+    RunnerError::CompilationError { diagnostics }
+}
@@ -0,0 +1,434 @@
+#[allow(dead_code)]
+mod __acm {
+    use std::cell::RefCell;
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+    use std::hash::Hash;
+    use std::mem::ManuallyDrop;
+    use std::rc::Rc;
+
+    /// Mirrors libc++'s `std::vector<T>`, which is how the judge passes lists to submissions.
+    #[repr(C)]
+    pub struct CppVec<T> {
+        begin: *mut T,
+        end: *mut T,
+        capacity: *mut T,
+    }
+
+    /// Mirrors the long form of libc++'s `std::string`.
+    #[repr(C)]
+    pub struct CppString {
+        data: *mut u8,
+        size: usize,
+        capacity: usize,
+    }
+
+    /// Converts between Rust types and the C++ layout used by the judge.
+    pub trait Abi: Sized {
+        type Repr;
+
+        unsafe fn from_repr(repr: *const Self::Repr) -> Self;
+        fn into_repr(self) -> Self::Repr;
+    }
+
+    macro_rules! impl_scalar_abi {
+        ($($t:ty),*) => {
+            $(
+                impl Abi for $t {
+                    type Repr = $t;
+
+                    unsafe fn from_repr(repr: *const $t) -> $t {
+                        repr.read()
+                    }
+
+                    fn into_repr(self) -> $t {
+                        self
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_scalar_abi!(i32, i64, f32, f64, bool, u32, u64, i16, u8, i128);
+
+    /// C++ chars are a single byte
+    impl Abi for char {
+        type Repr = u8;
+
+        unsafe fn from_repr(repr: *const u8) -> char {
+            repr.read() as char
+        }
+
+        fn into_repr(self) -> u8 {
+            self as u8
+        }
+    }
+
+    impl<T: Abi> Abi for Vec<T> {
+        type Repr = CppVec<T::Repr>;
+
+        unsafe fn from_repr(repr: *const CppVec<T::Repr>) -> Vec<T> {
+            let repr = &*repr;
+            let len = repr.end.offset_from(repr.begin) as usize;
+
+            (0..len).map(|i| T::from_repr(repr.begin.add(i))).collect()
+        }
+
+        fn into_repr(self) -> CppVec<T::Repr> {
+            // The judge never frees return values, so the buffer is intentionally leaked.
+            let mut elements =
+                ManuallyDrop::new(self.into_iter().map(T::into_repr).collect::<Vec<_>>());
+            let begin = elements.as_mut_ptr();
+
+            unsafe {
+                CppVec {
+                    begin,
+                    end: begin.add(elements.len()),
+                    capacity: begin.add(elements.capacity()),
+                }
+            }
+        }
+    }
+
+    /// Mirrors `std::pair` and `std::tuple`, whose elements are laid out like the fields of a C
+    /// struct.
+    macro_rules! impl_tuple_abi {
+        ($repr:ident, $($t:ident $i:tt),*) => {
+            #[repr(C)]
+            pub struct $repr<$($t),*>($(pub $t),*);
+
+            impl<$($t: Abi),*> Abi for ($($t,)*) {
+                type Repr = $repr<$(<$t as Abi>::Repr),*>;
+
+                unsafe fn from_repr(repr: *const Self::Repr) -> Self {
+                    let repr = &*repr;
+                    ($(<$t as Abi>::from_repr(&repr.$i),)*)
+                }
+
+                fn into_repr(self) -> Self::Repr {
+                    $repr($(self.$i.into_repr()),*)
+                }
+            }
+        };
+    }
+
+    impl_tuple_abi!(CppTuple1, A 0);
+    impl_tuple_abi!(CppTuple2, A 0, B 1);
+    impl_tuple_abi!(CppTuple3, A 0, B 1, C 2);
+    impl_tuple_abi!(CppTuple4, A 0, B 1, C 2, D 3);
+    impl_tuple_abi!(CppTuple5, A 0, B 1, C 2, D 3, E 4);
+    impl_tuple_abi!(CppTuple6, A 0, B 1, C 2, D 3, E 4, F 5);
+
+    /// Mirrors a node of libc++'s `std::__tree`, which backs `std::set` and `std::map`.
+    #[repr(C)]
+    pub struct CppTreeNode<T> {
+        left: *mut CppTreeNode<T>,
+        right: *mut CppTreeNode<T>,
+        parent: *mut CppTreeNode<T>,
+        is_black: bool,
+        value: T,
+    }
+
+    /// Mirrors libc++'s `std::__tree`. `root` is the only field of its end node.
+    #[repr(C)]
+    pub struct CppTree<T> {
+        begin: *mut CppTreeNode<T>,
+        root: *mut CppTreeNode<T>,
+        size: usize,
+    }
+
+    /// Mirrors a node of libc++'s `std::__hash_table`, which backs `std::unordered_set` and
+    /// `std::unordered_map`.
+    #[repr(C)]
+    pub struct CppHashNode<T> {
+        next: *mut CppHashNode<T>,
+        hash: usize,
+        value: T,
+    }
+
+    /// Mirrors libc++'s `std::__hash_table`. `first` is the only field of the node before the
+    /// first node.
+    #[repr(C)]
+    pub struct CppHashTable<T> {
+        buckets: *mut *mut CppHashNode<T>,
+        bucket_count: usize,
+        first: *mut CppHashNode<T>,
+        size: usize,
+        max_load_factor: f32,
+    }
+
+    unsafe fn tree_elements<T: Abi>(repr: *const CppTree<T::Repr>) -> Vec<T> {
+        let mut elements = vec![];
+        let mut stack = vec![];
+        let mut node = (*repr).root;
+
+        while !node.is_null() || !stack.is_empty() {
+            while !node.is_null() {
+                stack.push(node);
+                node = (*node).left;
+            }
+
+            let next = stack.pop().unwrap();
+            elements.push(T::from_repr(&(*next).value));
+            node = (*next).right;
+        }
+
+        elements
+    }
+
+    /// The judge only follows the links between nodes when reading a returned tree, so the nodes
+    /// are simply linked through their right children.
+    fn tree_from_elements<T: Abi>(elements: impl DoubleEndedIterator<Item = T>) -> CppTree<T::Repr> {
+        let mut root = std::ptr::null_mut();
+        let mut size = 0;
+
+        for element in elements.rev() {
+            root = Box::into_raw(Box::new(CppTreeNode {
+                left: std::ptr::null_mut(),
+                right: root,
+                parent: std::ptr::null_mut(),
+                is_black: true,
+                value: element.into_repr(),
+            }));
+            size += 1;
+        }
+
+        CppTree {
+            begin: root,
+            root,
+            size,
+        }
+    }
+
+    unsafe fn hash_table_elements<T: Abi>(repr: *const CppHashTable<T::Repr>) -> Vec<T> {
+        let mut elements = vec![];
+        let mut node = (*repr).first;
+
+        while !node.is_null() {
+            elements.push(T::from_repr(&(*node).value));
+            node = (*node).next;
+        }
+
+        elements
+    }
+
+    /// Like trees, returned hash tables only need the links between their nodes.
+    fn hash_table_from_elements<T: Abi>(elements: impl Iterator<Item = T>) -> CppHashTable<T::Repr> {
+        let mut first = std::ptr::null_mut();
+        let mut size = 0;
+
+        for element in elements {
+            first = Box::into_raw(Box::new(CppHashNode {
+                next: first,
+                hash: 0,
+                value: element.into_repr(),
+            }));
+            size += 1;
+        }
+
+        CppHashTable {
+            buckets: std::ptr::null_mut(),
+            bucket_count: 0,
+            first,
+            size,
+            max_load_factor: 1.0,
+        }
+    }
+
+    impl<T: Abi + Ord> Abi for BTreeSet<T> {
+        type Repr = CppTree<T::Repr>;
+
+        unsafe fn from_repr(repr: *const Self::Repr) -> Self {
+            tree_elements(repr).into_iter().collect()
+        }
+
+        fn into_repr(self) -> Self::Repr {
+            tree_from_elements(self.into_iter())
+        }
+    }
+
+    /// Map nodes hold a `std::pair<const K, V>`
+    impl<K: Abi + Ord, V: Abi> Abi for BTreeMap<K, V> {
+        type Repr = CppTree<<(K, V) as Abi>::Repr>;
+
+        unsafe fn from_repr(repr: *const Self::Repr) -> Self {
+            tree_elements::<(K, V)>(repr).into_iter().collect()
+        }
+
+        fn into_repr(self) -> Self::Repr {
+            tree_from_elements(self.into_iter())
+        }
+    }
+
+    impl<T: Abi + Eq + Hash> Abi for HashSet<T> {
+        type Repr = CppHashTable<T::Repr>;
+
+        unsafe fn from_repr(repr: *const Self::Repr) -> Self {
+            hash_table_elements(repr).into_iter().collect()
+        }
+
+        fn into_repr(self) -> Self::Repr {
+            hash_table_from_elements(self.into_iter())
+        }
+    }
+
+    impl<K: Abi + Eq + Hash, V: Abi> Abi for HashMap<K, V> {
+        type Repr = CppHashTable<<(K, V) as Abi>::Repr>;
+
+        unsafe fn from_repr(repr: *const Self::Repr) -> Self {
+            hash_table_elements::<(K, V)>(repr).into_iter().collect()
+        }
+
+        fn into_repr(self) -> Self::Repr {
+            hash_table_from_elements(self.into_iter())
+        }
+    }
+
+    /// Mirrors the `ListNode` given to C++ submissions.
+    #[repr(C)]
+    pub struct CppListNode {
+        val: i32,
+        next: *mut CppListNode,
+    }
+
+    /// Mirrors the `TreeNode` given to C++ submissions.
+    #[repr(C)]
+    pub struct CppBinaryTreeNode {
+        val: i32,
+        left: *mut CppBinaryTreeNode,
+        right: *mut CppBinaryTreeNode,
+    }
+
+    /// Lists are passed as a pointer to their head, which is null if they're empty
+    impl Abi for Option<Box<super::ListNode>> {
+        type Repr = *mut CppListNode;
+
+        unsafe fn from_repr(repr: *const Self::Repr) -> Self {
+            let mut values = vec![];
+            let mut node = *repr;
+
+            while !node.is_null() {
+                values.push((*node).val);
+                node = (*node).next;
+            }
+
+            // lists can be long, so they're built back to front instead of recursively
+            values
+                .into_iter()
+                .rev()
+                .fold(None, |next, val| Some(Box::new(super::ListNode { val, next })))
+        }
+
+        fn into_repr(self) -> Self::Repr {
+            let mut values = vec![];
+            let mut node = self;
+
+            while let Some(current) = node {
+                values.push(current.val);
+                node = current.next;
+            }
+
+            values.into_iter().rev().fold(std::ptr::null_mut(), |next, val| {
+                Box::into_raw(Box::new(CppListNode { val, next }))
+            })
+        }
+    }
+
+    /// Trees are passed as a pointer to their root, which is null if they're empty
+    impl Abi for Option<Rc<RefCell<super::TreeNode>>> {
+        type Repr = *mut CppBinaryTreeNode;
+
+        unsafe fn from_repr(repr: *const Self::Repr) -> Self {
+            let node = *repr;
+            if node.is_null() {
+                return None;
+            }
+
+            Some(Rc::new(RefCell::new(super::TreeNode {
+                val: (*node).val,
+                left: Self::from_repr(&(*node).left),
+                right: Self::from_repr(&(*node).right),
+            })))
+        }
+
+        /// Nodes shared by more than one parent are copied, because the judge rejects them
+        fn into_repr(self) -> Self::Repr {
+            match self {
+                Some(node) => {
+                    let node = node.borrow();
+                    Box::into_raw(Box::new(CppBinaryTreeNode {
+                        val: node.val,
+                        left: node.left.clone().into_repr(),
+                        right: node.right.clone().into_repr(),
+                    }))
+                }
+                None => std::ptr::null_mut(),
+            }
+        }
+    }
+
+    impl Abi for String {
+        type Repr = CppString;
+
+        unsafe fn from_repr(repr: *const CppString) -> String {
+            let bytes = repr as *const u8;
+
+            // Short strings are stored inline, which is signaled by the high bit of the last byte.
+            if *bytes.add(11) & 0b1000_0000 == 0 {
+                let len = (0..11).position(|i| *bytes.add(i) == 0).unwrap_or(11);
+                String::from_utf8_lossy(std::slice::from_raw_parts(bytes, len)).into_owned()
+            } else {
+                let repr = &*repr;
+                String::from_utf8_lossy(std::slice::from_raw_parts(repr.data, repr.size))
+                    .into_owned()
+            }
+        }
+
+        fn into_repr(self) -> CppString {
+            let size = self.len();
+            let mut bytes = ManuallyDrop::new(self.into_bytes());
+            bytes.push(0);
+
+            // Capacity must be even, and the high bit marks this as a long string.
+            let capacity = (bytes.len() + bytes.len() % 2) | (1 << 31);
+
+            CppString {
+                data: bytes.as_mut_ptr(),
+                size,
+                capacity,
+            }
+        }
+    }
+}
+
+// LeetCode's definitions of linked list and binary tree nodes, which submissions use without
+// declaring them
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ListNode {
+    pub val: i32,
+    pub next: Option<Box<ListNode>>,
+}
+
+impl ListNode {
+    #[inline]
+    pub fn new(val: i32) -> Self {
+        ListNode { next: None, val }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TreeNode {
+    pub val: i32,
+    pub left: Option<std::rc::Rc<std::cell::RefCell<TreeNode>>>,
+    pub right: Option<std::rc::Rc<std::cell::RefCell<TreeNode>>>,
+}
+
+impl TreeNode {
+    #[inline]
+    pub fn new(val: i32) -> Self {
+        TreeNode {
+            val,
+            left: None,
+            right: None,
+        }
+    }
+}
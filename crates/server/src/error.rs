@@ -120,6 +120,10 @@ impl IntoResponse for UserError {
 pub enum FormValidationError {
     #[error("Invalid field: {0}")]
     InvalidField(String),
+
+    /// The tests of a problem don't match the function in its reference solution
+    #[error("{0}")]
+    Signature(String),
 }
 
 impl IntoResponse for FormValidationError {
@@ -129,6 +133,7 @@ impl IntoResponse for FormValidationError {
                 StatusCode::BAD_REQUEST,
                 format!("Field '{field}' is invalid"),
             ),
+            FormValidationError::Signature(error) => (StatusCode::BAD_REQUEST, error),
         };
 
         let body = Json(json!({
@@ -1,7 +1,9 @@
 use axum::{extract::Path, Extension, Json};
 
 use serde::Deserialize;
+use shared::models::runner::{OutputNormalization, ProblemKind};
 use sqlx::SqlitePool;
+use wasm_memory::{ComparisonMode, FloatTolerance};
 
 use super::Difficulty;
 use crate::{auth::Claims, error::ServerError};
@@ -14,6 +16,14 @@ pub struct EditForm {
     runtime_multiplier: f64,
     difficulty: Difficulty,
     visible: bool,
+    kind: Option<ProblemKind>,
+    output_normalization: Option<OutputNormalization>,
+    float_tolerance: Option<FloatTolerance>,
+    comparison_mode: Option<ComparisonMode>,
+    /// An empty checker removes the problem's checker
+    checker: Option<String>,
+    /// An empty interactor removes the problem's interactor
+    interactor: Option<String>,
 }
 
 pub async fn edit(
@@ -52,5 +62,35 @@ pub async fn edit(
         ServerError::InternalError
     })?;
 
+    sqlx::query(
+        r#"
+        UPDATE problems SET
+        kind = COALESCE(?, kind),
+        output_normalization = COALESCE(?, output_normalization),
+        float_absolute_tolerance = COALESCE(?, float_absolute_tolerance),
+        float_relative_tolerance = COALESCE(?, float_relative_tolerance),
+        comparison_mode = COALESCE(?, comparison_mode),
+        checker = CASE WHEN ? IS NULL THEN checker ELSE NULLIF(TRIM(?), '') END,
+        interactor = CASE WHEN ? IS NULL THEN interactor ELSE NULLIF(TRIM(?), '') END
+        WHERE id = ?
+        "#,
+    )
+    .bind(form.kind)
+    .bind(form.output_normalization)
+    .bind(form.float_tolerance.map(|tolerance| tolerance.absolute))
+    .bind(form.float_tolerance.map(|tolerance| tolerance.relative))
+    .bind(form.comparison_mode)
+    .bind(&form.checker)
+    .bind(&form.checker)
+    .bind(&form.interactor)
+    .bind(&form.interactor)
+    .bind(id)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        log::error!("{e}");
+        ServerError::InternalError
+    })?;
+
     Ok(())
 }
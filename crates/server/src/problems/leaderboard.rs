@@ -1,6 +1,10 @@
-use axum::{extract::Path, Extension, Json};
+use axum::{
+    extract::{Path, Query},
+    Extension, Json,
+};
 use chrono::NaiveDateTime;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use shared::models::runner::Language;
 use sqlx::{FromRow, SqlitePool};
 
 use crate::error::ServerError;
@@ -12,10 +16,20 @@ pub struct ProblemLeaderboardItem {
     name: String,
     username: String,
     time: NaiveDateTime,
+    language: Language,
+}
+
+#[derive(Deserialize)]
+pub struct LeaderboardQuery {
+    /// The language whose submissions are ranked, since fuel isn't comparable across languages.
+    /// Defaults to C++.
+    #[serde(default)]
+    language: Language,
 }
 
 pub async fn leaderboard_users(
     Path(problem_id): Path<i64>,
+    Query(query): Query<LeaderboardQuery>,
     Extension(pool): Extension<SqlitePool>,
 ) -> Result<Json<Vec<ProblemLeaderboardItem>>, ServerError> {
     // if the problem is in a competition that is still active, we simply return an empty list
@@ -42,15 +56,18 @@ pub async fn leaderboard_users(
         MIN(submissions.runtime) AS runtime,
         users.name AS name,
         users.username AS username,
-        submissions.time AS time
+        submissions.time AS time,
+        submissions.language AS language
         FROM submissions
         JOIN users ON submissions.user_id = users.id
         WHERE problem_id = ?
         AND success = true
+        AND submissions.language = ?
         GROUP BY submissions.user_id
         ORDER BY runtime, submissions.time"#,
     )
     .bind(problem_id)
+    .bind(query.language)
     .fetch_all(&pool)
     .await
     .map_err(|_| ServerError::NotFound)?;
@@ -60,6 +77,7 @@ pub async fn leaderboard_users(
 
 pub async fn leaderboard_submissions(
     Path(problem_id): Path<i64>,
+    Query(query): Query<LeaderboardQuery>,
     Extension(pool): Extension<SqlitePool>,
 ) -> Result<Json<Vec<ProblemLeaderboardItem>>, ServerError> {
     // if the problem is in a competition that is still active, we simply return an empty list
@@ -86,15 +104,18 @@ pub async fn leaderboard_submissions(
         submissions.runtime AS runtime,
         users.name AS name,
         users.username AS username,
-        submissions.time AS time
+        submissions.time AS time,
+        submissions.language AS language
         FROM submissions
         JOIN users ON submissions.user_id = users.id
         WHERE problem_id = ?
         AND success = true
+        AND submissions.language = ?
         GROUP BY runtime
         ORDER BY runtime, submissions.time"#,
     )
     .bind(problem_id)
+    .bind(query.language)
     .fetch_all(&pool)
     .await
     .map_err(|_| ServerError::NotFound)?;
@@ -104,6 +125,7 @@ pub async fn leaderboard_submissions(
 
 pub async fn leaderboard_slow_submissions(
     Path(problem_id): Path<i64>,
+    Query(query): Query<LeaderboardQuery>,
     Extension(pool): Extension<SqlitePool>,
 ) -> Result<Json<Vec<ProblemLeaderboardItem>>, ServerError> {
     // if the problem is in a competition that is still active, we simply return an empty list
@@ -130,15 +152,82 @@ pub async fn leaderboard_slow_submissions(
         submissions.runtime AS runtime,
         users.name AS name,
         users.username AS username,
-        submissions.time AS time
+        submissions.time AS time,
+        submissions.language AS language
         FROM submissions
         JOIN users ON submissions.user_id = users.id
         WHERE problem_id = ?
         AND success = true
+        AND submissions.language = ?
         GROUP BY runtime
         ORDER BY runtime DESC, submissions.time"#,
     )
     .bind(problem_id)
+    .bind(query.language)
+    .fetch_all(&pool)
+    .await
+    .map_err(|_| ServerError::NotFound)?;
+
+    Ok(Json(items))
+}
+
+#[derive(Serialize, FromRow)]
+pub struct MemoryLeaderboardItem {
+    submission_id: i64,
+    /// The peak memory of the submission, in bytes
+    memory: i64,
+    runtime: i64,
+    name: String,
+    username: String,
+    time: NaiveDateTime,
+    language: Language,
+}
+
+/// Ranks each user's submission with the lowest peak memory. Submissions made before memory was
+/// measured aren't ranked.
+pub async fn leaderboard_memory(
+    Path(problem_id): Path<i64>,
+    Query(query): Query<LeaderboardQuery>,
+    Extension(pool): Extension<SqlitePool>,
+) -> Result<Json<Vec<MemoryLeaderboardItem>>, ServerError> {
+    // if the problem is in a competition that is still active, we simply return an empty list
+    let res = sqlx::query_scalar!(
+        "SELECT datetime('now') < end
+        FROM competitions
+        JOIN problems
+        WHERE competitions.id = problems.competition_id
+        AND problems.id = ?",
+        problem_id
+    )
+    .fetch_one(&pool)
+    .await;
+
+    if let Ok(res) = res {
+        if res == 1 {
+            return Err(ServerError::NotFound);
+        }
+    }
+
+    let items = sqlx::query_as(
+        r#"SELECT
+        submissions.id AS submission_id,
+        MIN(submissions.memory) AS memory,
+        submissions.runtime AS runtime,
+        users.name AS name,
+        users.username AS username,
+        submissions.time AS time,
+        submissions.language AS language
+        FROM submissions
+        JOIN users ON submissions.user_id = users.id
+        WHERE problem_id = ?
+        AND success = true
+        AND submissions.memory IS NOT NULL
+        AND submissions.language = ?
+        GROUP BY submissions.user_id
+        ORDER BY memory, runtime, submissions.time"#,
+    )
+    .bind(problem_id)
+    .bind(query.language)
     .fetch_all(&pool)
     .await
     .map_err(|_| ServerError::NotFound)?;
@@ -13,8 +13,11 @@ mod new;
 mod problem;
 mod recent_submission;
 mod recent_tests;
+mod signature;
 mod tests;
 
+pub(crate) use signature::parse_text_tests;
+
 #[derive(Serialize, Deserialize, Clone, Type)]
 pub enum Difficulty {
     Easy,
@@ -52,6 +55,7 @@ pub fn routes() -> Router {
     Router::new()
         .route("/", get(index::problems))
         .route("/new", post(new::new))
+        .route("/signature", post(signature::signature))
         .route("/:problem_id", get(problem::problem))
         .route("/:problem_id/edit", post(edit::edit))
         .route("/:problem_id/tests", get(tests::tests))
@@ -69,6 +73,10 @@ pub fn routes() -> Router {
             "/:problem_id/leaderboard/slow-submissions",
             get(leaderboard::leaderboard_slow_submissions),
         )
+        .route(
+            "/:problem_id/leaderboard/memory",
+            get(leaderboard::leaderboard_memory),
+        )
         .route(
             "/:problem_id/recent-submission",
             get(recent_submission::recent_submission),
@@ -1,12 +1,20 @@
 use axum::{Extension, Json};
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use shared::models::test::Test;
+use shared::models::{
+    runner::{OutputNormalization, ProblemKind},
+    test::Test,
+};
 use sqlx::SqlitePool;
 use tokio::sync::broadcast::Sender;
+use wasm_memory::{ComparisonMode, FloatTolerance, FunctionSignature};
 
-use super::Problem;
-use crate::{auth::Claims, error::ServerError, ws::BroadcastMessage};
+use super::{parse_text_tests, Problem};
+use crate::{
+    auth::Claims,
+    error::{FormValidationError, ServerError},
+    ws::BroadcastMessage,
+};
 
 #[derive(Deserialize)]
 pub struct NewForm {
@@ -15,10 +23,23 @@ pub struct NewForm {
     reference: String,
     template: String,
     tests: Vec<Test>,
+    /// More tests in the text format, e.g. `twoSum([2, 7, 11, 15], 9) -> [0, 1]`, which are added
+    /// after `tests`
+    tests_text: Option<String>,
     activity_id: Option<i64>,
     publish_time: Option<NaiveDateTime>,
     competition_id: Option<i64>,
     runtime_multiplier: Option<f64>,
+    #[serde(default)]
+    kind: ProblemKind,
+    #[serde(default)]
+    output_normalization: OutputNormalization,
+    #[serde(default)]
+    float_tolerance: FloatTolerance,
+    #[serde(default)]
+    comparison_mode: ComparisonMode,
+    checker: Option<String>,
+    interactor: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -30,10 +51,32 @@ pub async fn new(
     Extension(pool): Extension<SqlitePool>,
     Extension(broadcast): Extension<Sender<BroadcastMessage>>,
     claims: Claims,
-    Json(form): Json<NewForm>,
+    Json(mut form): Json<NewForm>,
 ) -> Result<Json<NewBody>, ServerError> {
     claims.validate_officer()?;
 
+    if let Some(text) = form.tests_text.take() {
+        for test in parse_text_tests(&form.reference, &text)? {
+            let index = form.tests.len() as i64;
+            let expected_output = test.expected_output.ok_or_else(|| {
+                FormValidationError::Signature(format!("Test {index} has no expected output"))
+            })?;
+
+            form.tests.push(Test {
+                id: 0,
+                index,
+                max_fuel: None,
+                input: test.input,
+                expected_output,
+            });
+        }
+    }
+
+    let template = match form.kind {
+        ProblemKind::Function => function_template(&form)?,
+        _ => form.template.clone(),
+    };
+
     let mut tx = pool.begin().await.map_err(|_| ServerError::InternalError)?;
 
     let visible = form.publish_time.is_none();
@@ -50,8 +93,15 @@ pub async fn new(
             visible,
             publish_time,
             runtime_multiplier,
-            competition_id
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            competition_id,
+            kind,
+            output_normalization,
+            float_absolute_tolerance,
+            float_relative_tolerance,
+            comparison_mode,
+            checker,
+            interactor
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         RETURNING
             id,
             title,
@@ -68,12 +118,22 @@ pub async fn new(
     .bind(form.description)
     .bind("")
     .bind(form.reference)
-    .bind(form.template)
+    .bind(template)
     .bind(form.activity_id)
     .bind(visible)
     .bind(form.publish_time)
     .bind(form.runtime_multiplier)
     .bind(form.competition_id)
+    .bind(form.kind)
+    .bind(form.output_normalization)
+    .bind(form.float_tolerance.absolute)
+    .bind(form.float_tolerance.relative)
+    .bind(form.comparison_mode)
+    .bind(form.checker.filter(|checker| !checker.trim().is_empty()))
+    .bind(
+        form.interactor
+            .filter(|interactor| !interactor.trim().is_empty()),
+    )
     .fetch_one(&mut tx)
     .await
     .map_err(|e| {
@@ -124,3 +184,35 @@ pub async fn new(
 
     Ok(Json(NewBody { id: problem.id }))
 }
+
+/// Checks the tests of a function problem against the function's definition in the reference
+/// solution, and returns the problem's template, generating one from the definition if the form
+/// left it empty.
+fn function_template(form: &NewForm) -> Result<String, FormValidationError> {
+    let Some(name) = form.tests.first().map(|test| &test.input.name) else {
+        return Ok(form.template.clone());
+    };
+
+    let signature = match FunctionSignature::from_cpp(&form.reference, name) {
+        Ok(signature) => signature,
+        // References we can't read are still accepted as long as they come with a template
+        Err(e) if !form.template.trim().is_empty() => {
+            log::warn!("Couldn't read the signature of `{name}` from the reference: {e}");
+            return Ok(form.template.clone());
+        }
+        Err(e) => return Err(FormValidationError::Signature(e.to_string())),
+    };
+
+    for test in &form.tests {
+        signature
+            .check(&test.input)
+            .and_then(|_| signature.check_output(&test.expected_output))
+            .map_err(|e| FormValidationError::Signature(format!("Test {}: {e}", test.index)))?;
+    }
+
+    if form.template.trim().is_empty() {
+        Ok(signature.template())
+    } else {
+        Ok(form.template.clone())
+    }
+}
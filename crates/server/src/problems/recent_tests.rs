@@ -78,6 +78,10 @@ pub async fn recent_tests_test(
             test_results.output as output,
             test_results.runtime as runtime,
             test_results.error as error,
+            test_results.queries as queries,
+            test_results.interactor_fuel as interactor_fuel,
+            test_results.memory as memory,
+            test_results.heap as heap,
             tests.max_runtime as max_runtime,
             tests.input as input,
             tests.expected_output as expected_output,
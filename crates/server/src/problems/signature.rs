@@ -0,0 +1,40 @@
+use axum::Json;
+use serde::Deserialize;
+use wasm_memory::{called_function, FunctionSignature, TextTest};
+
+use crate::{
+    auth::Claims,
+    error::{FormValidationError, ServerError},
+};
+
+#[derive(Deserialize)]
+pub struct SignatureForm {
+    reference: String,
+    name: String,
+}
+
+/// Reads the signature of a function from a reference solution, so the test editor doesn't have to
+/// be filled out by hand.
+pub async fn signature(
+    claims: Claims,
+    Json(form): Json<SignatureForm>,
+) -> Result<Json<FunctionSignature>, ServerError> {
+    claims.validate_officer()?;
+
+    let signature = FunctionSignature::from_cpp(&form.reference, &form.name)
+        .map_err(|e| FormValidationError::Signature(e.to_string()))?;
+
+    Ok(Json(signature))
+}
+
+/// Parses tests written in the text format, typed against the signature of the function they
+/// call in the reference solution.
+pub(crate) fn parse_text_tests(
+    reference: &str,
+    text: &str,
+) -> Result<Vec<TextTest>, FormValidationError> {
+    called_function(text)
+        .and_then(|name| FunctionSignature::from_cpp(reference, name))
+        .and_then(|signature| signature.parse_tests(text))
+        .map_err(|e| FormValidationError::Signature(e.to_string()))
+}
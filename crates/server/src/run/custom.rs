@@ -2,19 +2,24 @@ use axum::{async_trait, Extension, Json};
 use reqwest::Client;
 use serde::Deserialize;
 use serde_json::Value;
-use shared::models::{forms::CustomInputJob, runner::RunnerError};
+use shared::models::{
+    forms::CustomInputJob,
+    runner::{Language, RunnerError},
+};
 use sqlx::SqlitePool;
 use tokio::sync::broadcast::{self, Sender};
 use wasm_memory::WasmFunctionCall;
 
 use crate::{auth::Claims, error::ServerError, ws::BroadcastMessage};
 
-use super::{add_job, JobMap, JobQueue, JobStatus, Queueable};
+use super::{add_job, JobMap, JobProgress, JobQueue, JobStatus, JudgingSettings, Queueable};
 
 #[derive(Deserialize)]
 pub struct CustomProblemInputForm {
     pub problem_id: i64,
     pub implementation: String,
+    #[serde(default)]
+    pub language: Language,
     pub input: WasmFunctionCall,
 }
 
@@ -28,27 +33,25 @@ pub async fn custom(
 ) -> Result<Json<JobStatus>, ServerError> {
     claims.validate_logged_in()?;
 
-    let (reference, runtime_multiplier): (String, Option<f64>) = sqlx::query_as(
-        r#"
-        SELECT
-            reference,
-            runtime_multiplier
-        FROM
-            problems
-        WHERE
-            id = ?
-        "#,
-    )
-    .bind(form.problem_id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| ServerError::NotFound)?;
+    let (reference,): (String,) = sqlx::query_as(r#"SELECT reference FROM problems WHERE id = ?"#)
+        .bind(form.problem_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|_| ServerError::NotFound)?;
+    let settings = JudgingSettings::fetch(&pool, form.problem_id).await?;
 
     let queue_item = Box::new(CustomInputJob {
         problem_id: form.problem_id,
         user_id: claims.user_id,
         implementation: form.implementation,
-        runtime_multiplier,
+        language: form.language,
+        kind: settings.kind,
+        normalization: settings.output_normalization,
+        tolerance: settings.tolerance(),
+        comparison: settings.comparison_mode,
+        checker: settings.checker,
+        interactor: settings.interactor,
+        runtime_multiplier: settings.runtime_multiplier,
         reference,
         input: form.input,
     });
@@ -65,10 +68,15 @@ impl Queueable for CustomInputJob {
         ramiel_url: &str,
         _pool: &SqlitePool,
         _broadcast: &broadcast::Sender<BroadcastMessage>,
+        progress: &JobProgress,
     ) -> Result<Value, ServerError> {
         let client = Client::new();
         let res = client
-            .post(&format!("{ramiel_url}/custom-input/c++"))
+            .post(format!(
+                "{ramiel_url}/custom-input/{}?id={}",
+                self.language.as_str(),
+                progress.id()
+            ))
             .json(self)
             .send()
             .await
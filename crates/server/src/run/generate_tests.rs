@@ -5,19 +5,27 @@ use shared::models::{forms::GenerateTestsJob, runner::RunnerError, test::Test};
 use sqlx::SqlitePool;
 use tokio::sync::broadcast::{self, Sender};
 
-use crate::{auth::Claims, error::ServerError, ws::BroadcastMessage};
+use crate::{auth::Claims, error::ServerError, problems::parse_text_tests, ws::BroadcastMessage};
 
-use super::{add_job, JobMap, JobQueue, JobStatus, Queueable};
+use super::{add_job, JobMap, JobProgress, JobQueue, JobStatus, Queueable};
 
 pub async fn generate_tests(
     claims: Claims,
     Extension(job_queue): Extension<JobQueue>,
     Extension(job_map): Extension<JobMap>,
     Extension(broadcast): Extension<Sender<BroadcastMessage>>,
-    Json(queue_item): Json<GenerateTestsJob>,
+    Json(mut queue_item): Json<GenerateTestsJob>,
 ) -> Result<Json<JobStatus>, ServerError> {
     claims.validate_officer()?;
 
+    // expected outputs written in the text are ignored, since the reference generates them
+    if let Some(text) = queue_item.inputs_text.take() {
+        let tests = parse_text_tests(&queue_item.reference, &text)?;
+        queue_item
+            .inputs
+            .extend(tests.into_iter().map(|test| test.input));
+    }
+
     let job = add_job(
         claims.user_id,
         job_queue,
@@ -37,10 +45,14 @@ impl Queueable for GenerateTestsJob {
         ramiel_url: &str,
         _pool: &SqlitePool,
         _broadcast: &broadcast::Sender<BroadcastMessage>,
+        progress: &JobProgress,
     ) -> Result<Value, ServerError> {
         let client = Client::new();
         let res = client
-            .post(&format!("{ramiel_url}/generate-tests/c++"))
+            .post(format!(
+                "{ramiel_url}/generate-tests/c++?id={}",
+                progress.id()
+            ))
             .json(&self)
             .send()
             .await
@@ -6,14 +6,16 @@ use std::{
 use axum::{
     async_trait,
     extract::Path,
+    http::StatusCode,
     routing::{get, post},
     Extension, Json, Router,
 };
 
+use reqwest::Client;
 use serde::Serialize;
 use serde_json::Value;
-use shared::models::runner::RunnerError;
-use sqlx::SqlitePool;
+use shared::models::runner::{OutputNormalization, ProblemKind, RunnerError};
+use sqlx::{FromRow, SqlitePool};
 use tokio::{
     sync::{
         broadcast::{self, Sender},
@@ -22,6 +24,7 @@ use tokio::{
     task::JoinHandle,
     time::{sleep, Duration},
 };
+use wasm_memory::{ComparisonMode, FloatTolerance};
 
 use crate::{
     auth::Claims,
@@ -34,6 +37,52 @@ mod custom;
 mod generate_tests;
 mod submit;
 
+/// The columns of a problem deciding how submissions to it are judged
+#[derive(FromRow)]
+struct JudgingSettings {
+    runtime_multiplier: Option<f64>,
+    kind: ProblemKind,
+    output_normalization: OutputNormalization,
+    float_absolute_tolerance: f64,
+    float_relative_tolerance: f64,
+    comparison_mode: ComparisonMode,
+    checker: Option<String>,
+    interactor: Option<String>,
+}
+
+impl JudgingSettings {
+    async fn fetch(pool: &SqlitePool, problem_id: i64) -> Result<JudgingSettings, ServerError> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                runtime_multiplier,
+                kind,
+                output_normalization,
+                float_absolute_tolerance,
+                float_relative_tolerance,
+                comparison_mode,
+                checker,
+                interactor
+            FROM
+                problems
+            WHERE
+                id = ?
+            "#,
+        )
+        .bind(problem_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|_| ServerError::NotFound)
+    }
+
+    fn tolerance(&self) -> FloatTolerance {
+        FloatTolerance {
+            absolute: self.float_absolute_tolerance,
+            relative: self.float_relative_tolerance,
+        }
+    }
+}
+
 pub type JobQueueItem = Box<dyn Queueable>;
 pub type JobQueue = mpsc::UnboundedSender<(u64, JobQueueItem)>;
 pub type JobMap = Arc<RwLock<HashMap<u64, JobStatus>>>;
@@ -50,6 +99,32 @@ pub struct JobStatus {
 
     response: Option<Value>,
     error: Option<String>,
+
+    // Only reported by jobs that run tests, once they start running
+    tests_completed: Option<usize>,
+    tests_total: Option<usize>,
+
+    cancelled: bool,
+}
+
+/// Lets a job report its progress while it is running
+pub struct JobProgress {
+    id: u64,
+    job_map: JobMap,
+}
+
+impl JobProgress {
+    /// The id of the job, which is sent to ramiel so the job can be cancelled while it runs
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub async fn update(&self, tests_completed: usize, tests_total: usize) {
+        if let Some(job) = self.job_map.write().await.get_mut(&self.id) {
+            job.tests_completed = Some(tests_completed);
+            job.tests_total = Some(tests_total);
+        }
+    }
 }
 
 #[async_trait]
@@ -60,6 +135,7 @@ pub trait Queueable: Send + Sync {
         ramiel_url: &str,
         pool: &SqlitePool,
         broadcast: &broadcast::Sender<BroadcastMessage>,
+        progress: &JobProgress,
     ) -> Result<Value, ServerError>;
 
     // Returns some basic info about the job -- for logging purposes only
@@ -89,6 +165,9 @@ async fn add_job(
         problem_id: queue_item.problem_id(),
         response: None,
         error: None,
+        tests_completed: None,
+        tests_total: None,
+        cancelled: false,
     };
 
     broadcast
@@ -127,6 +206,63 @@ pub async fn check_job(
     }
 }
 
+/// Cancels a job. Queued jobs are skipped once they reach the front of the queue, while running
+/// jobs are stopped by ramiel.
+pub async fn cancel_job(
+    Path(id): Path<u64>,
+    Extension(job_map): Extension<JobMap>,
+    Extension(ramiel_url): Extension<String>,
+    claims: Claims,
+) -> Result<StatusCode, ServerError> {
+    claims.validate_logged_in()?;
+
+    {
+        let mut job_map = job_map.write().await;
+        let job = job_map.get_mut(&id).ok_or(ServerError::NotFound)?;
+
+        if job.user_id != claims.user_id {
+            return Err(AuthError::Unauthorized.into());
+        }
+
+        // Already finished
+        if job.response.is_some() || job.error.is_some() {
+            return Ok(StatusCode::NO_CONTENT);
+        }
+
+        job.cancelled = true;
+    }
+
+    if id <= PROCESSING_JOB.load(Ordering::SeqCst) {
+        // ramiel remembers cancellations of jobs it hasn't received yet, and starts them cancelled
+        let response = Client::new()
+            .delete(format!("{ramiel_url}/run/{id}"))
+            .send()
+            .await
+            .map_err(|e| {
+                log::error!("error cancelling job on ramiel: {e}");
+                ServerError::InternalError
+            })?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            // Only fine if the job finished in the meantime
+            let job_map = job_map.read().await;
+            let finished = job_map
+                .get(&id)
+                .is_none_or(|job| job.response.is_some() || job.error.is_some());
+
+            if !finished {
+                log::error!("ramiel has no job {id} to cancel");
+                return Err(ServerError::InternalError);
+            }
+        } else if let Err(e) = response.error_for_status() {
+            log::error!("error cancelling job on ramiel: {e}");
+            return Err(ServerError::InternalError);
+        }
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 async fn process_job(
     id: u64,
     queue_item: JobQueueItem,
@@ -139,7 +275,24 @@ async fn process_job(
 
     log::info!("Processing job {id}: {}", queue_item.info());
 
-    let res = queue_item.run(&ramiel_url, &pool, &broadcast).await;
+    let progress = JobProgress {
+        id,
+        job_map: queued_jobs.clone(),
+    };
+
+    let cancelled = queued_jobs
+        .read()
+        .await
+        .get(&id)
+        .is_some_and(|job| job.cancelled);
+
+    let res = if cancelled {
+        Err(RunnerError::Cancelled.into())
+    } else {
+        queue_item
+            .run(&ramiel_url, &pool, &broadcast, &progress)
+            .await
+    };
 
     let mut job_map_writer = queued_jobs.write().await;
     let job = job_map_writer.get_mut(&id).expect("Job missing in job map");
@@ -205,4 +358,5 @@ pub fn routes() -> Router {
         .route("/generate-tests", post(generate_tests::generate_tests))
         .route("/submit", post(submit::submit))
         .route("/check/:id", get(check_job))
+        .route("/cancel/:id", post(cancel_job))
 }
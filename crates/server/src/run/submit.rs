@@ -1,11 +1,11 @@
 use axum::{async_trait, Extension, Json};
 use chrono::Utc;
-use reqwest::Client;
+use reqwest::{Client, Response};
 use serde::Deserialize;
 use serde_json::Value;
 use shared::models::{
     forms::SubmitJob,
-    runner::{RunnerError, RunnerResponse},
+    runner::{Language, RunnerError, RunnerEvent, RunnerResponse},
     test::Test,
 };
 use sqlx::SqlitePool;
@@ -13,12 +13,14 @@ use tokio::sync::broadcast::Sender;
 
 use crate::{auth::Claims, error::ServerError, submissions::Submission, ws::BroadcastMessage};
 
-use super::{add_job, JobMap, JobQueue, JobStatus, Queueable};
+use super::{add_job, JobMap, JobProgress, JobQueue, JobStatus, JudgingSettings, Queueable};
 
 #[derive(Deserialize)]
 pub struct SubmitForm {
     pub problem_id: i64,
     pub implementation: String,
+    #[serde(default)]
+    pub language: Language,
 }
 
 pub async fn submit(
@@ -51,19 +53,21 @@ pub async fn submit(
     .await
     .map_err(|_| ServerError::NotFound)?;
 
-    let (runtime_multiplier,): (Option<f64>,) =
-        sqlx::query_as(r#"SELECT runtime_multiplier FROM problems WHERE id = ?"#)
-            .bind(form.problem_id)
-            .fetch_one(&pool)
-            .await
-            .map_err(|_| ServerError::NotFound)?;
+    let settings = JudgingSettings::fetch(&pool, form.problem_id).await?;
 
     let queue_item = Box::new(SubmitJob {
         problem_id: form.problem_id,
         user_id: claims.user_id,
         implementation: form.implementation.clone(),
+        language: form.language,
+        kind: settings.kind,
+        normalization: settings.output_normalization,
+        tolerance: settings.tolerance(),
+        comparison: settings.comparison_mode,
+        checker: settings.checker,
+        interactor: settings.interactor,
         tests,
-        runtime_multiplier,
+        runtime_multiplier: settings.runtime_multiplier,
     });
 
     let job = add_job(claims.user_id, job_queue, job_map, queue_item, broadcast).await?;
@@ -78,10 +82,15 @@ impl Queueable for SubmitJob {
         ramiel_url: &str,
         pool: &SqlitePool,
         broadcast: &Sender<BroadcastMessage>,
+        progress: &JobProgress,
     ) -> Result<Value, ServerError> {
         let client = Client::new();
         let res = client
-            .post(&format!("{ramiel_url}/run/c++"))
+            .post(format!(
+                "{ramiel_url}/run/{}/stream?id={}",
+                self.language.as_str(),
+                progress.id()
+            ))
             .json(self)
             .send()
             .await
@@ -90,13 +99,19 @@ impl Queueable for SubmitJob {
                 ServerError::InternalError
             })?;
 
-        let res: Result<RunnerResponse, RunnerError> = res.json().await.map_err(|e| {
-            log::error!("error converting json from ramiel: {e}");
-            ServerError::InternalError
-        })?;
+        let res = read_events(res, progress).await?;
 
-        let (passed, runtime, error, tests) = match res {
-            Ok(res) => (res.passed, res.runtime, None, res.tests),
+        let (passed, runtime, memory, heap, error, tests) = match res {
+            Ok(res) => (
+                res.passed,
+                res.runtime,
+                Some(res.memory),
+                Some(res.heap),
+                None,
+                res.tests,
+            ),
+            // cancelled submissions aren't recorded
+            Err(RunnerError::Cancelled) => return Err(RunnerError::Cancelled.into()),
             Err(err) => {
                 let err = match err {
                     RunnerError::CompilationError { diagnostics } => {
@@ -105,12 +120,12 @@ impl Queueable for SubmitJob {
                     _ => err.to_string(),
                 };
 
-                (false, 0, Some(err), vec![])
+                (false, 0, None, None, Some(err), vec![])
             }
         };
 
         // find the asymptotic complexity
-        let complexity = if passed {
+        let estimate = if passed {
             let inputs = tests.iter().map(|test| test.input.clone()).collect();
             let times = tests.iter().map(|test| test.fuel as f32).collect();
             wasm_memory::estimate_asymptotic_complexity(inputs, times)
@@ -131,9 +146,14 @@ impl Queueable for SubmitJob {
                 error,
                 code,
                 time,
-                complexity
+                complexity,
+                complexity_r_squared,
+                complexity_constant,
+                memory,
+                heap,
+                language
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             RETURNING *
             "#,
         )
@@ -144,7 +164,12 @@ impl Queueable for SubmitJob {
         .bind(error)
         .bind(&self.implementation)
         .bind(now)
-        .bind(complexity)
+        .bind(estimate.map(|estimate| estimate.complexity))
+        .bind(estimate.map(|estimate| estimate.r_squared))
+        .bind(estimate.map(|estimate| estimate.constant))
+        .bind(memory)
+        .bind(heap)
+        .bind(self.language)
         .fetch_one(&mut tx)
         .await
         .map_err(|e| {
@@ -155,7 +180,7 @@ impl Queueable for SubmitJob {
         for test in &tests {
             let output = serde_json::to_string(&test.output).unwrap();
 
-            sqlx::query!(
+            sqlx::query(
                 r#"
                 INSERT INTO test_results (
                     submission_id,
@@ -163,16 +188,24 @@ impl Queueable for SubmitJob {
                     runtime,
                     output,
                     error,
-                    success
-                ) VALUES (?, ?, ?, ?, ?, ?)
+                    success,
+                    queries,
+                    interactor_fuel,
+                    memory,
+                    heap
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
-                submission.id,
-                test.id,
-                test.fuel,
-                output,
-                test.error,
-                test.success,
             )
+            .bind(submission.id)
+            .bind(test.id)
+            .bind(test.fuel)
+            .bind(output)
+            .bind(&test.error)
+            .bind(test.success)
+            .bind(test.queries)
+            .bind(test.interactor_fuel)
+            .bind(test.memory)
+            .bind(test.heap)
             .execute(&mut tx)
             .await
             .map_err(|e| {
@@ -224,3 +257,40 @@ impl Queueable for SubmitJob {
         self.problem_id
     }
 }
+
+/// Reads the events streamed by ramiel, reporting progress as tests complete, until the final
+/// result is received.
+async fn read_events(
+    mut res: Response,
+    progress: &JobProgress,
+) -> Result<Result<RunnerResponse, RunnerError>, ServerError> {
+    let mut buffer = Vec::new();
+    let mut tests_completed = 0;
+    let mut tests_total = 0;
+
+    while let Some(chunk) = res.chunk().await.map_err(|e| {
+        log::error!("error reading stream from ramiel: {e}");
+        ServerError::InternalError
+    })? {
+        buffer.extend_from_slice(&chunk);
+
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line = buffer.drain(..=end).collect::<Vec<_>>();
+            let event: RunnerEvent = serde_json::from_slice(&line).map_err(|e| {
+                log::error!("error converting json from ramiel: {e}");
+                ServerError::InternalError
+            })?;
+
+            match event {
+                RunnerEvent::Started { tests_total: total } => tests_total = total,
+                RunnerEvent::TestCompleted(_) => tests_completed += 1,
+                RunnerEvent::Finished(res) => return Ok(res),
+            }
+
+            progress.update(tests_completed, tests_total).await;
+        }
+    }
+
+    log::error!("ramiel closed the stream without a result");
+    Err(ServerError::InternalError)
+}
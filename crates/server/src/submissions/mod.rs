@@ -1,6 +1,7 @@
 use axum::{routing::get, Router};
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use shared::models::runner::Language;
 use sqlx::FromRow;
 use wasm_memory::AsymptoticComplexity;
 
@@ -19,8 +20,17 @@ pub struct Submission {
     pub runtime: i64,
     pub error: Option<String>,
     pub complexity: Option<AsymptoticComplexity>,
+    /// How well `complexity` fit the fuel of each test, from 0 to 1
+    pub complexity_r_squared: Option<f64>,
+    /// The constant factor of `complexity`, i.e. the fuel of a test ≈ `complexity_constant * f(n)`
+    pub complexity_constant: Option<f64>,
+    /// The largest peak memory of any test, in bytes
+    pub memory: Option<i64>,
+    /// The most memory grew past the arguments of any test, in bytes
+    pub heap: Option<i64>,
     pub time: NaiveDateTime,
     pub code: String,
+    pub language: Language,
 }
 
 pub fn routes() -> Router {
@@ -25,7 +25,13 @@ pub async fn new_completions(
             success,
             runtime,
             error,
+            complexity,
+            complexity_r_squared,
+            complexity_constant,
+            memory,
+            heap,
             code,
+            language,
             min(time) as time
         FROM
             submissions
@@ -1,15 +1,36 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use wasm_memory::WasmFunctionCall;
+use wasm_memory::{ComparisonMode, FloatTolerance, WasmFunctionCall};
 
-use crate::models::test::Test;
+use crate::models::{
+    runner::{Language, OutputNormalization, ProblemKind},
+    test::Test,
+};
 
 #[derive(Deserialize, Serialize)]
 pub struct SubmitJob {
     pub problem_id: i64,
     pub user_id: i64,
     pub implementation: String,
+    #[serde(default)]
+    pub language: Language,
+    #[serde(default)]
+    pub kind: ProblemKind,
+    #[serde(default)]
+    pub normalization: OutputNormalization,
+    /// How close floating point outputs must be to the expected output
+    #[serde(default)]
+    pub tolerance: FloatTolerance,
+    /// Which orderings of returned containers are accepted
+    #[serde(default)]
+    pub comparison: ComparisonMode,
     pub runtime_multiplier: Option<f64>,
+    /// C++ source of the problem's checker, see `ramiel::runners::Judge`
+    #[serde(default)]
+    pub checker: Option<String>,
+    /// C++ source of the interactor of a [`ProblemKind::Interactive`] problem
+    #[serde(default)]
+    pub interactor: Option<String>,
     pub tests: Vec<Test>,
 }
 
@@ -17,7 +38,17 @@ pub struct SubmitJob {
 pub struct GenerateTestsJob {
     pub reference: String,
     pub user_id: i64,
+    #[serde(default)]
+    pub kind: ProblemKind,
+    #[serde(default)]
+    pub checker: Option<String>,
+    #[serde(default)]
+    pub interactor: Option<String>,
     pub inputs: Vec<WasmFunctionCall>,
+    /// More inputs in the text format, e.g. `twoSum([2, 7, 11, 15], 9)`, which the server parses
+    /// into `inputs` before the job is queued
+    #[serde(default)]
+    pub inputs_text: Option<String>,
 }
 
 // TODO: Make naming less bad
@@ -27,7 +58,21 @@ pub struct CustomInputJob {
     pub user_id: i64,
     pub reference: String,
     pub implementation: String,
+    #[serde(default)]
+    pub language: Language,
+    #[serde(default)]
+    pub kind: ProblemKind,
+    #[serde(default)]
+    pub normalization: OutputNormalization,
+    #[serde(default)]
+    pub tolerance: FloatTolerance,
+    #[serde(default)]
+    pub comparison: ComparisonMode,
     pub runtime_multiplier: Option<f64>,
+    #[serde(default)]
+    pub checker: Option<String>,
+    #[serde(default)]
+    pub interactor: Option<String>,
     pub input: WasmFunctionCall,
 }
 
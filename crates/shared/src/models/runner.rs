@@ -1,8 +1,94 @@
 use serde::{Deserialize, Serialize};
+use sqlx::Type;
 use thiserror::Error;
 
 use crate::models::test::TestResult;
 
+/// A language submissions can be written in. Each has its own runner in ramiel, and submissions
+/// are only ranked against others in the same language.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default, Type)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    #[sqlx(rename = "c++")]
+    #[serde(rename = "c++")]
+    CPlusPlus,
+    C,
+    Rust,
+    Python,
+}
+
+impl Language {
+    /// The name of the language as used in ramiel's routes, e.g. `/run/c++`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::CPlusPlus => "c++",
+            Language::C => "c",
+            Language::Rust => "rust",
+            Language::Python => "python",
+        }
+    }
+}
+
+/// How submissions to a problem are run.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default, Type)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ProblemKind {
+    /// The submission defines a function, which is called with the arguments of each test.
+    #[default]
+    Function,
+
+    /// The submission is a program which reads the test from stdin and writes its answer to
+    /// stdout. Tests are created with [`wasm_memory::WasmFunctionCall::stdio`].
+    Stdio,
+
+    /// The submission defines a function taking no arguments, which answers the test by calling
+    /// functions of the problem's interactor. The arguments of each test are only given to the
+    /// interactor.
+    Interactive,
+}
+
+/// How the output of a [`ProblemKind::Stdio`] problem is compared to the expected output.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default, Type)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum OutputNormalization {
+    /// The output must match exactly.
+    Exact,
+
+    /// Trailing whitespace on each line, trailing blank lines and `\r\n` line endings are
+    /// ignored.
+    #[default]
+    Lines,
+
+    /// Only the whitespace separated tokens are compared, so e.g. `1 2\n3` matches `1\n2 3`.
+    Tokens,
+}
+
+impl OutputNormalization {
+    pub fn normalize(&self, output: &str) -> String {
+        match self {
+            OutputNormalization::Exact => output.to_string(),
+            OutputNormalization::Lines => {
+                let lines = output.lines().map(str::trim_end).collect::<Vec<_>>();
+                let len = lines
+                    .iter()
+                    .rposition(|line| !line.is_empty())
+                    .map_or(0, |i| i + 1);
+
+                lines[..len].join("\n")
+            }
+            OutputNormalization::Tokens => output.split_whitespace().collect::<Vec<_>>().join(" "),
+        }
+    }
+
+    pub fn matches(&self, expected: &str, output: &str) -> bool {
+        self.normalize(expected) == self.normalize(output)
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub enum DiagnosticType {
     Error,
@@ -25,6 +111,26 @@ pub struct RunnerResponse {
 
     // runtime, stored as milliseconds
     pub runtime: i64,
+
+    /// The largest peak memory of any test, in bytes
+    #[serde(default)]
+    pub memory: i64,
+    /// The most memory grew past the arguments of any test, in bytes, see [`TestResult::heap`]
+    #[serde(default)]
+    pub heap: i64,
+}
+
+/// A line of the newline delimited json returned by ramiel's `/run/{language}/stream`.
+#[derive(Deserialize, Serialize, Clone)]
+pub enum RunnerEvent {
+    /// Sent as soon as the submission is received, before it is compiled.
+    Started { tests_total: usize },
+
+    /// Sent as each test finishes, which is not necessarily in order.
+    TestCompleted(Box<TestResult>),
+
+    /// Always the last event.
+    Finished(Result<RunnerResponse, RunnerError>),
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -47,6 +153,9 @@ pub enum RunnerError {
 
     #[error("Process took too long to execute")]
     TimeoutError { message: String },
+
+    #[error("The job was cancelled")]
+    Cancelled,
 }
 
 impl From<std::io::Error> for RunnerError {
@@ -53,6 +53,10 @@ impl Test {
             error: None,
             max_fuel: self.max_fuel,
             fuel: fuel as i64,
+            queries: None,
+            interactor_fuel: None,
+            memory: None,
+            heap: None,
             hidden: false,
         }
     }
@@ -68,6 +72,10 @@ impl Test {
             error: Some(error),
             max_fuel: self.max_fuel,
             fuel: fuel as i64,
+            queries: None,
+            interactor_fuel: None,
+            memory: None,
+            heap: None,
             hidden: false,
         }
     }
@@ -86,6 +94,22 @@ pub struct TestResult {
     pub error: Option<String>,
     pub max_fuel: Option<i64>,
 
+    /// How many times the submission called the interactor of a
+    /// [`super::runner::ProblemKind::Interactive`] problem
+    #[serde(default)]
+    pub queries: Option<i64>,
+    /// The fuel the interactor consumed, which doesn't count towards `fuel`
+    #[serde(default)]
+    pub interactor_fuel: Option<i64>,
+
+    /// The peak size of linear memory while running the test, in bytes
+    #[serde(default)]
+    pub memory: Option<i64>,
+    /// How far memory grew once the program's arguments were in place, in bytes. Memory grows in
+    /// whole 64 KiB pages, so this is the heap's growth rounded up to pages
+    #[serde(default)]
+    pub heap: Option<i64>,
+
     #[serde(default = "default_hidden")]
     pub hidden: bool,
 }
@@ -107,7 +131,7 @@ impl Serialize for TestResult {
             s.serialize_field("error", &self.error)?;
             s.end()
         } else {
-            let mut s = serializer.serialize_struct("TestResult", 9)?;
+            let mut s = serializer.serialize_struct("TestResult", 13)?;
             s.serialize_field("id", &self.id)?;
             s.serialize_field("index", &self.index)?;
             s.serialize_field("success", &self.success)?;
@@ -117,6 +141,10 @@ impl Serialize for TestResult {
             s.serialize_field("fuel", &self.fuel)?;
             s.serialize_field("error", &self.error)?;
             s.serialize_field("max_fuel", &self.max_fuel)?;
+            s.serialize_field("queries", &self.queries)?;
+            s.serialize_field("interactor_fuel", &self.interactor_fuel)?;
+            s.serialize_field("memory", &self.memory)?;
+            s.serialize_field("heap", &self.heap)?;
             s.end()
         }
     }
@@ -171,6 +199,10 @@ impl FromRow<'_, SqliteRow> for TestResult {
             fuel: row.try_get("runtime")?,
             error: row.try_get("error")?,
             max_fuel: row.try_get("max_runtime")?,
+            queries: row.try_get("queries")?,
+            interactor_fuel: row.try_get("interactor_fuel")?,
+            memory: row.try_get("memory")?,
+            heap: row.try_get("heap")?,
             hidden: row.try_get("hidden")?,
         })
     }
@@ -0,0 +1,358 @@
+use anyhow::{bail, Context, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use wasmtime::*;
+
+use crate::{
+    AllocatorFunc, ContainerVariant, ContainerVariantType, WasmMemory, VECTORS_UNSUPPORTED_IN_C,
+};
+
+/// An element of a C array.
+///
+/// Unlike C++, C has no containers, so lists are passed as a pointer to their first element
+/// followed by their length, e.g. `int* nums, int numsSize`. Grids are passed as an array of row
+/// pointers followed by the number of rows and an array of row lengths, e.g.
+/// `int** grid, int gridSize, int* gridColSize`. Strings are null terminated `char*`s.
+pub trait CMemory: Sized {
+    const C_SIZE_OF: usize;
+
+    /// writes the item to `offset`, which must already be allocated
+    fn into_c_memory<S>(
+        self,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+        offset: usize,
+    ) -> Result<()>;
+
+    fn from_c_memory<S>(store: &mut Store<S>, memory: &Memory, offset: usize) -> Result<Self>;
+}
+
+macro_rules! impl_c_memory_scalar {
+    ($($t:ty),*) => {
+        $(
+            impl CMemory for $t {
+                const C_SIZE_OF: usize = <$t as WasmMemory>::CPP_SIZE_OF;
+
+                fn into_c_memory<S>(
+                    self,
+                    store: &mut Store<S>,
+                    memory: &Memory,
+                    allocator: &AllocatorFunc,
+                    offset: usize,
+                ) -> Result<()> {
+                    self.into_memory(store, memory, allocator, Some(offset))?;
+                    Ok(())
+                }
+
+                fn from_c_memory<S>(store: &mut Store<S>, memory: &Memory, offset: usize) -> Result<Self> {
+                    <$t as WasmMemory>::from_memory(store, memory, offset)
+                }
+            }
+        )*
+    };
+}
+
+impl_c_memory_scalar!(i32, i64, f32, f64, char, bool, u32, u64, i16, u8, i128);
+
+/// maps to `char*`
+impl CMemory for String {
+    const C_SIZE_OF: usize = 4;
+
+    fn into_c_memory<S>(
+        self,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+        offset: usize,
+    ) -> Result<()> {
+        let address = c_string_into_memory(self, store, memory, allocator)?;
+        (address as i32).into_memory(store, memory, allocator, Some(offset))?;
+
+        Ok(())
+    }
+
+    fn from_c_memory<S>(store: &mut Store<S>, memory: &Memory, offset: usize) -> Result<Self> {
+        let address = i32::from_memory(store, memory, offset)?;
+        c_string_from_memory(store, memory, address as usize)
+    }
+}
+
+/// Writes a null terminated string, returning its address
+pub fn c_string_into_memory<S>(
+    string: String,
+    mut store: &mut Store<S>,
+    memory: &Memory,
+    allocator: &AllocatorFunc,
+) -> Result<usize> {
+    let bytes = string.as_bytes();
+    let address = allocator.call(&mut store, bytes.len() as i32 + 1)? as usize;
+
+    memory.write(&mut store, address, bytes)?;
+    memory.write(&mut store, address + bytes.len(), b"\0")?;
+
+    Ok(address)
+}
+
+/// Reads a null terminated string starting at `address`
+pub fn c_string_from_memory<S>(
+    store: &mut Store<S>,
+    memory: &Memory,
+    address: usize,
+) -> Result<String> {
+    let data = memory.data(&store);
+
+    let bytes = match data.get(address..) {
+        Some(bytes) => bytes,
+        None => bail!("string pointer {address:#x} is out of bounds"),
+    };
+
+    let length = match bytes.iter().position(|b| *b == b'\0') {
+        Some(length) => length,
+        None => bail!("string at {address:#x} is not null terminated"),
+    };
+
+    Ok(String::from_utf8_lossy(&bytes[..length]).to_string())
+}
+
+/// Writes `values` as a C array, returning the address of the first element
+fn array_into_memory<T: CMemory, S>(
+    values: Vec<T>,
+    mut store: &mut Store<S>,
+    memory: &Memory,
+    allocator: &AllocatorFunc,
+) -> Result<usize> {
+    let address = allocator.call(&mut store, (T::C_SIZE_OF * values.len()) as i32)? as usize;
+
+    for (i, value) in values.into_iter().enumerate() {
+        value.into_c_memory(store, memory, allocator, address + i * T::C_SIZE_OF)?;
+    }
+
+    Ok(address)
+}
+
+fn array_from_memory<T: CMemory, S>(
+    store: &mut Store<S>,
+    memory: &Memory,
+    address: usize,
+    length: usize,
+) -> Result<Vec<T>> {
+    (0..length)
+        .map(|i| T::from_c_memory(store, memory, address + i * T::C_SIZE_OF))
+        .collect()
+}
+
+fn read_length<S>(store: &mut Store<S>, memory: &Memory, address: usize) -> Result<usize> {
+    let mut buf = [0; 4];
+    memory.read(store, address, &mut buf)?;
+
+    let length = LittleEndian::read_i32(&buf);
+    if length < 0 {
+        bail!("returned a negative size ({length})");
+    }
+
+    Ok(length as usize)
+}
+
+impl<T> ContainerVariant<T>
+where
+    T: WasmMemory + CMemory,
+{
+    /// Returns the parameters used to pass this container to a C function.
+    pub(crate) fn into_c_params<S>(
+        self,
+        mut store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+    ) -> Result<Vec<Val>> {
+        let params = match self {
+            ContainerVariant::Single(single) => {
+                let address = allocator.call(&mut store, T::C_SIZE_OF as i32)? as usize;
+                single.into_c_memory(store, memory, allocator, address)?;
+
+                // the slot holds the value itself, e.g. the `char*` of a string
+                vec![Val::I32(i32::from_memory(store, memory, address)?)]
+            }
+            container => match container.variant_type().dimensions() {
+                1 => {
+                    let list = container.into_list().context(VECTORS_UNSUPPORTED_IN_C)?;
+                    let length = list.len();
+                    let address = array_into_memory(list, store, memory, allocator)?;
+
+                    vec![Val::I32(address as i32), Val::I32(length as i32)]
+                }
+                2 => {
+                    let grid = container.into_rows().context(VECTORS_UNSUPPORTED_IN_C)?;
+                    let rows = grid.len();
+                    let column_sizes = grid.iter().map(|row| row.len() as i32).collect::<Vec<_>>();
+
+                    let row_addresses = grid
+                        .into_iter()
+                        .map(|row| Ok(array_into_memory(row, store, memory, allocator)? as i32))
+                        .collect::<Result<Vec<_>>>()?;
+
+                    let address = array_into_memory(row_addresses, store, memory, allocator)?;
+                    let column_sizes = array_into_memory(column_sizes, store, memory, allocator)?;
+
+                    vec![
+                        Val::I32(address as i32),
+                        Val::I32(rows as i32),
+                        Val::I32(column_sizes as i32),
+                    ]
+                }
+                _ => bail!(VECTORS_UNSUPPORTED_IN_C),
+            },
+        };
+
+        Ok(params)
+    }
+
+    /// Reads a container returned from a C function. `pointer` is the function's return value,
+    /// and `out_params` are the addresses of the sizes written by the function. Single values
+    /// have no sizes, and are read from `pointer` itself.
+    pub(crate) fn from_c_memory<S>(
+        variant: &ContainerVariantType,
+        store: &mut Store<S>,
+        memory: &Memory,
+        pointer: usize,
+        out_params: &[usize],
+    ) -> Result<Self> {
+        let res = match variant {
+            ContainerVariantType::Single => {
+                ContainerVariant::Single(T::from_c_memory(store, memory, pointer)?)
+            }
+            ContainerVariantType::Graph | ContainerVariantType::Vector(_) => {
+                match variant.dimensions() {
+                    1 => {
+                        let length = read_length(store, memory, out_params[0])?;
+                        ContainerVariant::list(array_from_memory(store, memory, pointer, length)?)
+                    }
+                    2 => {
+                        let rows = read_length(store, memory, out_params[0])?;
+                        let column_sizes = i32::from_memory(store, memory, out_params[1])? as usize;
+                        let row_addresses =
+                            array_from_memory::<i32, S>(store, memory, pointer, rows)?;
+
+                        let grid = row_addresses
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, row)| {
+                                let length = read_length(store, memory, column_sizes + i * 4)?;
+                                array_from_memory(store, memory, row as usize, length)
+                            })
+                            .collect::<Result<Vec<_>>>()?;
+
+                        if *variant == ContainerVariantType::Graph {
+                            ContainerVariant::Graph(grid)
+                        } else {
+                            ContainerVariant::grid(grid)
+                        }
+                    }
+                    _ => bail!(VECTORS_UNSUPPORTED_IN_C),
+                }
+            }
+        };
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm_memory::test_setup;
+
+    #[test]
+    fn list_i32() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        let initial = (0..10).collect::<Vec<i32>>();
+        let params = ContainerVariant::list(initial.clone())
+            .into_c_params(&mut store, &memory, &allocator)?;
+
+        // the length is usually written by the function through `int* returnSize`
+        let size = allocator.call(&mut store, 4)? as usize;
+        params[1]
+            .unwrap_i32()
+            .into_memory(&mut store, &memory, &allocator, Some(size))?;
+
+        let result = ContainerVariant::<i32>::from_c_memory(
+            &ContainerVariantType::list(),
+            &mut store,
+            &memory,
+            params[0].unwrap_i32() as usize,
+            &[size],
+        )?;
+
+        assert_eq!(ContainerVariant::list(initial), result);
+
+        Ok(())
+    }
+
+    #[test]
+    fn grid_string() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        let initial = vec![
+            vec!["a".to_string(), "bc".to_string()],
+            vec![],
+            vec!["def".to_string()],
+        ];
+        let params = ContainerVariant::grid(initial.clone())
+            .into_c_params(&mut store, &memory, &allocator)?;
+
+        let size = allocator.call(&mut store, 4)? as usize;
+        params[1]
+            .unwrap_i32()
+            .into_memory(&mut store, &memory, &allocator, Some(size))?;
+        let column_sizes = allocator.call(&mut store, 4)? as usize;
+        params[2]
+            .unwrap_i32()
+            .into_memory(&mut store, &memory, &allocator, Some(column_sizes))?;
+
+        let result = ContainerVariant::<String>::from_c_memory(
+            &ContainerVariantType::grid(),
+            &mut store,
+            &memory,
+            params[0].unwrap_i32() as usize,
+            &[size, column_sizes],
+        )?;
+
+        assert_eq!(ContainerVariant::grid(initial), result);
+
+        Ok(())
+    }
+
+    #[test]
+    fn single_without_out_params() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        let address = allocator.call(&mut store, String::C_SIZE_OF as i32)? as usize;
+        "single"
+            .to_string()
+            .into_c_memory(&mut store, &memory, &allocator, address)?;
+
+        let result = ContainerVariant::<String>::from_c_memory(
+            &ContainerVariantType::Single,
+            &mut store,
+            &memory,
+            address,
+            &[],
+        )?;
+
+        assert_eq!(ContainerVariant::Single("single".to_string()), result);
+
+        Ok(())
+    }
+
+    #[test]
+    fn string() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        let initial = "hello world".to_string();
+        let address = c_string_into_memory(initial.clone(), &mut store, &memory, &allocator)?;
+
+        assert_eq!(initial, c_string_from_memory(&mut store, &memory, address)?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,825 @@
+use anyhow::{bail, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use wasmtime::*;
+
+use crate::{
+    align_to, container_from_memory, container_into_memory, AllocatorFunc, ContainerVariant,
+    ContainerVariantType, FunctionType, FunctionValue, StructKind, StructType, WasmMemory,
+};
+
+/// The type of a `std::set<T>`, or a `std::unordered_set<T>` if `unordered` is set.
+///
+/// Sets are always written and read in sorted order without duplicates, so outputs are compared
+/// as sets regardless of how the submission's set ordered them.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SetType {
+    #[serde(default)]
+    pub unordered: bool,
+    pub key: FunctionType,
+}
+
+/// The type of a `std::map<K, V>`, or a `std::unordered_map<K, V>` if `unordered` is set. Entries
+/// are sorted by their keys like the elements of a [`SetType`].
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MapType {
+    #[serde(default)]
+    pub unordered: bool,
+    pub key: FunctionType,
+    pub value: FunctionType,
+}
+
+impl SetType {
+    fn table(&self) -> Table {
+        Table {
+            unordered: self.unordered,
+            element: self.key.clone(),
+        }
+    }
+
+    pub(crate) fn cpp_layout(&self) -> (usize, usize) {
+        self.table().cpp_layout()
+    }
+
+    pub(crate) fn write<S>(
+        &self,
+        value: ContainerVariant<Vec<FunctionValue>>,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+        offset: Option<usize>,
+    ) -> Result<usize> {
+        let table = self.table();
+
+        container_into_memory(
+            value,
+            table.cpp_layout().0,
+            store,
+            memory,
+            allocator,
+            offset,
+            |keys, store, address| {
+                for key in &keys {
+                    check_type(key, &self.key)?;
+                }
+
+                let keys = canonical_set(keys);
+                let hashes = table.hashes(keys.iter())?;
+                table.write(keys, hashes, store, memory, allocator, address)
+            },
+        )
+    }
+
+    pub(crate) fn read<S>(
+        &self,
+        variant: &ContainerVariantType,
+        store: &mut Store<S>,
+        memory: &Memory,
+        offset: usize,
+    ) -> Result<ContainerVariant<Vec<FunctionValue>>> {
+        let table = self.table();
+
+        container_from_memory(
+            variant,
+            table.cpp_layout().0,
+            store,
+            memory,
+            offset,
+            |store, address| Ok(canonical_set(table.read(store, memory, address)?)),
+        )
+    }
+}
+
+impl MapType {
+    /// Maps store their entries as `std::pair<const K, V>`s
+    fn entry(&self) -> StructType {
+        StructType {
+            kind: StructKind::Pair,
+            fields: vec![self.key.clone(), self.value.clone()],
+        }
+    }
+
+    fn table(&self) -> Table {
+        Table {
+            unordered: self.unordered,
+            element: FunctionType::Struct(Box::new(self.entry()), ContainerVariantType::Single),
+        }
+    }
+
+    pub(crate) fn cpp_layout(&self) -> (usize, usize) {
+        self.table().cpp_layout()
+    }
+
+    pub(crate) fn write<S>(
+        &self,
+        value: ContainerVariant<Vec<(FunctionValue, FunctionValue)>>,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+        offset: Option<usize>,
+    ) -> Result<usize> {
+        let table = self.table();
+        let entry = Box::new(self.entry());
+
+        container_into_memory(
+            value,
+            table.cpp_layout().0,
+            store,
+            memory,
+            allocator,
+            offset,
+            |entries, store, address| {
+                for (key, value) in &entries {
+                    check_type(key, &self.key)?;
+                    check_type(value, &self.value)?;
+                }
+
+                let entries = canonical_map(entries);
+                let hashes = table.hashes(entries.iter().map(|(key, _)| key))?;
+                let entries = entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        FunctionValue::Struct(
+                            entry.clone(),
+                            ContainerVariant::Single(vec![key, value]),
+                        )
+                    })
+                    .collect();
+
+                table.write(entries, hashes, store, memory, allocator, address)
+            },
+        )
+    }
+
+    pub(crate) fn read<S>(
+        &self,
+        variant: &ContainerVariantType,
+        store: &mut Store<S>,
+        memory: &Memory,
+        offset: usize,
+    ) -> Result<ContainerVariant<Vec<(FunctionValue, FunctionValue)>>> {
+        let table = self.table();
+
+        container_from_memory(
+            variant,
+            table.cpp_layout().0,
+            store,
+            memory,
+            offset,
+            |store, address| {
+                let entries = table
+                    .read(store, memory, address)?
+                    .into_iter()
+                    .map(|entry| match entry {
+                        FunctionValue::Struct(_, ContainerVariant::Single(fields)) => {
+                            let [key, value]: [FunctionValue; 2] =
+                                fields.try_into().expect("map entries are pairs");
+                            (key, value)
+                        }
+                        _ => unreachable!("map entries are pairs"),
+                    })
+                    .collect();
+
+                Ok(canonical_map(entries))
+            },
+        )
+    }
+}
+
+/// maps to `std::set<T>`
+///
+/// Unordered sets need the `std::hash` of their keys, so they are only supported through
+/// [`SetType`].
+impl<T> WasmMemory for BTreeSet<T>
+where
+    T: WasmMemory + Ord,
+{
+    const CPP_SIZE_OF: usize = 12;
+    const CPP_ALIGN_OF: usize = 4;
+
+    fn into_memory<S>(
+        self,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+        offset: Option<usize>,
+    ) -> Result<usize> {
+        tree_into_memory(self.into_iter(), store, memory, allocator, offset)
+    }
+
+    fn from_memory<S>(store: &mut Store<S>, memory: &Memory, offset: usize) -> Result<Self> {
+        Ok(tree_from_memory::<T, S>(store, memory, offset)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// maps to `std::map<K, V>`, whose nodes hold a `std::pair<const K, V>`
+impl<K, V> WasmMemory for BTreeMap<K, V>
+where
+    K: WasmMemory + Ord,
+    V: WasmMemory,
+{
+    const CPP_SIZE_OF: usize = 12;
+    const CPP_ALIGN_OF: usize = 4;
+
+    fn into_memory<S>(
+        self,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+        offset: Option<usize>,
+    ) -> Result<usize> {
+        tree_into_memory(self.into_iter(), store, memory, allocator, offset)
+    }
+
+    fn from_memory<S>(store: &mut Store<S>, memory: &Memory, offset: usize) -> Result<Self> {
+        Ok(tree_from_memory::<(K, V), S>(store, memory, offset)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// Writes the sorted `elements` into a `std::__tree` at `offset`, returning its address.
+fn tree_into_memory<T: WasmMemory, S>(
+    elements: impl ExactSizeIterator<Item = T>,
+    store: &mut Store<S>,
+    memory: &Memory,
+    allocator: &AllocatorFunc,
+    offset: Option<usize>,
+) -> Result<usize> {
+    let address = match offset {
+        Some(offset) => offset,
+        None => allocator.call(&mut *store, 12)? as usize,
+    };
+
+    let element_offset = align_to(13, T::CPP_ALIGN_OF);
+    let node_size = element_offset + T::CPP_SIZE_OF;
+    let nodes = (0..elements.len())
+        .map(|_| Ok(allocator.call(&mut *store, node_size as i32)? as usize))
+        .collect::<Result<Vec<_>>>()?;
+
+    write_tree(&nodes, store, memory, address)?;
+
+    for (element, node) in elements.zip(nodes) {
+        element.into_memory(store, memory, allocator, Some(node + element_offset))?;
+    }
+
+    Ok(address)
+}
+
+fn tree_from_memory<T: WasmMemory, S>(
+    store: &mut Store<S>,
+    memory: &Memory,
+    offset: usize,
+) -> Result<Vec<T>> {
+    let element_offset = align_to(13, T::CPP_ALIGN_OF);
+
+    tree_nodes(store, memory, offset)?
+        .into_iter()
+        .map(|node| T::from_memory(store, memory, node + element_offset))
+        .collect()
+}
+
+fn check_type(value: &FunctionValue, expected: &FunctionType) -> Result<()> {
+    if value.function_type() != *expected {
+        bail!(
+            "Expected an element of type {:?}, but got {:?}",
+            expected,
+            value.function_type()
+        );
+    }
+
+    Ok(())
+}
+
+/// Sorts the keys of a set and removes duplicates, keeping the first like `std::set` would.
+pub(crate) fn canonical_set(mut keys: Vec<FunctionValue>) -> Vec<FunctionValue> {
+    keys.sort_by(FunctionValue::total_cmp);
+    keys.dedup_by(|right, left| left.total_cmp(right).is_eq());
+    keys
+}
+
+/// Sorts the entries of a map by their keys and removes duplicate keys, keeping the first.
+pub(crate) fn canonical_map(
+    mut entries: Vec<(FunctionValue, FunctionValue)>,
+) -> Vec<(FunctionValue, FunctionValue)> {
+    entries.sort_by(|(left, _), (right, _)| left.total_cmp(right));
+    entries.dedup_by(|(right, _), (left, _)| left.total_cmp(right).is_eq());
+    entries
+}
+
+/// The nodes of a libc++ `std::__tree` (`std::set` and `std::map`) or `std::__hash_table`
+/// (`std::unordered_set` and `std::unordered_map`), which each hold an `element`.
+struct Table {
+    unordered: bool,
+    element: FunctionType,
+}
+
+impl Table {
+    /// `std::__tree` is a pointer to its leftmost node, the end node, which only holds a pointer
+    /// to the root, and its size. `std::__hash_table` is a pointer to its buckets, the number of
+    /// buckets, the node before the first node, which only holds a pointer to the first node, its
+    /// size, and its maximum load factor.
+    fn cpp_layout(&self) -> (usize, usize) {
+        if self.unordered {
+            (20, 4)
+        } else {
+            (12, 4)
+        }
+    }
+
+    /// Where the element is placed in a node. Tree nodes start with pointers to their left child,
+    /// right child and parent, and whether they are black. Hash nodes start with a pointer to the
+    /// next node and the hash of their key.
+    fn element_offset(&self) -> usize {
+        let header = if self.unordered { 8 } else { 13 };
+        align_to(header, self.element.cpp_layout().1)
+    }
+
+    /// Hashes the keys of the elements, which is only needed by unordered tables.
+    fn hashes<'a>(&self, keys: impl Iterator<Item = &'a FunctionValue>) -> Result<Vec<u32>> {
+        if self.unordered {
+            keys.map(std_hash).collect()
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    /// Writes `elements`, which must already be sorted and unique, to `address`.
+    fn write<S>(
+        &self,
+        elements: Vec<FunctionValue>,
+        hashes: Vec<u32>,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+        address: usize,
+    ) -> Result<()> {
+        // Every node is a separate allocation, so the submission can free them when erasing
+        let node_size = self.element_offset() + self.element.cpp_layout().0;
+        let nodes = elements
+            .iter()
+            .map(|_| Ok(allocator.call(&mut *store, node_size as i32)? as usize))
+            .collect::<Result<Vec<_>>>()?;
+
+        if self.unordered {
+            write_hash_table(&nodes, &hashes, store, memory, allocator, address)?;
+        } else {
+            write_tree(&nodes, store, memory, address)?;
+        }
+
+        for (element, node) in elements.into_iter().zip(nodes) {
+            element.into_memory(store, memory, allocator, Some(node + self.element_offset()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the elements of the table at `address`, in the order they are stored.
+    fn read<S>(
+        &self,
+        store: &mut Store<S>,
+        memory: &Memory,
+        address: usize,
+    ) -> Result<Vec<FunctionValue>> {
+        let nodes = if self.unordered {
+            hash_table_nodes(store, memory, address)?
+        } else {
+            tree_nodes(store, memory, address)?
+        };
+
+        nodes
+            .into_iter()
+            .map(|node| {
+                self.element
+                    .read_from_memory(store, memory, node + self.element_offset())
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn read_pointer<S>(
+    store: &mut Store<S>,
+    memory: &Memory,
+    address: usize,
+) -> Result<usize> {
+    let mut buf = [0; 4];
+    memory.read(store, address, &mut buf)?;
+
+    Ok(LittleEndian::read_u32(&buf) as usize)
+}
+
+pub(crate) fn write_words<S>(
+    store: &mut Store<S>,
+    memory: &Memory,
+    address: usize,
+    words: &[u32],
+) -> Result<()> {
+    let mut data = vec![0; words.len() * 4];
+    LittleEndian::write_u32_into(words, &mut data);
+    memory.write(store, address, &data)?;
+
+    Ok(())
+}
+
+/// Links the sorted `nodes` into a red-black tree. The tree is balanced by always picking the
+/// middle node as the root of a subtree, so every path from the root to a leaf has at least
+/// `floor(log2(n + 1))` nodes. Those nodes are black, and the nodes below them are red.
+fn write_tree<S>(
+    nodes: &[usize],
+    store: &mut Store<S>,
+    memory: &Memory,
+    address: usize,
+) -> Result<()> {
+    let end_node = address + 4;
+    let red_depth = (nodes.len() + 1).ilog2();
+
+    let root = link_subtree(nodes, end_node, 0, red_depth, store, memory)?;
+    let begin_node = nodes.first().copied().unwrap_or(end_node);
+
+    write_words(
+        store,
+        memory,
+        address,
+        &[begin_node as u32, root as u32, nodes.len() as u32],
+    )
+}
+
+/// Links `nodes` into a subtree below `parent`, returning its root, or 0 if it's empty.
+fn link_subtree<S>(
+    nodes: &[usize],
+    parent: usize,
+    depth: u32,
+    red_depth: u32,
+    store: &mut Store<S>,
+    memory: &Memory,
+) -> Result<usize> {
+    if nodes.is_empty() {
+        return Ok(0);
+    }
+
+    let middle = nodes.len() / 2;
+    let node = nodes[middle];
+
+    let left = link_subtree(&nodes[..middle], node, depth + 1, red_depth, store, memory)?;
+    let right = link_subtree(
+        &nodes[middle + 1..],
+        node,
+        depth + 1,
+        red_depth,
+        store,
+        memory,
+    )?;
+
+    write_words(
+        store,
+        memory,
+        node,
+        &[left as u32, right as u32, parent as u32],
+    )?;
+    memory.write(&mut *store, node + 12, &[(depth != red_depth) as u8])?;
+
+    Ok(node)
+}
+
+/// Returns the nodes of the tree at `address` in order.
+fn tree_nodes<S>(store: &mut Store<S>, memory: &Memory, address: usize) -> Result<Vec<usize>> {
+    let mut nodes = vec![];
+    let mut stack = vec![];
+    let mut node = read_pointer(store, memory, address + 4)?;
+
+    while node != 0 || !stack.is_empty() {
+        while node != 0 {
+            stack.push(node);
+            node = read_pointer(store, memory, node)?;
+        }
+
+        let Some(next) = stack.pop() else { break };
+        nodes.push(next);
+        node = read_pointer(store, memory, next + 4)?;
+    }
+
+    Ok(nodes)
+}
+
+/// Links `nodes` into a hash table. Its nodes form a singly linked list where the nodes of each
+/// bucket are next to each other, and each bucket points to the node before its first node.
+fn write_hash_table<S>(
+    nodes: &[usize],
+    hashes: &[u32],
+    store: &mut Store<S>,
+    memory: &Memory,
+    allocator: &AllocatorFunc,
+    address: usize,
+) -> Result<()> {
+    // `max_load_factor` is 1 by default
+    let max_load_factor = 1.0f32.to_bits();
+
+    if nodes.is_empty() {
+        return write_words(store, memory, address, &[0, 0, 0, 0, max_load_factor]);
+    }
+
+    let bucket_count = next_prime(nodes.len().max(2));
+    let mut buckets = BTreeMap::<usize, Vec<usize>>::new();
+    for (i, hash) in hashes.iter().enumerate() {
+        buckets
+            .entry(constrain_hash(*hash as usize, bucket_count))
+            .or_default()
+            .push(i);
+    }
+
+    let bucket_list = allocator.call(&mut *store, (bucket_count * 4) as i32)? as usize;
+    write_words(store, memory, bucket_list, &vec![0; bucket_count])?;
+
+    // the first bucket points to the node before the first node, which is part of the table
+    let mut previous = address + 8;
+    for (bucket, elements) in buckets {
+        write_words(store, memory, bucket_list + bucket * 4, &[previous as u32])?;
+
+        for i in elements {
+            write_words(store, memory, previous, &[nodes[i] as u32])?;
+            write_words(store, memory, nodes[i], &[0, hashes[i]])?;
+            previous = nodes[i];
+        }
+    }
+
+    write_words(
+        store,
+        memory,
+        address,
+        &[bucket_list as u32, bucket_count as u32],
+    )?;
+    write_words(
+        store,
+        memory,
+        address + 12,
+        &[nodes.len() as u32, max_load_factor],
+    )
+}
+
+/// Returns the nodes of the hash table at `address`, in the order they are linked.
+fn hash_table_nodes<S>(
+    store: &mut Store<S>,
+    memory: &Memory,
+    address: usize,
+) -> Result<Vec<usize>> {
+    let mut nodes = vec![];
+    let mut node = read_pointer(store, memory, address + 8)?;
+
+    while node != 0 {
+        nodes.push(node);
+        node = read_pointer(store, memory, node)?;
+    }
+
+    Ok(nodes)
+}
+
+/// The bucket a hash belongs to, computed the same way as libc++'s `__constrain_hash`
+fn constrain_hash(hash: usize, bucket_count: usize) -> usize {
+    if bucket_count.is_power_of_two() {
+        hash & (bucket_count - 1)
+    } else if hash < bucket_count {
+        hash
+    } else {
+        hash % bucket_count
+    }
+}
+
+fn next_prime(n: usize) -> usize {
+    (n..)
+        .find(|&candidate| {
+            (2..)
+                .take_while(|d| d * d <= candidate)
+                .all(|d| candidate % d != 0)
+        })
+        .expect("there are infinitely many primes")
+}
+
+/// The hash libc++'s `std::hash` gives `key` on wasm32, where `size_t` is 32 bits.
+fn std_hash(key: &FunctionValue) -> Result<u32> {
+    let hash = match key {
+        FunctionValue::Int(ContainerVariant::Single(i)) => *i as u32,
+        // chars are signed, so they're sign extended
+        FunctionValue::Char(ContainerVariant::Single(c)) => *c as u8 as i8 as u32,
+        FunctionValue::Bool(ContainerVariant::Single(b)) => *b as u32,
+        FunctionValue::UInt(ContainerVariant::Single(u)) => *u,
+        FunctionValue::Short(ContainerVariant::Single(s)) => *s as u32,
+        FunctionValue::Byte(ContainerVariant::Single(b)) => *b as u32,
+        // integers wider than `size_t` are hashed as bytes
+        FunctionValue::Long(ContainerVariant::Single(l)) => murmur2(&l.to_le_bytes()),
+        FunctionValue::ULong(ContainerVariant::Single(u)) => murmur2(&u.to_le_bytes()),
+        FunctionValue::Int128(ContainerVariant::Single(i)) => murmur2(&i.to_le_bytes()),
+        // -0.0 and 0.0 are equal, so they must hash to the same value
+        FunctionValue::Float(ContainerVariant::Single(f)) if *f == 0.0 => 0,
+        FunctionValue::Float(ContainerVariant::Single(f)) => f.to_bits(),
+        FunctionValue::Double(ContainerVariant::Single(d)) if *d == 0.0 => 0,
+        FunctionValue::Double(ContainerVariant::Single(d)) => murmur2(&d.to_le_bytes()),
+        FunctionValue::String(ContainerVariant::Single(s)) => murmur2(s.as_bytes()),
+        _ => bail!(
+            "Unordered containers can't hold {:?}, since it has no `std::hash`",
+            key.function_type()
+        ),
+    };
+
+    Ok(hash)
+}
+
+/// libc++'s `__murmur2_or_cityhash` for a 32 bit `size_t`
+fn murmur2(data: &[u8]) -> u32 {
+    const M: u32 = 0x5bd1e995;
+    const R: u32 = 24;
+
+    let mut hash = data.len() as u32;
+    let mut chunks = data.chunks_exact(4);
+
+    for chunk in &mut chunks {
+        let mut k = LittleEndian::read_u32(chunk);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        hash = hash.wrapping_mul(M);
+        hash ^= k;
+    }
+
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+        for (i, byte) in rest.iter().enumerate().rev() {
+            hash ^= (*byte as u32) << (8 * i);
+        }
+        hash = hash.wrapping_mul(M);
+    }
+
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(M);
+    hash ^= hash >> 15;
+
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm_memory::test_setup;
+
+    fn ints(values: impl IntoIterator<Item = i32>) -> Vec<FunctionValue> {
+        values
+            .into_iter()
+            .map(|i| FunctionValue::Int(ContainerVariant::Single(i)))
+            .collect()
+    }
+
+    /// Returns the black height of the subtree at `node`, checking that it's a valid red-black
+    /// tree whose nodes point to their parents.
+    fn black_height(
+        store: &mut Store<usize>,
+        memory: &Memory,
+        node: usize,
+        parent: usize,
+        parent_red: bool,
+    ) -> Result<usize> {
+        if node == 0 {
+            return Ok(1);
+        }
+
+        let left = read_pointer(store, memory, node)?;
+        let right = read_pointer(store, memory, node + 4)?;
+        assert_eq!(read_pointer(store, memory, node + 8)?, parent);
+
+        let mut is_black = [0];
+        memory.read(&mut *store, node + 12, &mut is_black)?;
+        let red = is_black[0] == 0;
+        assert!(!(red && parent_red), "red nodes can't have red children");
+
+        let left_height = black_height(store, memory, left, node, red)?;
+        let right_height = black_height(store, memory, right, node, red)?;
+        assert_eq!(left_height, right_height);
+
+        Ok(left_height + !red as usize)
+    }
+
+    #[test]
+    fn set() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        let set = SetType {
+            unordered: false,
+            key: FunctionType::Int(ContainerVariantType::Single),
+        };
+
+        for n in 0..40 {
+            let keys = ints((0..n).rev().chain(0..n / 2));
+            let address = set.write(
+                ContainerVariant::Single(keys),
+                &mut store,
+                &memory,
+                &allocator,
+                None,
+            )?;
+
+            let root = read_pointer(&mut store, &memory, address + 4)?;
+            black_height(&mut store, &memory, root, address + 4, true)?;
+
+            let begin = read_pointer(&mut store, &memory, address)?;
+            let nodes = tree_nodes(&mut store, &memory, address)?;
+            assert_eq!(begin, nodes.first().copied().unwrap_or(address + 4));
+            assert_eq!(read_pointer(&mut store, &memory, address + 8)?, n as usize);
+
+            let result = set.read(&ContainerVariantType::Single, &mut store, &memory, address)?;
+            assert_eq!(result, ContainerVariant::Single(ints(0..n)));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn btree_map() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        let map: BTreeMap<i64, Vec<i32>> = (0..10).map(|i| (i, vec![i as i32; 3])).collect();
+        let address = map
+            .clone()
+            .into_memory(&mut store, &memory, &allocator, None)?;
+
+        // `std::pair<const long long, std::vector<int>>` is padded to start at 16
+        let root = read_pointer(&mut store, &memory, address + 4)?;
+        assert_eq!(
+            i64::from_memory(&mut store, &memory, root + 16)?,
+            *map.keys().nth(5).unwrap()
+        );
+        assert_eq!(BTreeMap::from_memory(&mut store, &memory, address)?, map);
+
+        Ok(())
+    }
+
+    #[test]
+    fn unordered_map() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        let map = MapType {
+            unordered: true,
+            key: FunctionType::String(ContainerVariantType::Single),
+            value: FunctionType::Long(ContainerVariantType::Single),
+        };
+        let entries = (0..20)
+            .map(|i| {
+                (
+                    FunctionValue::String(ContainerVariant::Single(format!("key {i}"))),
+                    FunctionValue::Long(ContainerVariant::Single(i)),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let address = map.write(
+            ContainerVariant::Single(entries.clone()),
+            &mut store,
+            &memory,
+            &allocator,
+            None,
+        )?;
+
+        let buckets = read_pointer(&mut store, &memory, address)?;
+        let bucket_count = read_pointer(&mut store, &memory, address + 4)?;
+        assert_eq!(bucket_count, 23);
+        assert_eq!(read_pointer(&mut store, &memory, address + 12)?, 20);
+
+        // every bucket points to the node before its first node
+        let mut previous = address + 8;
+        let mut seen = vec![];
+        for node in hash_table_nodes(&mut store, &memory, address)? {
+            let hash = read_pointer(&mut store, &memory, node + 4)?;
+            let bucket = constrain_hash(hash, bucket_count);
+
+            if !seen.contains(&bucket) {
+                assert_eq!(
+                    read_pointer(&mut store, &memory, buckets + bucket * 4)?,
+                    previous
+                );
+                seen.push(bucket);
+            }
+            previous = node;
+        }
+
+        let result = map.read(&ContainerVariantType::Single, &mut store, &memory, address)?;
+        assert_eq!(result, ContainerVariant::Single(canonical_map(entries)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn std_hashes() -> Result<()> {
+        assert_eq!(
+            std_hash(&FunctionValue::Int(ContainerVariant::Single(-1)))?,
+            u32::MAX
+        );
+        assert_eq!(
+            std_hash(&FunctionValue::Double(ContainerVariant::Single(-0.0)))?,
+            0
+        );
+        assert_eq!(murmur2(b""), 0);
+        assert!(std_hash(&FunctionValue::Int(ContainerVariant::list(vec![]))).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,605 @@
+use anyhow::{bail, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use serde::{Deserialize, Serialize, Serializer};
+use wasmtime::*;
+
+use crate::{AllocatorFunc, SizeMetrics, WasmMemory};
+
+/// The shape of a value, e.g. `Vector(Vector(Single))` for `vector<vector<int>>`.
+///
+/// Lists and grids are serialized as `"List"` and `"Grid"`, and nested vectors as the type of
+/// their elements, e.g. `{"Vector": "Grid"}` for `vector<vector<vector<int>>>`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(from = "ContainerTypeRepr", into = "ContainerTypeRepr")]
+pub enum ContainerVariantType {
+    /// An adjacency list, which is passed like a grid, but scales with its nodes and edges when
+    /// estimating complexity
+    Graph,
+    Single,
+
+    /// A `std::vector` of containers of the given type
+    Vector(Box<ContainerVariantType>),
+}
+
+/// A value of the shape described by [`ContainerVariantType`], which is serialized like it, e.g.
+/// `{"List": [1, 2]}` or `{"Vector": ["Grid", [{"Grid": [[1, 2]]}, {"Grid": [[3]]}]]}`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(from = "ContainerRepr<T>")]
+pub enum ContainerVariant<T> {
+    Graph(Vec<Vec<T>>),
+    Single(T),
+
+    /// A `std::vector` of containers of the given type, which is kept so that empty vectors still
+    /// have one
+    Vector(Box<ContainerVariantType>, Vec<ContainerVariant<T>>),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ContainerVariantType")]
+enum ContainerTypeRepr {
+    Graph,
+    Grid,
+    List,
+    Single,
+    Vector(Box<ContainerVariantType>),
+}
+
+impl From<ContainerTypeRepr> for ContainerVariantType {
+    fn from(repr: ContainerTypeRepr) -> Self {
+        match repr {
+            ContainerTypeRepr::Graph => ContainerVariantType::Graph,
+            ContainerTypeRepr::Grid => ContainerVariantType::grid(),
+            ContainerTypeRepr::List => ContainerVariantType::list(),
+            ContainerTypeRepr::Single => ContainerVariantType::Single,
+            ContainerTypeRepr::Vector(ty) => ContainerVariantType::Vector(ty),
+        }
+    }
+}
+
+impl From<ContainerVariantType> for ContainerTypeRepr {
+    fn from(ty: ContainerVariantType) -> Self {
+        match ty {
+            ContainerVariantType::Graph => ContainerTypeRepr::Graph,
+            ContainerVariantType::Single => ContainerTypeRepr::Single,
+            ty if ty == ContainerVariantType::list() => ContainerTypeRepr::List,
+            ty if ty == ContainerVariantType::grid() => ContainerTypeRepr::Grid,
+            ContainerVariantType::Vector(ty) => ContainerTypeRepr::Vector(ty),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "ContainerVariant")]
+enum ContainerRepr<T> {
+    Graph(Vec<Vec<T>>),
+    Grid(Vec<Vec<T>>),
+    List(Vec<T>),
+    Single(T),
+    Vector(Box<ContainerVariantType>, Vec<ContainerVariant<T>>),
+}
+
+impl<T> From<ContainerRepr<T>> for ContainerVariant<T> {
+    fn from(repr: ContainerRepr<T>) -> Self {
+        match repr {
+            ContainerRepr::Graph(graph) => ContainerVariant::Graph(graph),
+            ContainerRepr::Grid(grid) => ContainerVariant::grid(grid),
+            ContainerRepr::List(list) => ContainerVariant::list(list),
+            ContainerRepr::Single(single) => ContainerVariant::Single(single),
+            ContainerRepr::Vector(ty, elements) => ContainerVariant::Vector(ty, elements),
+        }
+    }
+}
+
+/// Serializes a [`ContainerVariant`] without cloning its elements.
+#[derive(Serialize)]
+#[serde(rename = "ContainerVariant")]
+enum ContainerRef<'a, T> {
+    Graph(&'a [Vec<T>]),
+    Grid(Vec<Vec<&'a T>>),
+    List(Vec<&'a T>),
+    Single(&'a T),
+    Vector(&'a ContainerVariantType, &'a [ContainerVariant<T>]),
+}
+
+impl<T: Serialize> Serialize for ContainerVariant<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match self {
+            ContainerVariant::Graph(graph) => ContainerRef::Graph(graph),
+            ContainerVariant::Single(single) => ContainerRef::Single(single),
+            ContainerVariant::Vector(ty, elements) => {
+                if let Some(list) = self.as_list() {
+                    ContainerRef::List(list)
+                } else if let Some(rows) = self.as_rows() {
+                    ContainerRef::Grid(rows)
+                } else {
+                    ContainerRef::Vector(ty, elements)
+                }
+            }
+        };
+
+        repr.serialize(serializer)
+    }
+}
+
+impl ContainerVariantType {
+    /// The type of a `std::vector` of single elements
+    pub fn list() -> Self {
+        ContainerVariantType::Vector(Box::new(ContainerVariantType::Single))
+    }
+
+    /// The type of a `std::vector` of lists
+    pub fn grid() -> Self {
+        ContainerVariantType::Vector(Box::new(ContainerVariantType::list()))
+    }
+
+    /// How many vectors are nested in this type, e.g. 2 for grids and graphs.
+    pub fn dimensions(&self) -> usize {
+        match self {
+            ContainerVariantType::Graph => 2,
+            ContainerVariantType::Single => 0,
+            ContainerVariantType::Vector(ty) => ty.dimensions() + 1,
+        }
+    }
+
+    /// The distance between elements of this type in a `std::vector`, where a single element
+    /// takes up `size` bytes. Every other container is a `std::vector` itself.
+    fn stride(&self, size: usize) -> usize {
+        match self {
+            ContainerVariantType::Single => size,
+            _ => Vec::<i32>::CPP_SIZE_OF,
+        }
+    }
+}
+
+impl<T> ContainerVariant<T>
+where
+    T: WasmMemory,
+{
+    pub(crate) fn into_memory<S>(
+        self,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+        offset: Option<usize>,
+    ) -> Result<usize> {
+        container_into_memory(
+            self,
+            T::CPP_SIZE_OF,
+            store,
+            memory,
+            allocator,
+            offset,
+            |element, store, address| {
+                element.into_memory(store, memory, allocator, Some(address))?;
+                Ok(())
+            },
+        )
+    }
+
+    pub(crate) fn from_memory<S>(
+        variant: &ContainerVariantType,
+        store: &mut Store<S>,
+        memory: &Memory,
+        offset: usize,
+    ) -> Result<Self> {
+        container_from_memory(
+            variant,
+            T::CPP_SIZE_OF,
+            store,
+            memory,
+            offset,
+            |store, address| T::from_memory(store, memory, address),
+        )
+    }
+}
+
+impl<T> ContainerVariant<T> {
+    /// A `std::vector` of `elements`
+    pub fn list(elements: Vec<T>) -> Self {
+        ContainerVariant::Vector(
+            Box::new(ContainerVariantType::Single),
+            elements.into_iter().map(ContainerVariant::Single).collect(),
+        )
+    }
+
+    /// A `std::vector` of `rows`
+    pub fn grid(rows: Vec<Vec<T>>) -> Self {
+        ContainerVariant::Vector(
+            Box::new(ContainerVariantType::list()),
+            rows.into_iter().map(ContainerVariant::list).collect(),
+        )
+    }
+
+    /// The elements of a list, or `None` for other containers.
+    pub fn into_list(self) -> Option<Vec<T>> {
+        match self {
+            ContainerVariant::Vector(ty, elements) if *ty == ContainerVariantType::Single => {
+                elements
+                    .into_iter()
+                    .map(|element| match element {
+                        ContainerVariant::Single(single) => Some(single),
+                        _ => None,
+                    })
+                    .collect()
+            }
+            _ => None,
+        }
+    }
+
+    /// The rows of a grid or graph, or `None` for other containers.
+    pub fn into_rows(self) -> Option<Vec<Vec<T>>> {
+        match self {
+            ContainerVariant::Graph(rows) => Some(rows),
+            ContainerVariant::Vector(ty, elements) if *ty == ContainerVariantType::list() => {
+                elements
+                    .into_iter()
+                    .map(ContainerVariant::into_list)
+                    .collect()
+            }
+            _ => None,
+        }
+    }
+
+    fn as_list(&self) -> Option<Vec<&T>> {
+        match self {
+            ContainerVariant::Vector(ty, elements) if **ty == ContainerVariantType::Single => {
+                elements
+                    .iter()
+                    .map(|element| match element {
+                        ContainerVariant::Single(single) => Some(single),
+                        _ => None,
+                    })
+                    .collect()
+            }
+            _ => None,
+        }
+    }
+
+    fn as_rows(&self) -> Option<Vec<Vec<&T>>> {
+        match self {
+            ContainerVariant::Graph(rows) => {
+                Some(rows.iter().map(|row| row.iter().collect()).collect())
+            }
+            ContainerVariant::Vector(ty, elements) if **ty == ContainerVariantType::list() => {
+                elements.iter().map(ContainerVariant::as_list).collect()
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn map<U>(self, f: impl Fn(T) -> U) -> ContainerVariant<U> {
+        self.map_with(&f)
+    }
+
+    fn map_with<U>(self, f: &dyn Fn(T) -> U) -> ContainerVariant<U> {
+        match self {
+            ContainerVariant::Graph(graph) => ContainerVariant::Graph(
+                graph
+                    .into_iter()
+                    .map(|row| row.into_iter().map(f).collect())
+                    .collect(),
+            ),
+            ContainerVariant::Single(single) => ContainerVariant::Single(f(single)),
+            ContainerVariant::Vector(ty, elements) => {
+                ContainerVariant::Vector(ty, elements.into_iter().map(|e| e.map_with(f)).collect())
+            }
+        }
+    }
+
+    pub(crate) fn variant_type(&self) -> ContainerVariantType {
+        match self {
+            ContainerVariant::Graph(_) => ContainerVariantType::Graph,
+            ContainerVariant::Single(_) => ContainerVariantType::Single,
+            ContainerVariant::Vector(ty, _) => ContainerVariantType::Vector(ty.clone()),
+        }
+    }
+
+    /// The number of elements in the container
+    fn element_count(&self) -> usize {
+        match self {
+            ContainerVariant::Graph(rows) => rows.iter().map(Vec::len).sum(),
+            ContainerVariant::Single(_) => 1,
+            ContainerVariant::Vector(_, elements) => {
+                elements.iter().map(ContainerVariant::element_count).sum()
+            }
+        }
+    }
+
+    /// How large the container is for estimating complexity, where `single` gives the size of a
+    /// single element.
+    pub(crate) fn scaling_factor<S: Into<SizeMetrics>>(
+        &self,
+        single: impl Fn(&T) -> S,
+    ) -> SizeMetrics {
+        let total = self.element_count() as f32;
+
+        match self {
+            ContainerVariant::Single(s) => single(s).into(),
+            ContainerVariant::Vector(ty, rows) if **ty == ContainerVariantType::list() => {
+                SizeMetrics {
+                    rows: rows.len() as f32,
+                    cols: rows.first().map_or(0, ContainerVariant::element_count) as f32,
+                    total,
+                    ..Default::default()
+                }
+            }
+            ContainerVariant::Vector(..) => total.into(),
+            // each row is the list of a node's neighbors
+            ContainerVariant::Graph(g) => SizeMetrics {
+                nodes: g.len() as f32,
+                edges: total,
+                total: g.len() as f32 + total,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Writes a container of elements which take up `size` bytes, and are written in place by `write`,
+/// returning its address. Vectors are written as a `std::vector` of their elements, and graphs as
+/// grids.
+pub(crate) fn container_into_memory<T, S>(
+    value: ContainerVariant<T>,
+    size: usize,
+    store: &mut Store<S>,
+    memory: &Memory,
+    allocator: &AllocatorFunc,
+    offset: Option<usize>,
+    mut write: impl FnMut(T, &mut Store<S>, usize) -> Result<()>,
+) -> Result<usize> {
+    write_container(value, size, store, memory, allocator, offset, &mut write)
+}
+
+type WriteElement<'a, T, S> = dyn FnMut(T, &mut Store<S>, usize) -> Result<()> + 'a;
+type ReadElement<'a, T, S> = dyn FnMut(&mut Store<S>, usize) -> Result<T> + 'a;
+
+fn write_container<T, S>(
+    value: ContainerVariant<T>,
+    size: usize,
+    store: &mut Store<S>,
+    memory: &Memory,
+    allocator: &AllocatorFunc,
+    offset: Option<usize>,
+    write: &mut WriteElement<T, S>,
+) -> Result<usize> {
+    match value {
+        ContainerVariant::Single(element) => {
+            let address = match offset {
+                Some(offset) => offset,
+                None => allocator.call(&mut *store, size as i32)? as usize,
+            };
+
+            write(element, store, address)?;
+            Ok(address)
+        }
+        // graphs are passed as `vector<vector<int>>`s of each node's neighbors
+        ContainerVariant::Graph(rows) => write_container(
+            ContainerVariant::grid(rows),
+            size,
+            store,
+            memory,
+            allocator,
+            offset,
+            write,
+        ),
+        ContainerVariant::Vector(ty, elements) => {
+            if let Some(element) = elements.iter().find(|e| e.variant_type() != *ty) {
+                bail!(
+                    "Expected every element of the vector to be a {ty:?}, but found a {:?}",
+                    element.variant_type()
+                );
+            }
+
+            let stride = ty.stride(size);
+            let (address, buffer_address) =
+                vector_into_memory(store, memory, allocator, offset, stride, elements.len())?;
+
+            for (i, element) in elements.into_iter().enumerate() {
+                let element_address = buffer_address + i * stride;
+                write_container(
+                    element,
+                    size,
+                    store,
+                    memory,
+                    allocator,
+                    Some(element_address),
+                    write,
+                )?;
+            }
+
+            Ok(address)
+        }
+    }
+}
+
+/// Reads a container written by [`container_into_memory`], where each element is read by `read`.
+pub(crate) fn container_from_memory<T, S>(
+    variant: &ContainerVariantType,
+    size: usize,
+    store: &mut Store<S>,
+    memory: &Memory,
+    offset: usize,
+    mut read: impl FnMut(&mut Store<S>, usize) -> Result<T>,
+) -> Result<ContainerVariant<T>> {
+    read_container(variant, size, store, memory, offset, &mut read)
+}
+
+fn read_container<T, S>(
+    variant: &ContainerVariantType,
+    size: usize,
+    store: &mut Store<S>,
+    memory: &Memory,
+    offset: usize,
+    read: &mut ReadElement<T, S>,
+) -> Result<ContainerVariant<T>> {
+    let res = match variant {
+        ContainerVariantType::Single => ContainerVariant::Single(read(store, offset)?),
+        ContainerVariantType::Graph => {
+            let grid = read_container(
+                &ContainerVariantType::grid(),
+                size,
+                store,
+                memory,
+                offset,
+                read,
+            )?;
+
+            ContainerVariant::Graph(grid.into_rows().expect("grids have rows"))
+        }
+        ContainerVariantType::Vector(ty) => {
+            let elements = vector_from_memory(store, memory, offset, ty.stride(size))?
+                .into_iter()
+                .map(|address| read_container(ty, size, store, memory, address, read))
+                .collect::<Result<Vec<_>>>()?;
+
+            ContainerVariant::Vector(ty.clone(), elements)
+        }
+    };
+
+    Ok(res)
+}
+
+/// Writes the header of a `std::vector` with `len` elements which are `stride` bytes apart,
+/// returning the address of the vector and the address of its buffer.
+fn vector_into_memory<S>(
+    mut store: &mut Store<S>,
+    memory: &Memory,
+    allocator: &AllocatorFunc,
+    offset: Option<usize>,
+    stride: usize,
+    len: usize,
+) -> Result<(usize, usize)> {
+    let address = match offset {
+        Some(offset) => offset,
+        None => allocator.call(&mut store, Vec::<i32>::CPP_SIZE_OF as i32)? as usize,
+    };
+    let buffer_address = allocator.call(&mut store, (stride * len) as i32)? as usize;
+
+    let mut data = [0; 12];
+    LittleEndian::write_i32_into(
+        &[
+            buffer_address as i32,                  // start pointer
+            (buffer_address + stride * len) as i32, // end pointer
+            (buffer_address + stride * len) as i32, // capacity pointer
+        ],
+        &mut data,
+    );
+    memory.write(&mut store, address, &data)?;
+
+    Ok((address, buffer_address))
+}
+
+/// Returns the addresses of the elements of the `std::vector` at `offset`.
+fn vector_from_memory<S>(
+    store: &mut Store<S>,
+    memory: &Memory,
+    offset: usize,
+    stride: usize,
+) -> Result<Vec<usize>> {
+    let mut buf = [0; 8];
+    memory.read(store, offset, &mut buf)?;
+
+    let start = LittleEndian::read_u32(&buf) as usize;
+    let end = LittleEndian::read_u32(&buf[4..]) as usize;
+
+    Ok((start..end).step_by(stride).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm_memory::test_setup;
+
+    #[test]
+    fn nested_vectors() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        // vector<vector<vector<long>>>
+        let ty = ContainerVariantType::Vector(Box::new(ContainerVariantType::grid()));
+        let table = ContainerVariant::Vector(
+            Box::new(ContainerVariantType::grid()),
+            vec![
+                ContainerVariant::grid(vec![vec![1i64, 2], vec![3]]),
+                ContainerVariant::grid(vec![]),
+                ContainerVariant::grid(vec![vec![4, 5, 6]]),
+            ],
+        );
+
+        let address = table
+            .clone()
+            .into_memory(&mut store, &memory, &allocator, None)?;
+        let result = ContainerVariant::<i64>::from_memory(&ty, &mut store, &memory, address)?;
+        assert_eq!(result, table);
+
+        // graphs are written the same way as grids
+        let rows = vec![vec!["a".to_string(), "bc".to_string()], vec![]];
+        let address = ContainerVariant::Graph(rows.clone())
+            .into_memory(&mut store, &memory, &allocator, None)?;
+        let grid = ContainerVariant::<String>::from_memory(
+            &ContainerVariantType::grid(),
+            &mut store,
+            &memory,
+            address,
+        )?;
+        assert_eq!(grid, ContainerVariant::grid(rows.clone()));
+        let graph = ContainerVariant::<String>::from_memory(
+            &ContainerVariantType::Graph,
+            &mut store,
+            &memory,
+            address,
+        )?;
+        assert_eq!(graph, ContainerVariant::Graph(rows));
+
+        let mismatched = ContainerVariant::Vector(
+            Box::new(ContainerVariantType::list()),
+            vec![ContainerVariant::Single(1)],
+        );
+        assert!(mismatched
+            .into_memory(&mut store, &memory, &allocator, None)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn serialized_shapes() -> Result<()> {
+        let roundtrip = |value: &ContainerVariant<i32>, json: &str| -> Result<()> {
+            assert_eq!(serde_json::to_string(value)?, json);
+            assert_eq!(&serde_json::from_str::<ContainerVariant<i32>>(json)?, value);
+            Ok(())
+        };
+
+        roundtrip(&ContainerVariant::Single(1), r#"{"Single":1}"#)?;
+        roundtrip(&ContainerVariant::list(vec![1, 2]), r#"{"List":[1,2]}"#)?;
+        roundtrip(
+            &ContainerVariant::grid(vec![vec![1], vec![]]),
+            r#"{"Grid":[[1],[]]}"#,
+        )?;
+        roundtrip(
+            &ContainerVariant::Graph(vec![vec![1]]),
+            r#"{"Graph":[[1]]}"#,
+        )?;
+        roundtrip(
+            &ContainerVariant::Vector(
+                Box::new(ContainerVariantType::grid()),
+                vec![ContainerVariant::grid(vec![vec![1]])],
+            ),
+            r#"{"Vector":["Grid",[{"Grid":[[1]]}]]}"#,
+        )?;
+
+        // vectors of lists are grids, however they were written
+        let grid = serde_json::from_str::<ContainerVariant<i32>>(
+            r#"{"Vector":["List",[{"List":[1]},{"Vector":["Single",[{"Single":2}]]}]]}"#,
+        )?;
+        assert_eq!(grid, ContainerVariant::grid(vec![vec![1], vec![2]]));
+
+        let ty = ContainerVariantType::Vector(Box::new(ContainerVariantType::grid()));
+        assert_eq!(serde_json::to_string(&ty)?, r#"{"Vector":"Grid"}"#);
+        assert_eq!(
+            serde_json::from_str::<ContainerVariantType>(r#"{"Vector":{"Vector":"List"}}"#)?,
+            ty
+        );
+
+        Ok(())
+    }
+}
@@ -1,176 +1,456 @@
 use serde::{Deserialize, Serialize};
 use sqlx::Type;
+use std::f64::consts::LN_2;
 
-use crate::WasmFunctionCall;
-
-trait RuntimeFactor {
-    fn get_factor(&self) -> u32;
-}
+use crate::{FunctionValue, WasmFunctionCall};
 
+/// Classes of complexity, from the most to the least complex. Classes like `Product` relate
+/// several sizes of the input, e.g. the rows and columns of a grid.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, Type)]
 #[sqlx(rename_all = "SCREAMING_SNAKE_CASE")]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum AsymptoticComplexity {
     Exponential,
     Quadratic,
+
+    /// `O(n·m)`, e.g. in the rows and columns of a grid, or the lengths of two strings
+    Product,
+
+    /// `O(E log V)` in the edges and nodes of a graph
+    EdgesLogVertices,
     LogLinear,
+
+    /// `O(V + E)` in the nodes and edges of a graph
+    VerticesPlusEdges,
     Linear,
     Sqrt,
     Log,
     Constant,
 }
 
-fn transpose<T>(list: Vec<Vec<T>>) -> Vec<Vec<T>> {
-    let len = list[0].len();
-    let mut iters: Vec<_> = list.into_iter().map(|n| n.into_iter()).collect();
-    (0..len)
-        .map(|_| iters.iter_mut().map(|n| n.next().unwrap()).collect())
-        .collect()
+impl AsymptoticComplexity {
+    /// The classes of a single size
+    const SINGLE: [AsymptoticComplexity; 7] = [
+        AsymptoticComplexity::Exponential,
+        AsymptoticComplexity::Quadratic,
+        AsymptoticComplexity::LogLinear,
+        AsymptoticComplexity::Linear,
+        AsymptoticComplexity::Sqrt,
+        AsymptoticComplexity::Log,
+        AsymptoticComplexity::Constant,
+    ];
+
+    /// The natural log of how much work this class does for an input of the given sizes, e.g.
+    /// `ln(n²)` for `Quadratic`. Working with logs keeps exponentials from overflowing.
+    ///
+    /// Graph classes take the number of nodes and then the number of edges, `Product` takes any
+    /// two sizes, and every other class takes one.
+    fn ln_growth(self, sizes: &[f64]) -> f64 {
+        match (self, sizes) {
+            (AsymptoticComplexity::Exponential, &[n]) => n * LN_2,
+            (AsymptoticComplexity::Quadratic, &[n]) => 2.0 * (n + 1.0).ln(),
+            (AsymptoticComplexity::Product, &[n, m]) => (n + 1.0).ln() + (m + 1.0).ln(),
+            (AsymptoticComplexity::EdgesLogVertices, &[v, e]) => {
+                (e + 1.0).ln() + (v + 2.0).log2().ln()
+            }
+            (AsymptoticComplexity::LogLinear, &[n]) => (n + 1.0).ln() + (n + 2.0).log2().ln(),
+            (AsymptoticComplexity::VerticesPlusEdges, &[v, e]) => (v + e + 1.0).ln(),
+            (AsymptoticComplexity::Linear, &[n]) => (n + 1.0).ln(),
+            (AsymptoticComplexity::Sqrt, &[n]) => 0.5 * (n + 1.0).ln(),
+            (AsymptoticComplexity::Log, &[n]) => (n + 2.0).log2().ln(),
+            (AsymptoticComplexity::Constant, &[_]) => 0.0,
+            _ => unreachable!("{self:?} doesn't take {} sizes", sizes.len()),
+        }
+    }
 }
 
-fn normalize(list: &mut Vec<f32>) {
-    let min = *list
-        .iter()
-        .min_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
-    let max = *list
-        .iter()
-        .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
+/// How large a value is along each dimension that the time taken on it might depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SizeMetrics {
+    /// The number of rows of a grid
+    pub rows: f32,
+
+    /// The number of columns of a grid, going by its first row
+    pub cols: f32,
+
+    /// The number of nodes of a graph
+    pub nodes: f32,
+
+    /// The number of edges of a graph, i.e. the number of neighbors listed for all of its nodes
+    pub edges: f32,
 
-    // simple case
-    if min == max {
-        for item in list.iter_mut() {
-            *item = 1.0;
+    /// The overall size, e.g. the length of a list, the number of elements in a grid, or the
+    /// magnitude of a number
+    pub total: f32,
+}
+
+impl SizeMetrics {
+    /// The larger of each metric, e.g. for a struct made of several values
+    pub fn max(self, other: SizeMetrics) -> SizeMetrics {
+        SizeMetrics {
+            rows: self.rows.max(other.rows),
+            cols: self.cols.max(other.cols),
+            nodes: self.nodes.max(other.nodes),
+            edges: self.edges.max(other.edges),
+            total: self.total.max(other.total),
         }
-    } else {
-        for item in list.iter_mut() {
-            *item = (*item - min) / (max - min);
+    }
+}
+
+/// The size of a value with only one dimension, e.g. a list or a number
+impl From<f32> for SizeMetrics {
+    fn from(total: f32) -> Self {
+        SizeMetrics {
+            total,
+            ..Default::default()
         }
     }
 }
 
-fn calculate_mean_difference(a: &Vec<f32>, b: &Vec<f32>) -> f32 {
-    a.iter()
-        .zip(b.iter())
-        .map(|(ai, bi)| (ai - bi).abs())
-        .sum::<f32>()
-        / a.len() as f32
+/// The complexity class which best explains how the fuel consumed by a submission grows with the
+/// size of its input.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct ComplexityEstimate {
+    pub complexity: AsymptoticComplexity,
+
+    /// The coefficient of determination of the fit, where 1 means the class explains the fuel
+    /// of every test exactly
+    pub r_squared: f64,
+
+    /// The constant factor of the fit, i.e. `fuel ≈ constant * f(n)`
+    pub constant: f64,
 }
 
-fn normalize_with_sample_function<F>(inputs: &Vec<Vec<(f32, usize)>>, function: F) -> Vec<Vec<f32>>
-where
-    F: Fn(f32, usize) -> f32,
-{
-    inputs
-        .clone()
-        .into_iter()
-        .map(|argument| {
-            let mut argument = argument
-                .into_iter()
-                .map(|(factor, i)| factor / function(factor, i))
-                .collect();
-
-            normalize(&mut argument);
-            argument
-        })
-        .collect()
+/// Estimates below this R² are too unsure to be shown.
+const MIN_R_SQUARED: f64 = 0.9;
+
+/// How many different input sizes there must be to fit a curve through the fuel.
+const MIN_DISTINCT_SIZES: usize = 3;
+
+/// Fits within this much R² of the best one are considered just as good. Of those, fits relating
+/// more sizes are preferred since they describe the input more precisely, e.g. `O(V + E)` over
+/// `O(n)` where `n` is the size of the graph, and then simpler classes.
+const R_SQUARED_TOLERANCE: f64 = 0.005;
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
 }
 
+fn distinct_count(values: &[f64]) -> usize {
+    let mut values = values.to_vec();
+    values.sort_by(f64::total_cmp);
+    values.dedup();
+    values.len()
+}
+
+/// A complexity class in terms of particular sizes of the inputs, e.g. `Linear` in the length
+/// of the first argument.
+struct Model {
+    complexity: AsymptoticComplexity,
+
+    /// The values of each size for each test
+    sizes: Vec<Vec<f64>>,
+}
+
+impl Model {
+    /// Whether each size varies enough between tests to fit the model.
+    fn fittable(&self) -> bool {
+        self.sizes
+            .iter()
+            .all(|size| distinct_count(size) >= MIN_DISTINCT_SIZES)
+    }
+
+    /// Fits `ln(fuel) = ln(constant) + ln(f(n))` by least squares, where `f` is the growth of
+    /// the model's class. The fit is done on logs so that the largest tests don't drown out the
+    /// rest.
+    fn fit(&self, ln_fuel: &[f64]) -> ComplexityEstimate {
+        let residuals = ln_fuel
+            .iter()
+            .enumerate()
+            .map(|(test, fuel)| {
+                let sizes = self.sizes.iter().map(|size| size[test]).collect::<Vec<_>>();
+                fuel - self.complexity.ln_growth(&sizes)
+            })
+            .collect::<Vec<_>>();
+
+        let ln_constant = mean(&residuals);
+        let residual_sum: f64 = residuals.iter().map(|r| (r - ln_constant).powi(2)).sum();
+
+        let mean_fuel = mean(ln_fuel);
+        let total_sum: f64 = ln_fuel.iter().map(|fuel| (fuel - mean_fuel).powi(2)).sum();
+
+        // if every test took the same fuel, only a constant explains it
+        let r_squared = if total_sum <= f64::EPSILON {
+            if residual_sum <= f64::EPSILON {
+                1.0
+            } else {
+                0.0
+            }
+        } else {
+            1.0 - residual_sum / total_sum
+        };
+
+        ComplexityEstimate {
+            complexity: self.complexity,
+            r_squared,
+            constant: ln_constant.exp(),
+        }
+    }
+}
+
+/// The models to try for inputs whose arguments have the given sizes in each test. Single classes
+/// are fit against the total size of each argument and of all of them together. Products are fit
+/// against each pair of arguments and the rows and columns of grids, and graph classes against
+/// the nodes and edges of graphs.
+fn models(sizes: &[Vec<SizeMetrics>]) -> Vec<Model> {
+    let argument_count = sizes.iter().map(Vec::len).min().unwrap_or(0);
+    let metric = |argument: usize, metric: fn(&SizeMetrics) -> f32| {
+        sizes
+            .iter()
+            .map(|test| metric(&test[argument]) as f64)
+            .collect::<Vec<_>>()
+    };
+
+    let mut totals = (0..argument_count)
+        .map(|argument| metric(argument, |size| size.total))
+        .collect::<Vec<_>>();
+
+    let mut models = vec![];
+
+    for (i, first) in totals.iter().enumerate() {
+        for second in &totals[i + 1..] {
+            models.push(Model {
+                complexity: AsymptoticComplexity::Product,
+                sizes: vec![first.clone(), second.clone()],
+            });
+        }
+    }
+
+    for argument in 0..argument_count {
+        models.push(Model {
+            complexity: AsymptoticComplexity::Product,
+            sizes: vec![
+                metric(argument, |size| size.rows),
+                metric(argument, |size| size.cols),
+            ],
+        });
+
+        for complexity in [
+            AsymptoticComplexity::EdgesLogVertices,
+            AsymptoticComplexity::VerticesPlusEdges,
+        ] {
+            models.push(Model {
+                complexity,
+                sizes: vec![
+                    metric(argument, |size| size.nodes),
+                    metric(argument, |size| size.edges),
+                ],
+            });
+        }
+    }
+
+    if argument_count > 1 {
+        totals.push(
+            sizes
+                .iter()
+                .map(|test| test.iter().map(|size| size.total as f64).sum())
+                .collect(),
+        );
+    }
+
+    for total in totals {
+        for complexity in AsymptoticComplexity::SINGLE {
+            models.push(Model {
+                complexity,
+                sizes: vec![total.clone()],
+            });
+        }
+    }
+
+    models
+}
+
+/// Estimates the complexity of a submission from the fuel it consumed on each test.
+///
+/// Every class is fit against the sizes of the inputs it could depend on, and the best fit is
+/// returned. Returns `None` if the sizes of the inputs don't vary enough, or if no class fits
+/// well.
 pub fn estimate_asymptotic_complexity(
     inputs: Vec<WasmFunctionCall>,
     times: Vec<f32>,
-) -> Option<AsymptoticComplexity> {
-    let inputs = transpose(
-        inputs
-            .into_iter()
-            .enumerate()
-            .map(|(i, input)| {
-                let mut argument = input
-                    .arguments
-                    .into_iter()
-                    .map(|argument| (argument.scaling_factor(), i))
-                    .collect::<Vec<_>>();
+) -> Option<ComplexityEstimate> {
+    let ln_fuel = times
+        .iter()
+        .map(|&fuel| (fuel as f64).max(1.0).ln())
+        .collect::<Vec<_>>();
 
-                // sort by input difficulty factor
-                argument.sort_unstable_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+    let sizes = inputs
+        .iter()
+        .map(|input| {
+            input
+                .arguments
+                .iter()
+                .map(FunctionValue::scaling_factor)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
 
-                argument
-            })
-            .collect::<Vec<_>>(),
-    );
-
-    // println!("times: {:?}", times);
-    // println!("base data: {:?}\n", inputs);
-
-    let factors = normalize_with_sample_function(&inputs, |_, i| times[i]);
-    let exponentials = normalize_with_sample_function(&inputs, |i, _| (i as f32).exp());
-    let quadratics = normalize_with_sample_function(&inputs, |i, _| (i as f32 + 1.0).powi(2));
-    let log_linears =
-        normalize_with_sample_function(&inputs, |i, _| (i as f32 + 1.0) * (i as f32 + 2.0).log2());
-    let linears = normalize_with_sample_function(&inputs, |i, _| i as f32 + 1.0);
-    let sqrts = normalize_with_sample_function(&inputs, |i, _| (i as f32 + 1.0).sqrt());
-    let logs = normalize_with_sample_function(&inputs, |i, _| (i as f32 + 2.0).log2());
-    let constants = normalize_with_sample_function(&inputs, |_, _| 1.0);
-
-    // println!("actual: {:?}\n", factors);
-    // println!("exponentials: {:?}", exponentials);
-    // println!("quadratics: {:?}", quadratics);
-    // println!("log_linears: {:?}", log_linears);
-    // println!("linears: {:?}", linears);
-    // println!("sqrts: {:?}", sqrts);
-    // println!("constants: {:?}", constants);
-
-    let mut detected_complexities = vec![];
-
-    for (i, factor) in factors.iter().enumerate() {
-        let mut mean_differences = vec![];
-
-        mean_differences.push((
-            AsymptoticComplexity::Exponential,
-            calculate_mean_difference(&exponentials[i], &factor),
-        ));
-        mean_differences.push((
-            AsymptoticComplexity::Quadratic,
-            calculate_mean_difference(&quadratics[i], &factor),
-        ));
-        mean_differences.push((
-            AsymptoticComplexity::LogLinear,
-            calculate_mean_difference(&log_linears[i], &factor),
-        ));
-        mean_differences.push((
-            AsymptoticComplexity::Linear,
-            calculate_mean_difference(&linears[i], &factor),
-        ));
-        mean_differences.push((
-            AsymptoticComplexity::Sqrt,
-            calculate_mean_difference(&sqrts[i], &factor),
-        ));
-        mean_differences.push((
-            AsymptoticComplexity::Log,
-            calculate_mean_difference(&logs[i], &factor),
-        ));
-        mean_differences.push((
-            AsymptoticComplexity::Constant,
-            calculate_mean_difference(&constants[i], &factor),
-        ));
-
-        // find min index
-        let idx = mean_differences
+    let estimates = models(&sizes)
+        .into_iter()
+        .filter(Model::fittable)
+        .map(|model| (model.sizes.len(), model.fit(&ln_fuel)))
+        .collect::<Vec<_>>();
+
+    let best = estimates
+        .iter()
+        .map(|(_, estimate)| estimate.r_squared)
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    estimates
+        .into_iter()
+        .filter(|(_, estimate)| {
+            estimate.r_squared >= MIN_R_SQUARED && estimate.r_squared >= best - R_SQUARED_TOLERANCE
+        })
+        .max_by_key(|(size_count, estimate)| (*size_count, estimate.complexity))
+        .map(|(_, estimate)| estimate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContainerVariant, ContainerVariantType, FunctionType, FunctionValue};
+
+    /// Calls with a list of `n` elements and a number which doesn't affect the fuel
+    fn inputs(sizes: &[usize]) -> Vec<WasmFunctionCall> {
+        sizes
             .iter()
-            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-            .unwrap();
+            .map(|&n| {
+                WasmFunctionCall::new(
+                    "solve",
+                    vec![
+                        FunctionValue::Int(ContainerVariant::list(vec![0; n])),
+                        FunctionValue::Int(ContainerVariant::Single(7)),
+                    ],
+                    FunctionType::Int(ContainerVariantType::Single),
+                )
+            })
+            .collect()
+    }
 
-        detected_complexities.push(*idx);
+    fn estimate(sizes: &[usize], fuel: impl Fn(f32) -> f32) -> Option<ComplexityEstimate> {
+        let times = sizes.iter().map(|&n| fuel(n as f32)).collect();
+        estimate_asymptotic_complexity(inputs(sizes), times)
     }
 
-    // We only give a complexity estimate if we're pretty confident.
-    // This saves us from giving some incorrect answers.
-    for (complexity, mean_diff) in detected_complexities {
-        if mean_diff < 0.1 {
-            return Some(complexity);
-        }
+    #[test]
+    fn fits_growth() {
+        let sizes = [1, 5, 10, 50, 100, 500, 1000, 5000];
+
+        let linear = estimate(&sizes, |n| 30.0 * n + 200.0).unwrap();
+        assert_eq!(linear.complexity, AsymptoticComplexity::Linear);
+        assert!(linear.r_squared > 0.95);
+
+        let log_linear = estimate(&sizes, |n| 12.0 * (n + 1.0) * (n + 2.0).log2()).unwrap();
+        assert_eq!(log_linear.complexity, AsymptoticComplexity::LogLinear);
+        assert!((log_linear.constant - 12.0).abs() < 0.01);
+
+        let quadratic = estimate(&sizes, |n| 4.0 * n * n + 100.0).unwrap();
+        assert_eq!(quadratic.complexity, AsymptoticComplexity::Quadratic);
+
+        let exponential = estimate(&[1, 3, 5, 10, 15, 20], |n| 2f32.powf(n) * 9.0).unwrap();
+        assert_eq!(exponential.complexity, AsymptoticComplexity::Exponential);
+
+        let constant = estimate(&sizes, |_| 1234.0).unwrap();
+        assert_eq!(constant.complexity, AsymptoticComplexity::Constant);
+        assert_eq!(constant.r_squared, 1.0);
+
+        // a handful of similar inputs can't tell classes apart
+        assert!(estimate(&[10, 10, 11, 11], |n| n * n).is_none());
     }
 
-    None
+    /// Estimates the complexity of calls taking a single container of the given dimensions
+    fn estimate_2d(
+        dimensions: &[(usize, usize)],
+        container: impl Fn(Vec<Vec<i32>>) -> ContainerVariant<i32>,
+        fuel: impl Fn(f32, f32) -> f32,
+    ) -> Option<ComplexityEstimate> {
+        let inputs = dimensions
+            .iter()
+            .map(|&(rows, cols)| {
+                WasmFunctionCall::new(
+                    "solve",
+                    vec![FunctionValue::Int(container(vec![vec![0; cols]; rows]))],
+                    FunctionType::Int(ContainerVariantType::Single),
+                )
+            })
+            .collect();
+
+        let times = dimensions
+            .iter()
+            .map(|&(rows, cols)| fuel(rows as f32, cols as f32))
+            .collect();
+
+        estimate_asymptotic_complexity(inputs, times)
+    }
+
+    #[test]
+    fn fits_several_sizes() {
+        let dimensions = [
+            (2, 3),
+            (10, 5),
+            (5, 40),
+            (50, 20),
+            (100, 100),
+            (20, 300),
+            (300, 10),
+        ];
+
+        let grid = estimate_2d(&dimensions, ContainerVariant::grid, |n, m| {
+            7.0 * n * m + 50.0
+        });
+        assert_eq!(grid.unwrap().complexity, AsymptoticComplexity::Product);
+
+        // the nodes and edges of a graph whose nodes each have the same number of neighbors
+        let graph = |nodes: f32, neighbors: f32| (nodes, nodes * neighbors);
+
+        let linear = estimate_2d(&dimensions, ContainerVariant::Graph, |n, m| {
+            let (v, e) = graph(n, m);
+            20.0 * (v + e)
+        });
+        assert_eq!(
+            linear.unwrap().complexity,
+            AsymptoticComplexity::VerticesPlusEdges
+        );
+
+        let dijkstra = estimate_2d(&dimensions, ContainerVariant::Graph, |n, m| {
+            let (v, e) = graph(n, m);
+            5.0 * (e + 1.0) * (v + 2.0).log2()
+        });
+        assert_eq!(
+            dijkstra.unwrap().complexity,
+            AsymptoticComplexity::EdgesLogVertices
+        );
+
+        // two strings, as in edit distance
+        let inputs = dimensions
+            .iter()
+            .map(|&(n, m)| {
+                WasmFunctionCall::new(
+                    "solve",
+                    vec![
+                        FunctionValue::String(ContainerVariant::Single("a".repeat(n))),
+                        FunctionValue::String(ContainerVariant::Single("b".repeat(m))),
+                    ],
+                    FunctionType::Int(ContainerVariantType::Single),
+                )
+            })
+            .collect();
+        let times = dimensions
+            .iter()
+            .map(|&(n, m)| 3.0 * ((n + 1) * (m + 1)) as f32)
+            .collect();
+        let edit_distance = estimate_asymptotic_complexity(inputs, times).unwrap();
+        assert_eq!(edit_distance.complexity, AsymptoticComplexity::Product);
+        assert!((edit_distance.constant - 3.0).abs() < 0.01);
+    }
 }
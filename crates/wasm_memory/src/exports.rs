@@ -0,0 +1,251 @@
+use anyhow::Result;
+use cpp_demangle::{DemangleOptions, Symbol};
+use wasmtime::*;
+
+use crate::{demangled_signature, types_match, FunctionType};
+
+#[derive(thiserror::Error, Debug)]
+enum FunctionError {
+    #[error("Expected a function with name \"{0}\", but it was not found.")]
+    NameNotFound(String),
+
+    #[error("Expected a function with name \"{name}\" taking the arguments of the test, but only found {}.", .candidates.join(", "))]
+    WrongArguments {
+        name: String,
+        candidates: Vec<String>,
+    },
+
+    #[error("Expected one function with name \"{name}\" taking the arguments of the test, but found {}.", .candidates.join(", "))]
+    Ambiguous {
+        name: String,
+        candidates: Vec<String>,
+    },
+}
+
+/// An exported function with the name being called.
+struct Candidate {
+    func: Func,
+
+    /// The demangled name of the function, e.g. `solve(int, int)`
+    signature: String,
+
+    /// The types of the function's parameters, or `None` for functions which aren't mangled, e.g.
+    /// those written in C. Parameters of types which tests can't pass are `None` as well.
+    parameters: Option<Vec<Option<FunctionType>>>,
+}
+
+impl Candidate {
+    fn takes(&self, arguments: &[FunctionType]) -> bool {
+        let Some(parameters) = &self.parameters else {
+            return true;
+        };
+
+        parameters.len() == arguments.len()
+            && parameters
+                .iter()
+                .zip(arguments)
+                .all(|(parameter, argument)| match parameter {
+                    Some(parameter) => types_match(parameter, argument),
+                    None => true,
+                })
+    }
+}
+
+fn demangle(symbol: &str, options: &DemangleOptions) -> Option<String> {
+    if !symbol.starts_with("_Z") {
+        return None;
+    }
+
+    Symbol::new(symbol).ok()?.demangle(options).ok()
+}
+
+/// Returns the name of a function without its signature, e.g. `query` for `_Z5queryi` and
+/// `ns::query` for `_ZN2ns5queryEi`. Names which aren't mangled are returned unchanged.
+pub fn function_name(symbol: &str) -> String {
+    demangle(symbol, &DemangleOptions::new().no_params().no_return_type())
+        .unwrap_or_else(|| symbol.to_string())
+}
+
+/// Finds the exported function called `name` which takes `arguments` and has the wasm `params`
+/// and `results`.
+///
+/// C++ functions are exported under their mangled names, which are demangled so that helpers like
+/// `solve_helper` and overloads taking other types aren't mistaken for `solve`. Functions written
+/// in C or Rust are exported under their own names.
+pub(crate) fn find_function<S>(
+    store: &mut Store<S>,
+    instance: &Instance,
+    name: &str,
+    arguments: &[FunctionType],
+    params: &[ValType],
+    results: &[ValType],
+) -> Result<Func> {
+    let exports = instance
+        .exports(&mut *store)
+        .filter_map(|export| {
+            let export_name = export.name().to_string();
+            export.into_func().map(|func| (export_name, func))
+        })
+        .collect::<Vec<_>>();
+
+    let mut candidates = vec![];
+    for (export_name, func) in exports {
+        if export_name == name {
+            candidates.push(Candidate {
+                func,
+                signature: export_name,
+                parameters: None,
+            });
+            continue;
+        }
+
+        let Some(demangled) = demangle(&export_name, &DemangleOptions::new().no_return_type())
+        else {
+            continue;
+        };
+        let Some((function, parameters)) = demangled_signature(&demangled) else {
+            continue;
+        };
+
+        if function == name {
+            candidates.push(Candidate {
+                func,
+                parameters: Some(parameters),
+                signature: demangled,
+            });
+        }
+    }
+
+    if candidates.is_empty() {
+        return Err(FunctionError::NameNotFound(name.to_string()).into());
+    }
+
+    let matching = candidates
+        .iter()
+        .filter(|candidate| {
+            let ty = candidate.func.ty(&*store);
+
+            ty.params().eq(params.iter().cloned())
+                && ty.results().eq(results.iter().cloned())
+                && candidate.takes(arguments)
+        })
+        .collect::<Vec<_>>();
+
+    let signatures = |candidates: &[&Candidate]| {
+        candidates
+            .iter()
+            .map(|candidate| format!("`{}`", candidate.signature))
+            .collect()
+    };
+
+    match matching.as_slice() {
+        [candidate] => Ok(candidate.func),
+        [] => Err(FunctionError::WrongArguments {
+            name: name.to_string(),
+            candidates: signatures(&candidates.iter().collect::<Vec<_>>()),
+        }
+        .into()),
+        _ => Err(FunctionError::Ambiguous {
+            name: name.to_string(),
+            candidates: signatures(&matching),
+        }
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContainerVariantType;
+
+    const VECTOR: &str = "_Z5solveRNSt3__26vectorIiNS_9allocatorIiEEEEi";
+    const STRING: &str =
+        "_Z5solveRKNSt3__212basic_stringIcNS_11char_traitsIcEENS_9allocatorIcEEEEi";
+    const HELPER: &str = "_Z12solve_helperRNSt3__26vectorIiNS_9allocatorIiEEEEi";
+
+    fn test_setup(exports: &[&str]) -> Result<(Store<()>, Instance)> {
+        let functions = exports
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                format!("(func (export \"{name}\") (param i32 i32) (result i32) i32.const {i})")
+            })
+            .collect::<String>();
+
+        let engine = Engine::default();
+        let mut store = Store::new(&engine, ());
+        let module = Module::new(&engine, format!("(module {functions})"))?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+
+        Ok((store, instance))
+    }
+
+    /// Which of the exports `find_function` picked, going by the index it returns
+    fn find<S>(
+        store: &mut Store<S>,
+        instance: &Instance,
+        arguments: &[FunctionType],
+    ) -> Result<i32> {
+        let func = find_function(
+            store,
+            instance,
+            "solve",
+            arguments,
+            &[ValType::I32, ValType::I32],
+            &[ValType::I32],
+        )?;
+
+        let mut results = [Val::I32(-1)];
+        func.call(&mut *store, &[Val::I32(0), Val::I32(0)], &mut results)?;
+        Ok(results[0].unwrap_i32())
+    }
+
+    #[test]
+    fn overloads_and_helpers() -> Result<()> {
+        let (mut store, instance) = test_setup(&[HELPER, STRING, VECTOR])?;
+
+        let single = ContainerVariantType::Single;
+        let vector = [
+            FunctionType::Int(ContainerVariantType::list()),
+            FunctionType::Int(single.clone()),
+        ];
+        let string = [
+            FunctionType::String(single.clone()),
+            FunctionType::Int(single.clone()),
+        ];
+
+        assert_eq!(find(&mut store, &instance, &vector)?, 2);
+        assert_eq!(find(&mut store, &instance, &string)?, 1);
+
+        let ints = vec![FunctionType::Int(single); 2];
+        let error = find(&mut store, &instance, &ints).unwrap_err().to_string();
+        assert!(error.contains("`solve(std::__2::vector<int, std::__2::allocator<int> >&, int)`"));
+        assert!(!error.contains("solve_helper"));
+
+        // functions which aren't mangled can't be told apart by their parameters
+        let (mut store, instance) = test_setup(&[VECTOR, "solve"])?;
+        let error = find(&mut store, &instance, &vector)
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("Expected one function"));
+
+        let (mut store, instance) = test_setup(&[HELPER])?;
+        let error = find(&mut store, &instance, &vector)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("it was not found"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn function_names() {
+        assert_eq!(function_name("_Z5queryi"), "query");
+        assert_eq!(function_name(VECTOR), "solve");
+        assert_eq!(function_name(HELPER), "solve_helper");
+        assert_eq!(function_name("_ZN2ns5queryEi"), "ns::query");
+        assert_eq!(function_name("_Z5queryPKcS0_"), "query");
+        assert_eq!(function_name("query"), "query");
+        assert_eq!(function_name("_Z"), "_Z");
+    }
+}
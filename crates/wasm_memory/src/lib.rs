@@ -1,7 +1,24 @@
+mod c_memory;
+mod collections;
+mod containers;
 mod estimate_complexity;
+mod exports;
+mod nodes;
+mod signature;
+mod structs;
+mod text_format;
 mod wasm_function;
 mod wasm_memory;
 
+pub use c_memory::*;
+pub use collections::*;
+pub use containers::*;
 pub use estimate_complexity::*;
+pub use exports::function_name;
+pub(crate) use exports::*;
+pub use nodes::*;
+pub use signature::*;
+pub use structs::*;
+pub use text_format::*;
 pub use wasm_function::*;
 pub use wasm_memory::*;
@@ -0,0 +1,294 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashSet, VecDeque};
+use wasmtime::*;
+
+use crate::{read_pointer, write_words, AllocatorFunc, WasmMemory};
+
+/// A singly linked list, e.g. `[1, 2, 3]`, which maps to a pointer to its head node, or `nullptr`
+/// if it's empty.
+///
+/// ```cpp
+/// struct ListNode {
+///     int val;
+///     ListNode *next;
+/// };
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct LinkedList(pub Vec<i32>);
+
+/// A binary tree written in level order, where missing children are `null`, e.g. `[1, null, 2]`
+/// like on LeetCode. It maps to a pointer to its root node, or `nullptr` if it's empty.
+///
+/// ```cpp
+/// struct TreeNode {
+///     int val;
+///     TreeNode *left;
+///     TreeNode *right;
+/// };
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(transparent)]
+pub struct BinaryTree(pub Vec<Option<i32>>);
+
+impl LinkedList {
+    const NODE_SIZE: usize = 8;
+
+    /// Allocates a node for each element, returning the address of the head.
+    pub(crate) fn write_nodes<S>(
+        self,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+    ) -> Result<usize> {
+        let mut next = 0;
+
+        for val in self.0.into_iter().rev() {
+            let node = allocator.call(&mut *store, Self::NODE_SIZE as i32)? as usize;
+            write_words(store, memory, node, &[val as u32, next as u32])?;
+            next = node;
+        }
+
+        Ok(next)
+    }
+
+    /// Follows the list starting at `head`, failing if it loops back on itself.
+    pub(crate) fn read_nodes<S>(
+        store: &mut Store<S>,
+        memory: &Memory,
+        head: usize,
+    ) -> Result<Self> {
+        let mut values = vec![];
+        let mut visited = HashSet::new();
+        let mut node = head;
+
+        while node != 0 {
+            if !visited.insert(node) {
+                bail!("The linked list has a cycle");
+            }
+
+            values.push(i32::from_memory(store, memory, node)?);
+            node = read_pointer(store, memory, node + 4)?;
+        }
+
+        Ok(LinkedList(values))
+    }
+}
+
+impl BinaryTree {
+    const NODE_SIZE: usize = 12;
+
+    /// The number of nodes in the tree
+    pub fn len(&self) -> usize {
+        self.0.iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The level order without trailing `null`s, which don't change the tree.
+    fn trimmed(&self) -> &[Option<i32>] {
+        let len = self
+            .0
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |i| i + 1);
+        &self.0[..len]
+    }
+
+    /// Allocates a node for each value, returning the address of the root.
+    pub(crate) fn write_nodes<S>(
+        self,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+    ) -> Result<usize> {
+        let allocate = |store: &mut Store<S>, val: i32| -> Result<usize> {
+            let node = allocator.call(&mut *store, Self::NODE_SIZE as i32)? as usize;
+            write_words(store, memory, node, &[val as u32, 0, 0])?;
+            Ok(node)
+        };
+
+        let mut values = self.0.into_iter();
+        let Some(Some(root)) = values.next() else {
+            return Ok(0);
+        };
+
+        let root = allocate(store, root)?;
+        let mut queue = VecDeque::from([root]);
+
+        // each node takes the next two values as its left and right children
+        while let Some(node) = queue.pop_front() {
+            for child in [node + 4, node + 8] {
+                match values.next() {
+                    Some(Some(val)) => {
+                        let child_node = allocate(store, val)?;
+                        write_words(store, memory, child, &[child_node as u32])?;
+                        queue.push_back(child_node);
+                    }
+                    Some(None) => {}
+                    None => return Ok(root),
+                }
+            }
+        }
+
+        if values.any(|val| val.is_some()) {
+            bail!("The binary tree has values after its last node's children");
+        }
+
+        Ok(root)
+    }
+
+    /// Walks the tree starting at `root` in level order, failing if a node is reached twice,
+    /// which happens if the tree has a cycle or a node with more than one parent.
+    pub(crate) fn read_nodes<S>(
+        store: &mut Store<S>,
+        memory: &Memory,
+        root: usize,
+    ) -> Result<Self> {
+        let mut values = vec![];
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::from([root]);
+
+        while let Some(node) = queue.pop_front() {
+            if node == 0 {
+                values.push(None);
+                continue;
+            }
+
+            if !visited.insert(node) {
+                bail!("The binary tree has a cycle or a node with more than one parent");
+            }
+
+            values.push(Some(i32::from_memory(store, memory, node)?));
+            queue.push_back(read_pointer(store, memory, node + 4)?);
+            queue.push_back(read_pointer(store, memory, node + 8)?);
+        }
+
+        let mut tree = BinaryTree(values);
+        tree.0.truncate(tree.trimmed().len());
+
+        Ok(tree)
+    }
+}
+
+/// Trees are equal if they have the same nodes, regardless of trailing `null`s.
+impl PartialEq for BinaryTree {
+    fn eq(&self, other: &Self) -> bool {
+        self.trimmed() == other.trimmed()
+    }
+}
+
+impl Eq for BinaryTree {}
+
+impl PartialOrd for BinaryTree {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BinaryTree {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.trimmed().cmp(other.trimmed())
+    }
+}
+
+/// maps to `ListNode*`
+impl WasmMemory for LinkedList {
+    const CPP_SIZE_OF: usize = 4;
+    const CPP_ALIGN_OF: usize = 4;
+
+    fn into_memory<S>(
+        self,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+        offset: Option<usize>,
+    ) -> Result<usize> {
+        let head = self.write_nodes(store, memory, allocator)?;
+        (head as i32).into_memory(store, memory, allocator, offset)
+    }
+
+    fn from_memory<S>(store: &mut Store<S>, memory: &Memory, offset: usize) -> Result<Self> {
+        let head = read_pointer(store, memory, offset)?;
+        LinkedList::read_nodes(store, memory, head)
+    }
+}
+
+/// maps to `TreeNode*`
+impl WasmMemory for BinaryTree {
+    const CPP_SIZE_OF: usize = 4;
+    const CPP_ALIGN_OF: usize = 4;
+
+    fn into_memory<S>(
+        self,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+        offset: Option<usize>,
+    ) -> Result<usize> {
+        let root = self.write_nodes(store, memory, allocator)?;
+        (root as i32).into_memory(store, memory, allocator, offset)
+    }
+
+    fn from_memory<S>(store: &mut Store<S>, memory: &Memory, offset: usize) -> Result<Self> {
+        let root = read_pointer(store, memory, offset)?;
+        BinaryTree::read_nodes(store, memory, root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm_memory::test_setup;
+
+    #[test]
+    fn linked_list() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        let list = LinkedList(vec![1, 2, 3]);
+        let address = list
+            .clone()
+            .into_memory(&mut store, &memory, &allocator, None)?;
+        assert_eq!(LinkedList::from_memory(&mut store, &memory, address)?, list);
+
+        let empty = LinkedList(vec![]).write_nodes(&mut store, &memory, &allocator)?;
+        assert_eq!(empty, 0);
+
+        // point the last node back at the head
+        let head = list.write_nodes(&mut store, &memory, &allocator)?;
+        let second = read_pointer(&mut store, &memory, head + 4)?;
+        let third = read_pointer(&mut store, &memory, second + 4)?;
+        write_words(&mut store, &memory, third + 4, &[head as u32])?;
+        assert!(LinkedList::read_nodes(&mut store, &memory, head).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn binary_tree() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        let tree = BinaryTree(vec![Some(1), None, Some(2), Some(3), None, None]);
+        let root = tree.clone().write_nodes(&mut store, &memory, &allocator)?;
+        let read = BinaryTree::read_nodes(&mut store, &memory, root)?;
+        assert_eq!(read.0, vec![Some(1), None, Some(2), Some(3)]);
+        assert_eq!(read, tree);
+        assert_eq!(read.len(), 3);
+
+        // give the root's right child a second parent
+        let right = read_pointer(&mut store, &memory, root + 8)?;
+        write_words(&mut store, &memory, root + 4, &[right as u32])?;
+        assert!(BinaryTree::read_nodes(&mut store, &memory, root).is_err());
+
+        let invalid = BinaryTree(vec![Some(1), None, None, Some(2)]);
+        assert!(invalid
+            .write_nodes(&mut store, &memory, &allocator)
+            .is_err());
+
+        Ok(())
+    }
+}
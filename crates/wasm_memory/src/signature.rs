@@ -0,0 +1,873 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    ContainerVariantType, FunctionType, FunctionValue, MapType, SetType, StructKind, StructType,
+    WasmFunctionCall,
+};
+
+/// The parameters and return type of a function, read from its C++ definition, e.g.
+/// `vector<int> twoSum(vector<int>& nums, int target) { ... }`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FunctionSignature {
+    pub name: String,
+    pub arguments: Vec<FunctionType>,
+    pub argument_names: Vec<String>,
+    pub return_type: FunctionType,
+
+    /// The declaration as it was written, e.g. `int add(int a, int b)`
+    pub declaration: String,
+
+    /// The definitions of the structs used by the function, in the order they were defined
+    pub structs: Vec<String>,
+}
+
+impl FunctionSignature {
+    /// Reads the signature of the function called `name` which is defined in `source`.
+    pub fn from_cpp(source: &str, name: &str) -> Result<Self> {
+        let source = strip_comments(source);
+        let definitions = struct_definitions(&source);
+
+        let definition = find_definition(&source, name)
+            .ok_or_else(|| anyhow!("Couldn't find a definition of `{name}`"))?;
+
+        let mut parser = TypeParser {
+            definitions: &definitions,
+            parsing: vec![],
+            used: vec![],
+        };
+
+        let return_type = return_type_of(&source[definition.start..definition.name]);
+        if return_type == "void" {
+            bail!("`{name}` returns `void`, but functions must return their answer");
+        }
+        let return_type = parser.parse(return_type)?;
+
+        let mut arguments = vec![];
+        let mut argument_names = vec![];
+
+        let parameters = &source[definition.open_paren + 1..definition.close_paren];
+        for parameter in split_top_level(parameters, ',') {
+            // default arguments don't change the type
+            let parameter = split_top_level(parameter, '=')[0].trim();
+            if parameter.is_empty() || parameter == "void" {
+                continue;
+            }
+
+            let (ty, name) = split_declarator(parameter);
+            arguments.push(parser.parse(ty)?);
+            argument_names.push(name.to_string());
+        }
+
+        let mut used = parser.used;
+        used.sort_by_key(|name| definitions[name].position);
+        used.dedup();
+
+        Ok(FunctionSignature {
+            name: name.to_string(),
+            arguments,
+            argument_names,
+            return_type,
+            declaration: source[definition.start..=definition.close_paren].to_string(),
+            structs: used
+                .iter()
+                .map(|name| definitions[name].text.to_string())
+                .collect(),
+        })
+    }
+
+    /// A stub of the function for submissions to start from, along with the structs it uses.
+    pub fn template(&self) -> String {
+        let mut template = String::new();
+
+        for definition in &self.structs {
+            template.push_str(definition);
+            template.push_str("\n\n");
+        }
+
+        template.push_str(&self.declaration);
+        template.push_str(" {\n    \n}\n");
+        template
+    }
+
+    /// Checks that a test calls this function with arguments of the right types.
+    pub fn check(&self, call: &WasmFunctionCall) -> Result<()> {
+        if call.name != self.name {
+            bail!(
+                "The test calls `{}`, but expected `{}`",
+                call.name,
+                self.name
+            );
+        }
+
+        if call.arguments.len() != self.arguments.len() {
+            bail!(
+                "`{}` takes {} arguments, but the test has {}",
+                self.name,
+                self.arguments.len(),
+                call.arguments.len()
+            );
+        }
+
+        for ((argument, expected), name) in call
+            .arguments
+            .iter()
+            .zip(&self.arguments)
+            .zip(&self.argument_names)
+        {
+            let ty = argument.function_type();
+            if !types_match(expected, &ty) {
+                bail!("`{name}` should be a {expected:?}, but the test has a {ty:?}");
+            }
+        }
+
+        if !types_match(&self.return_type, &call.return_type) {
+            bail!(
+                "`{}` returns a {:?}, but the test expects a {:?}",
+                self.name,
+                self.return_type,
+                call.return_type
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a test's expected output is a value this function can return.
+    pub fn check_output(&self, output: &FunctionValue) -> Result<()> {
+        let ty = output.function_type();
+        if !types_match(&self.return_type, &ty) {
+            bail!(
+                "`{}` returns a {:?}, but the expected output is a {ty:?}",
+                self.name,
+                self.return_type
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether a value of type `actual` can be passed as `expected`. Graphs are laid out the same way
+/// as grids, so either can be given for a `vector<vector<T>>`.
+pub(crate) fn types_match(expected: &FunctionType, actual: &FunctionType) -> bool {
+    let mut actual = actual.clone();
+    if *actual.container() == ContainerVariantType::Graph {
+        *actual.container_mut() = ContainerVariantType::grid();
+    }
+
+    *expected == actual
+}
+
+/// Reads the name and parameter types of a function from its demangled name, e.g.
+/// `solve(std::__2::vector<int, std::__2::allocator<int> >&, int)`. The types of parameters which
+/// tests can't pass, e.g. structs, are `None`.
+pub(crate) fn demangled_signature(demangled: &str) -> Option<(&str, Vec<Option<FunctionType>>)> {
+    let open = demangled.find('(')?;
+    let close = matching(demangled, open)?;
+
+    // template functions are demangled with their arguments, e.g. `solve<int>(int)`
+    let name = demangled[..open].split('<').next()?.trim();
+
+    let parameters = demangled[open + 1..close].trim();
+    if parameters.is_empty() || parameters == "void" {
+        return Some((name, vec![]));
+    }
+
+    let parameters = split_top_level(parameters, ',')
+        .into_iter()
+        .map(|parameter| parse_demangled_type(parameter).ok())
+        .collect();
+
+    Some((name, parameters))
+}
+
+fn parse_demangled_type(ty: &str) -> Result<FunctionType> {
+    // libc++ declares everything in an inline namespace, e.g. `std::__2::vector`
+    let mut ty = ty
+        .replace("std::__1::", "std::")
+        .replace("std::__2::", "std::");
+    for default in ["allocator", "char_traits", "less", "hash", "equal_to"] {
+        ty = strip_template_argument(&ty, &format!("std::{default}<"));
+    }
+
+    let definitions = HashMap::new();
+    let mut parser = TypeParser {
+        definitions: &definitions,
+        parsing: vec![],
+        used: vec![],
+    };
+
+    parser.parse(&ty)
+}
+
+/// Removes the template arguments starting with `prefix` which are left as their defaults, e.g.
+/// the allocator of `std::vector<int, std::allocator<int> >`.
+fn strip_template_argument(ty: &str, prefix: &str) -> String {
+    let mut ty = ty.to_string();
+
+    while let Some(start) = ty.find(prefix) {
+        let Some(close) = matching(&ty, start + prefix.len() - 1) else {
+            break;
+        };
+
+        // the argument can't be a default if it's the first one
+        let before = ty[..start].trim_end();
+        if !before.ends_with(',') {
+            break;
+        }
+
+        ty.replace_range(before.len() - 1..=close, "");
+    }
+
+    ty
+}
+
+fn is_identifier(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+/// Replaces comments and preprocessor directives with whitespace.
+fn strip_comments(source: &str) -> String {
+    let bytes = source.as_bytes();
+    let mut stripped = String::with_capacity(source.len());
+    let mut i = 0;
+    let mut line_start = true;
+
+    while i < bytes.len() {
+        let rest = &source[i..];
+
+        if rest.starts_with("//") || (line_start && bytes[i] == b'#') {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            stripped.push(' ');
+            i += end;
+        } else if rest.starts_with("/*") {
+            let end = rest.find("*/").map_or(rest.len(), |end| end + 2);
+            stripped.push(' ');
+            i += end;
+        } else if bytes[i] == b'"' || bytes[i] == b'\'' {
+            // copy literals as they are, so that `"//"` isn't treated as a comment
+            let quote = bytes[i];
+            let mut end = i + 1;
+            while end < bytes.len() && bytes[end] != quote {
+                end += if bytes[end] == b'\\' { 2 } else { 1 };
+            }
+            let end = (end + 1).min(bytes.len());
+            stripped.push_str(&source[i..end]);
+            i = end;
+        } else {
+            let c = rest.chars().next().unwrap();
+            stripped.push(c);
+            i += c.len_utf8();
+        }
+
+        line_start = stripped.ends_with('\n') || (line_start && stripped.ends_with([' ', '\t']));
+    }
+
+    stripped
+}
+
+/// The positions of `word` in `source` where it isn't part of a longer identifier.
+fn word_positions<'a>(source: &'a str, word: &'a str) -> impl Iterator<Item = usize> + 'a {
+    let bytes = source.as_bytes();
+
+    source
+        .match_indices(word)
+        .map(|(i, _)| i)
+        .filter(move |&i| {
+            let end = i + word.len();
+            (i == 0 || !is_identifier(bytes[i - 1]))
+                && (end == bytes.len() || !is_identifier(bytes[end]))
+        })
+}
+
+/// The index of the bracket closing the one at `open`.
+fn matching(source: &str, open: usize) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let (opening, closing) = match bytes[open] {
+        b'(' => (b'(', b')'),
+        b'{' => (b'{', b'}'),
+        b'<' => (b'<', b'>'),
+        _ => return None,
+    };
+
+    let mut depth = 0;
+    for (i, &c) in bytes.iter().enumerate().skip(open) {
+        if c == opening {
+            depth += 1;
+        } else if c == closing {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+
+    None
+}
+
+fn skip_whitespace(source: &str, i: usize) -> usize {
+    i + (source[i..].len() - source[i..].trim_start().len())
+}
+
+/// Splits `s` on each `separator` which isn't nested in brackets.
+fn split_top_level(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' | '<' | '[' | '{' => depth += 1,
+            ')' | '>' | ']' | '}' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Splits a declaration like `const vector<int>& nums` into its type and name. Declarations
+/// without a name, e.g. `int`, are returned with an empty name.
+fn split_declarator(declaration: &str) -> (&str, &str) {
+    let declaration = declaration.trim();
+    let bytes = declaration.as_bytes();
+
+    let mut start = bytes.len();
+    while start > 0 && is_identifier(bytes[start - 1]) {
+        start -= 1;
+    }
+
+    let ty = declaration[..start].trim_end();
+    if ty.is_empty() || ty.ends_with("::") {
+        (declaration, "")
+    } else {
+        (ty, &declaration[start..])
+    }
+}
+
+/// Removes the specifiers which don't change the return type, e.g. `static`, and the class of a
+/// member function defined outside of it, e.g. `Solution::`.
+fn return_type_of(prefix: &str) -> &str {
+    let mut prefix = prefix.trim();
+
+    while let Some(rest) = ["static ", "inline ", "constexpr ", "virtual ", "explicit "]
+        .iter()
+        .find_map(|specifier| prefix.strip_prefix(specifier))
+    {
+        prefix = rest.trim_start();
+    }
+
+    if let Some(rest) = prefix.strip_suffix("::") {
+        let (ty, _class) = split_declarator(rest);
+        prefix = ty;
+    }
+
+    prefix
+}
+
+/// Where the definition of a function is in the source.
+struct Definition {
+    /// The start of its return type
+    start: usize,
+    name: usize,
+    open_paren: usize,
+    close_paren: usize,
+}
+
+/// Finds the definition of the function `name`, skipping calls to it and declarations without
+/// a body.
+fn find_definition(source: &str, name: &str) -> Option<Definition> {
+    let bytes = source.as_bytes();
+
+    word_positions(source, name).find_map(|i| {
+        let open_paren = skip_whitespace(source, i + name.len());
+        if bytes.get(open_paren) != Some(&b'(') {
+            return None;
+        }
+        let close_paren = matching(source, open_paren)?;
+
+        let mut after = skip_whitespace(source, close_paren + 1);
+        for qualifier in ["const", "noexcept", "override", "final"] {
+            if source[after..].starts_with(qualifier) {
+                after = skip_whitespace(source, after + qualifier.len());
+            }
+        }
+        if bytes.get(after) != Some(&b'{') {
+            return None;
+        }
+
+        // the declaration starts after the previous statement, block or access specifier
+        let start = (0..i)
+            .rev()
+            .find(|&j| match bytes[j] {
+                b';' | b'{' | b'}' => true,
+                b':' => bytes.get(j + 1) != Some(&b':') && (j == 0 || bytes[j - 1] != b':'),
+                _ => false,
+            })
+            .map_or(0, |j| j + 1);
+        let start = skip_whitespace(source, start);
+
+        // constructors don't have a return type
+        if return_type_of(&source[start..i]).is_empty() {
+            return None;
+        }
+
+        Some(Definition {
+            start,
+            name: i,
+            open_paren,
+            close_paren,
+        })
+    })
+}
+
+/// A struct or class defined in the source, e.g. `struct Point { int x; int y; };`
+struct StructDefinition<'a> {
+    position: usize,
+    body: &'a str,
+    text: &'a str,
+}
+
+fn struct_definitions(source: &str) -> HashMap<String, StructDefinition<'_>> {
+    let bytes = source.as_bytes();
+    let mut definitions = HashMap::new();
+
+    for keyword in ["struct", "class"] {
+        for position in word_positions(source, keyword) {
+            let name_start = skip_whitespace(source, position + keyword.len());
+            let mut name_end = name_start;
+            while name_end < bytes.len() && is_identifier(bytes[name_end]) {
+                name_end += 1;
+            }
+
+            let open = skip_whitespace(source, name_end);
+            if name_start == name_end || bytes.get(open) != Some(&b'{') {
+                continue;
+            }
+
+            let Some(close) = matching(source, open) else {
+                continue;
+            };
+            let end = skip_whitespace(source, close + 1);
+            let end = if bytes.get(end) == Some(&b';') {
+                end + 1
+            } else {
+                close + 1
+            };
+
+            definitions.insert(
+                source[name_start..name_end].to_string(),
+                StructDefinition {
+                    position,
+                    body: &source[open + 1..close],
+                    text: &source[position..end],
+                },
+            );
+        }
+    }
+
+    definitions
+}
+
+/// The data members of a struct as `(type, name)` pairs, skipping its member functions.
+fn struct_fields(body: &str) -> Vec<(&str, &str)> {
+    let mut fields = vec![];
+    let mut statement_start = 0;
+    let mut i = 0;
+
+    while i < body.len() {
+        match body.as_bytes()[i] {
+            // bodies of member functions and nested types
+            b'{' => {
+                i = matching(body, i).unwrap_or(body.len() - 1);
+                statement_start = i + 1;
+            }
+            b';' => {
+                let mut statement = body[statement_start..i].trim();
+                statement_start = i + 1;
+
+                for label in ["public:", "private:", "protected:"] {
+                    if let Some(rest) = statement.strip_prefix(label) {
+                        statement = rest.trim();
+                    }
+                }
+
+                let skipped = ["static ", "using ", "typedef ", "friend ", "constexpr "];
+                if !statement.is_empty()
+                    && !statement.contains('(')
+                    && !skipped.iter().any(|s| statement.starts_with(s))
+                {
+                    // `int x = 0, y` declares `x` and `y`, which are both `int`s
+                    let declarators = split_top_level(statement, ',');
+                    let (ty, first) = split_declarator(split_top_level(declarators[0], '=')[0]);
+                    fields.push((ty, first));
+
+                    for declarator in &declarators[1..] {
+                        fields.push((ty, split_top_level(declarator, '=')[0].trim()));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    fields
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Punct(char),
+}
+
+fn tokenize(ty: &str) -> Result<Vec<Token<'_>>> {
+    let bytes = ty.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            c if c.is_ascii_whitespace() => i += 1,
+            b'<' | b'>' | b',' | b'*' | b'&' => {
+                tokens.push(Token::Punct(bytes[i] as char));
+                i += 1;
+            }
+            c if is_identifier(c) || c == b':' => {
+                // words include their namespace, e.g. `std::vector`
+                let start = i;
+                while i < bytes.len() && (is_identifier(bytes[i]) || bytes[i] == b':') {
+                    i += 1;
+                }
+                tokens.push(Token::Word(&ty[start..i]));
+            }
+            _ => bail!(
+                "Unexpected `{}` in type `{ty}`",
+                ty[i..].chars().next().unwrap()
+            ),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parses C++ types into [`FunctionType`]s, e.g. `vector<pair<int, string>>` into
+/// `Struct(Pair, List)`.
+struct TypeParser<'a> {
+    definitions: &'a HashMap<String, StructDefinition<'a>>,
+
+    /// The names of the structs being parsed, to catch structs which contain themselves
+    parsing: Vec<String>,
+
+    /// The names of the structs which were parsed
+    used: Vec<String>,
+}
+
+impl<'a> TypeParser<'a> {
+    fn parse(&mut self, ty: &str) -> Result<FunctionType> {
+        let tokens = tokenize(ty)?;
+        let mut tokens = tokens.iter().peekable();
+
+        let parsed = self.parse_tokens(&mut tokens, ty)?;
+        if let Some(token) = tokens.next() {
+            bail!("Unexpected {token:?} in type `{ty}`");
+        }
+
+        Ok(parsed)
+    }
+
+    fn parse_tokens<'t>(
+        &mut self,
+        tokens: &mut std::iter::Peekable<impl Iterator<Item = &'t Token<'t>>>,
+        ty: &str,
+    ) -> Result<FunctionType> {
+        let mut words = vec![];
+        while let Some(Token::Word(word)) = tokens.peek() {
+            let word = word.strip_prefix("std::").unwrap_or(word);
+            if !["const", "volatile", "struct", "class", "typename"].contains(&word) {
+                words.push(word);
+            }
+            tokens.next();
+        }
+
+        let mut arguments = vec![];
+        if tokens.peek() == Some(&&Token::Punct('<')) {
+            tokens.next();
+            loop {
+                arguments.push(self.parse_tokens(tokens, ty)?);
+                match tokens.next() {
+                    Some(Token::Punct(',')) => {}
+                    Some(Token::Punct('>')) => break,
+                    _ => bail!("Unclosed `<` in type `{ty}`"),
+                }
+            }
+        }
+
+        // references and `const` don't change how the value is passed
+        let mut pointers = 0;
+        loop {
+            match tokens.peek() {
+                Some(Token::Punct('*')) => pointers += 1,
+                Some(Token::Punct('&')) | Some(Token::Word("const")) => {}
+                _ => break,
+            }
+            tokens.next();
+        }
+
+        self.resolve(&words.join(" "), arguments, pointers)
+    }
+
+    fn resolve(
+        &mut self,
+        name: &str,
+        arguments: Vec<FunctionType>,
+        pointers: usize,
+    ) -> Result<FunctionType> {
+        let single = ContainerVariantType::Single;
+
+        let ty = match (name, arguments.len(), pointers) {
+            ("ListNode", 0, 1) => FunctionType::LinkedList(single),
+            ("TreeNode", 0, 1) => FunctionType::BinaryTree(single),
+            (_, _, 1..) => bail!("Pointers to `{name}` aren't supported"),
+
+            ("vector", 1, _) => in_vector(arguments.into_iter().next().unwrap()),
+            ("basic_string", 1, _)
+                if arguments[0] == FunctionType::Char(ContainerVariantType::Single) =>
+            {
+                FunctionType::String(single)
+            }
+            ("pair", 2, _) => struct_type(StructKind::Pair, arguments),
+            ("tuple", 1.., _) => struct_type(StructKind::Tuple, arguments),
+            ("set" | "unordered_set", 1, _) => {
+                let key = arguments.into_iter().next().unwrap();
+                let unordered = name == "unordered_set";
+                FunctionType::Set(Box::new(SetType { unordered, key }), single)
+            }
+            ("map" | "unordered_map", 2, _) => {
+                let mut arguments = arguments.into_iter();
+                let (key, value) = (arguments.next().unwrap(), arguments.next().unwrap());
+                let unordered = name == "unordered_map";
+                FunctionType::Map(
+                    Box::new(MapType {
+                        unordered,
+                        key,
+                        value,
+                    }),
+                    single,
+                )
+            }
+
+            (_, 0, _) => match scalar_type(name) {
+                Some(scalar) => scalar(single),
+                None => self.named_struct(name)?,
+            },
+
+            (_, n, _) => bail!("`{name}` with {n} template arguments isn't supported"),
+        };
+
+        Ok(ty)
+    }
+
+    fn named_struct(&mut self, name: &str) -> Result<FunctionType> {
+        let definition = self
+            .definitions
+            .get(name)
+            .ok_or_else(|| anyhow!("The type `{name}` isn't supported"))?;
+
+        if self.parsing.iter().any(|parsing| parsing == name) {
+            bail!("`{name}` contains itself");
+        }
+        self.parsing.push(name.to_string());
+
+        let mut field_names = vec![];
+        let mut fields = vec![];
+        for (ty, field_name) in struct_fields(definition.body) {
+            fields.push(self.parse(ty)?);
+            field_names.push(field_name.to_string());
+        }
+
+        self.parsing.pop();
+        self.used.push(name.to_string());
+
+        let kind = StructKind::Named {
+            name: name.to_string(),
+            field_names,
+        };
+        Ok(struct_type(kind, fields))
+    }
+}
+
+fn struct_type(kind: StructKind, fields: Vec<FunctionType>) -> FunctionType {
+    FunctionType::Struct(
+        Box::new(StructType { kind, fields }),
+        ContainerVariantType::Single,
+    )
+}
+
+/// The type of a `vector<T>`.
+fn in_vector(mut ty: FunctionType) -> FunctionType {
+    let container = ty.container_mut();
+    let inner = std::mem::replace(container, ContainerVariantType::Single);
+    *container = ContainerVariantType::Vector(Box::new(inner));
+
+    ty
+}
+
+/// The types of numbers, characters and strings. `long` is 32 bits in wasm32.
+fn scalar_type(name: &str) -> Option<fn(ContainerVariantType) -> FunctionType> {
+    let ty = match name {
+        "int" | "signed" | "signed int" | "int32_t" | "long" | "long int" | "signed long" => {
+            FunctionType::Int
+        }
+        "long long" | "long long int" | "signed long long" | "int64_t" => FunctionType::Long,
+        "unsigned" | "unsigned int" | "uint32_t" | "size_t" | "unsigned long" => FunctionType::UInt,
+        "unsigned long long" | "unsigned long long int" | "uint64_t" => FunctionType::ULong,
+        "short" | "short int" | "int16_t" => FunctionType::Short,
+        "unsigned char" | "uint8_t" => FunctionType::Byte,
+        "__int128" | "__int128_t" => FunctionType::Int128,
+        "float" => FunctionType::Float,
+        "double" => FunctionType::Double,
+        "char" => FunctionType::Char,
+        "bool" => FunctionType::Bool,
+        "string" => FunctionType::String,
+        _ => return None,
+    };
+
+    Some(ty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContainerVariant;
+
+    #[test]
+    fn leetcode_signatures() -> Result<()> {
+        let source = r#"
+            #include <vector>
+            using namespace std;
+
+            // a helper which calls twoSum(nums, target) { shouldn't be read
+            int helper(vector<int>& nums) { return nums.size(); }
+
+            vector<int> twoSum(const vector<int>& nums, int target) {
+                if (helper(nums)) { return {}; }
+                return {0, 1};
+            }
+        "#;
+
+        let signature = FunctionSignature::from_cpp(source, "twoSum")?;
+        assert_eq!(
+            signature.arguments,
+            vec![
+                FunctionType::Int(ContainerVariantType::list()),
+                FunctionType::Int(ContainerVariantType::Single),
+            ]
+        );
+        assert_eq!(signature.argument_names, vec!["nums", "target"]);
+        assert_eq!(
+            signature.return_type,
+            FunctionType::Int(ContainerVariantType::list())
+        );
+        assert_eq!(
+            signature.template(),
+            "vector<int> twoSum(const vector<int>& nums, int target) {\n    \n}\n"
+        );
+
+        let call = WasmFunctionCall::new(
+            "twoSum",
+            vec![
+                FunctionValue::Int(ContainerVariant::list(vec![2, 7])),
+                FunctionValue::Int(ContainerVariant::Single(9)),
+            ],
+            FunctionType::Int(ContainerVariantType::list()),
+        );
+        assert!(signature.check(&call).is_ok());
+
+        let mut wrong = call.clone();
+        wrong.arguments[1] = FunctionValue::Long(ContainerVariant::Single(9));
+        assert!(signature.check(&wrong).is_err());
+
+        assert!(signature
+            .check_output(&FunctionValue::Int(ContainerVariant::Single(1)))
+            .is_err());
+
+        let source = "long long count(TreeNode* root, std::map<string, unsigned> m) { return 0; }";
+        let signature = FunctionSignature::from_cpp(source, "count")?;
+        assert_eq!(
+            signature.arguments[0],
+            FunctionType::BinaryTree(ContainerVariantType::Single)
+        );
+        assert!(
+            matches!(signature.arguments[1], FunctionType::Map(ref ty, _)
+            if ty.key == FunctionType::String(ContainerVariantType::Single)
+                && ty.value == FunctionType::UInt(ContainerVariantType::Single))
+        );
+        assert_eq!(
+            signature.return_type,
+            FunctionType::Long(ContainerVariantType::Single)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn nested_types_and_structs() -> Result<()> {
+        let source = r#"
+            struct Point {
+                int x, y;
+                Point(int x, int y) : x(x), y(y) {}
+            };
+
+            struct Unused { char c; };
+
+            /* vector<vector<vector<long long>>> dp(int n) {} */
+            vector<pair<Point, string>> walk(vector<vector<vector<long long>>> dp, tuple<int, bool> t) {
+                return {};
+            }
+        "#;
+
+        let signature = FunctionSignature::from_cpp(source, "walk")?;
+        assert_eq!(
+            signature.arguments[0],
+            FunctionType::Long(ContainerVariantType::Vector(Box::new(
+                ContainerVariantType::grid()
+            )))
+        );
+
+        let FunctionType::Struct(pair, container) = &signature.return_type else {
+            panic!("expected a list of pairs, got {:?}", signature.return_type);
+        };
+        assert_eq!(*container, ContainerVariantType::list());
+        let FunctionType::Struct(point, _) = &pair.fields[0] else {
+            panic!("expected a point, got {:?}", pair.fields[0]);
+        };
+        assert_eq!(
+            point.kind,
+            StructKind::Named {
+                name: "Point".to_string(),
+                field_names: vec!["x".to_string(), "y".to_string()],
+            }
+        );
+        assert_eq!(point.fields.len(), 2);
+
+        assert_eq!(signature.structs.len(), 1);
+        assert!(signature.template().starts_with("struct Point {"));
+
+        let recursive = "struct Node { Node next; }; int f(Node n) { return 0; }";
+        assert!(FunctionSignature::from_cpp(recursive, "f").is_err());
+        assert!(FunctionSignature::from_cpp("void f(int x) {}", "f").is_err());
+        assert!(FunctionSignature::from_cpp("int f(int* x) { return 0; }", "f").is_err());
+        assert!(FunctionSignature::from_cpp("int g() { return f(1); }", "f").is_err());
+
+        Ok(())
+    }
+}
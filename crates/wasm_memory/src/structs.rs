@@ -0,0 +1,301 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use wasmtime::*;
+
+use crate::{
+    align_to, container_from_memory, container_into_memory, AllocatorFunc, ContainerVariant,
+    ContainerVariantType, FunctionType, FunctionValue,
+};
+
+/// Which C++ type a [`StructType`] maps to.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum StructKind {
+    /// maps to `std::pair<A, B>`
+    Pair,
+
+    /// maps to `std::tuple<T...>`
+    Tuple,
+
+    /// maps to a struct declared by the submission, e.g. `struct Point { int x; int y; };`
+    Named {
+        name: String,
+        field_names: Vec<String>,
+    },
+}
+
+/// The type of a pair, tuple or struct, whose fields are laid out in order and padded to their
+/// alignment like the fields of a C struct.
+///
+/// Structs are passed and returned through a pointer, except for structs whose only field is a
+/// number, which clang passes as the number itself. Those are not supported.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StructType {
+    pub kind: StructKind,
+    pub fields: Vec<FunctionType>,
+}
+
+/// Where the fields of a [`StructType`] are placed in memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructLayout {
+    pub offsets: Vec<usize>,
+    pub size: usize,
+    pub align: usize,
+}
+
+impl StructType {
+    pub fn name(&self) -> &str {
+        match &self.kind {
+            StructKind::Pair => "pair",
+            StructKind::Tuple => "tuple",
+            StructKind::Named { name, .. } => name,
+        }
+    }
+
+    pub fn layout(&self) -> StructLayout {
+        let mut offsets = vec![];
+        let mut size = 0;
+        let mut align = 1;
+
+        for field in &self.fields {
+            let (field_size, field_align) = field.cpp_layout();
+
+            size = align_to(size, field_align);
+            offsets.push(size);
+            size += field_size;
+            align = align.max(field_align);
+        }
+
+        // Even empty structs take up a byte in C++
+        StructLayout {
+            offsets,
+            size: align_to(size.max(1), align),
+            align,
+        }
+    }
+
+    /// Whether clang passes this struct as the number it contains, e.g. `struct { int x; }`, or
+    /// `std::tuple<long>`.
+    pub(crate) fn passed_directly(&self) -> bool {
+        match self.fields.as_slice() {
+            [FunctionType::Struct(inner, ContainerVariantType::Single)] => inner.passed_directly(),
+            [FunctionType::String(_) | FunctionType::Set(..) | FunctionType::Map(..)] => false,
+            [field] => matches!(field.container(), ContainerVariantType::Single),
+            _ => false,
+        }
+    }
+
+    fn check_fields(&self, fields: &[FunctionValue]) -> Result<()> {
+        if fields.len() != self.fields.len() {
+            bail!(
+                "Expected {} fields for `{}`, but got {}",
+                self.fields.len(),
+                self.name(),
+                fields.len()
+            );
+        }
+
+        for (value, expected) in fields.iter().zip(&self.fields) {
+            if value.function_type() != *expected {
+                bail!(
+                    "Expected a field of type {:?} in `{}`, but got {:?}",
+                    expected,
+                    self.name(),
+                    value.function_type()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fields_into_memory<S>(
+        &self,
+        fields: Vec<FunctionValue>,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+        address: usize,
+        layout: &StructLayout,
+    ) -> Result<()> {
+        self.check_fields(&fields)?;
+
+        // Padding is left uninitialized, like it would be in C++
+        for (field, offset) in fields.into_iter().zip(&layout.offsets) {
+            field.into_memory(store, memory, allocator, Some(address + offset))?;
+        }
+
+        Ok(())
+    }
+
+    fn fields_from_memory<S>(
+        &self,
+        store: &mut Store<S>,
+        memory: &Memory,
+        address: usize,
+        layout: &StructLayout,
+    ) -> Result<Vec<FunctionValue>> {
+        self.fields
+            .iter()
+            .zip(&layout.offsets)
+            .map(|(field, offset)| field.read_from_memory(store, memory, address + offset))
+            .collect()
+    }
+
+    /// Writes a container of structs, returning its address. Lists of structs are written as a
+    /// `std::vector` of the structs themselves, with each element `layout().size` bytes apart.
+    pub(crate) fn write<S>(
+        &self,
+        value: ContainerVariant<Vec<FunctionValue>>,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+        offset: Option<usize>,
+    ) -> Result<usize> {
+        let layout = self.layout();
+
+        container_into_memory(
+            value,
+            layout.size,
+            store,
+            memory,
+            allocator,
+            offset,
+            |fields, store, address| {
+                self.fields_into_memory(fields, store, memory, allocator, address, &layout)
+            },
+        )
+    }
+
+    pub(crate) fn read<S>(
+        &self,
+        variant: &ContainerVariantType,
+        store: &mut Store<S>,
+        memory: &Memory,
+        offset: usize,
+    ) -> Result<ContainerVariant<Vec<FunctionValue>>> {
+        let layout = self.layout();
+
+        container_from_memory(
+            variant,
+            layout.size,
+            store,
+            memory,
+            offset,
+            |store, address| self.fields_from_memory(store, memory, address, &layout),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm_memory::test_setup;
+    use crate::WasmMemory;
+    use byteorder::{ByteOrder, LittleEndian};
+
+    fn single(ty: fn(ContainerVariantType) -> FunctionType) -> FunctionType {
+        ty(ContainerVariantType::Single)
+    }
+
+    #[test]
+    fn padding() {
+        let pair = StructType {
+            kind: StructKind::Pair,
+            fields: vec![single(FunctionType::Char), single(FunctionType::Int)],
+        };
+        assert_eq!(
+            pair.layout(),
+            StructLayout {
+                offsets: vec![0, 4],
+                size: 8,
+                align: 4
+            }
+        );
+
+        let tuple = StructType {
+            kind: StructKind::Tuple,
+            fields: vec![
+                single(FunctionType::Int),
+                single(FunctionType::Long),
+                single(FunctionType::Bool),
+            ],
+        };
+        assert_eq!(
+            tuple.layout(),
+            StructLayout {
+                offsets: vec![0, 8, 16],
+                size: 24,
+                align: 8
+            }
+        );
+
+        let nested = StructType {
+            kind: StructKind::Named {
+                name: "Edge".to_string(),
+                field_names: vec!["weight".to_string(), "ends".to_string()],
+            },
+            fields: vec![
+                single(FunctionType::Char),
+                FunctionType::Struct(Box::new(tuple), ContainerVariantType::Single),
+                FunctionType::Int(ContainerVariantType::list()),
+            ],
+        };
+        assert_eq!(
+            nested.layout(),
+            StructLayout {
+                offsets: vec![0, 8, 32],
+                size: 48,
+                align: 8
+            }
+        );
+    }
+
+    #[test]
+    fn list_of_pairs() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        let pair = StructType {
+            kind: StructKind::Pair,
+            fields: vec![single(FunctionType::Char), single(FunctionType::Long)],
+        };
+        let pair_value = |c, l| {
+            vec![
+                FunctionValue::Char(ContainerVariant::Single(c)),
+                FunctionValue::Long(ContainerVariant::Single(l)),
+            ]
+        };
+
+        let initial = ContainerVariant::list(vec![pair_value('a', 1), pair_value('b', -2)]);
+        let address = pair.write(initial.clone(), &mut store, &memory, &allocator, None)?;
+
+        // `second` is padded to the alignment of a long, as is each element
+        let mut buf = [0; 8];
+        memory.read(&mut store, address, &mut buf)?;
+        let start = LittleEndian::read_u32(&buf) as usize;
+        let end = LittleEndian::read_u32(&buf[4..]) as usize;
+        assert_eq!(end - start, 32);
+        assert_eq!(i64::from_memory(&mut store, &memory, start + 24)?, -2);
+
+        let result = pair.read(&ContainerVariantType::list(), &mut store, &memory, address)?;
+        assert_eq!(initial, result);
+
+        Ok(())
+    }
+
+    #[test]
+    fn mismatched_fields() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        let pair = StructType {
+            kind: StructKind::Pair,
+            fields: vec![single(FunctionType::Int), single(FunctionType::Int)],
+        };
+        let value = ContainerVariant::Single(vec![FunctionValue::Int(ContainerVariant::Single(1))]);
+
+        assert!(pair
+            .write(value, &mut store, &memory, &allocator, None)
+            .is_err());
+
+        Ok(())
+    }
+}
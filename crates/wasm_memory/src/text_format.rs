@@ -0,0 +1,703 @@
+use anyhow::{anyhow, Result};
+use std::{
+    fmt::{self, Formatter},
+    str::FromStr,
+};
+
+use crate::{
+    BinaryTree, ContainerVariant, ContainerVariantType, FunctionSignature, FunctionType,
+    FunctionValue, LinkedList, StructKind, StructType, WasmFunctionCall,
+};
+
+/// A test written as text, e.g. `twoSum([2, 7, 11, 15], 9) -> [0, 1]`.
+///
+/// Values are written like their C++ initializers: lists, grids, linked lists and trees in
+/// brackets, pairs and tuples in parentheses, and sets, maps and named structs in braces, e.g.
+/// `{"a": 1}` or `{x: 1, y: 2}`. Field names of structs can be left out.
+///
+/// The expected output can be left out for tests whose output is generated by the reference
+/// solution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextTest {
+    pub input: WasmFunctionCall,
+    pub expected_output: Option<FunctionValue>,
+}
+
+impl FunctionSignature {
+    /// Parses any number of tests calling this function, separated by whitespace. Lines starting
+    /// with `//` are ignored.
+    pub fn parse_tests(&self, text: &str) -> Result<Vec<TextTest>> {
+        let mut parser = Parser::new(text);
+        let mut tests = vec![];
+
+        while !parser.at_end() {
+            tests.push(parser.test(self)?);
+        }
+
+        Ok(tests)
+    }
+}
+
+impl FunctionType {
+    /// Parses a single value of this type, e.g. `[1, 2, 3]` for an `Int(List)`.
+    pub fn parse_value(&self, text: &str) -> Result<FunctionValue> {
+        let mut parser = Parser::new(text);
+        let value = parser.value(self)?;
+
+        if !parser.at_end() {
+            return Err(parser.error("expected the end of the value"));
+        }
+
+        Ok(value)
+    }
+}
+
+/// The name of the function called by the first test in `text`, so that its signature can be
+/// found before the tests are parsed.
+pub fn called_function(text: &str) -> Result<&str> {
+    Parser::new(text).identifier()
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+type Element<'p, 'a, T> = dyn FnMut(&mut Parser<'a>) -> Result<T> + 'p;
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Parser { text, position: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.position += rest.len() - trimmed.len();
+
+            if !trimmed.starts_with("//") {
+                break;
+            }
+            self.position += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.rest().is_empty()
+    }
+
+    /// An error pointing at the current position, e.g. `line 2, column 5: expected ']'`
+    fn error(&self, message: impl fmt::Display) -> anyhow::Error {
+        let before = &self.text[..self.position];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+
+        anyhow!("line {line}, column {column}: {message}")
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{token}`")))
+        }
+    }
+
+    /// A run of characters which make up a number or a name, e.g. `-1.5e3`.
+    fn word(&mut self) -> &'a str {
+        self.skip_whitespace();
+
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || "_.+-".contains(c)))
+            .unwrap_or(rest.len());
+
+        self.position += len;
+        &rest[..len]
+    }
+
+    fn identifier(&mut self) -> Result<&'a str> {
+        self.skip_whitespace();
+
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+
+        if len == 0 || rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(self.error("expected a name"));
+        }
+
+        self.position += len;
+        Ok(&rest[..len])
+    }
+
+    fn number<T: FromStr>(&mut self, name: &str) -> Result<T> {
+        let start = self.position;
+        let word = self.word();
+
+        word.parse().map_err(|_| {
+            self.position = start;
+            self.skip_whitespace();
+            self.error(format!("expected {name}, found `{word}`"))
+        })
+    }
+
+    fn bool(&mut self) -> Result<bool> {
+        match self.word() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            word => Err(self.error(format!("expected `true` or `false`, found `{word}`"))),
+        }
+    }
+
+    /// The characters between `quote`s, with escapes like `\n` replaced.
+    fn quoted(&mut self, quote: char) -> Result<String> {
+        self.expect(&quote.to_string())?;
+
+        let mut result = String::new();
+        let mut chars = self.rest().char_indices();
+
+        loop {
+            let Some((i, c)) = chars.next() else {
+                return Err(self.error(format!("missing a closing `{quote}`")));
+            };
+
+            if c == quote {
+                self.position += i + 1;
+                return Ok(result);
+            }
+
+            if c != '\\' {
+                result.push(c);
+                continue;
+            }
+
+            result.push(match chars.next() {
+                Some((_, 'n')) => '\n',
+                Some((_, 't')) => '\t',
+                Some((_, 'r')) => '\r',
+                Some((_, '0')) => '\0',
+                Some((_, c @ ('\\' | '"' | '\''))) => c,
+                _ => {
+                    self.position += i;
+                    return Err(self.error("unknown escape"));
+                }
+            });
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.quoted('"')
+    }
+
+    fn char(&mut self) -> Result<char> {
+        let start = self.position;
+        let quoted = self.quoted('\'')?;
+
+        let mut chars = quoted.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => {
+                self.position = start;
+                self.skip_whitespace();
+                Err(self.error("expected a single character"))
+            }
+        }
+    }
+
+    /// Elements separated by commas between `open` and `close`, with an optional trailing comma.
+    fn list<T>(
+        &mut self,
+        open: &str,
+        close: &str,
+        element: &mut Element<'_, 'a, T>,
+    ) -> Result<Vec<T>> {
+        self.expect(open)?;
+
+        let mut elements = vec![];
+        while !self.eat(close) {
+            elements.push(element(self)?);
+
+            if !self.eat(",") {
+                self.expect(close)?;
+                break;
+            }
+        }
+
+        Ok(elements)
+    }
+
+    fn container<T>(
+        &mut self,
+        ty: &ContainerVariantType,
+        element: &mut Element<'_, 'a, T>,
+    ) -> Result<ContainerVariant<T>> {
+        Ok(match ty {
+            ContainerVariantType::Single => ContainerVariant::Single(element(self)?),
+            ContainerVariantType::Graph => {
+                ContainerVariant::Graph(self.list("[", "]", &mut |p| p.list("[", "]", element))?)
+            }
+            ContainerVariantType::Vector(inner) => ContainerVariant::Vector(
+                inner.clone(),
+                self.list("[", "]", &mut |p| p.container(inner, element))?,
+            ),
+        })
+    }
+
+    fn fields(&mut self, ty: &StructType) -> Result<Vec<FunctionValue>> {
+        let (open, close, names) = match &ty.kind {
+            StructKind::Pair | StructKind::Tuple => ("(", ")", None),
+            StructKind::Named { field_names, .. } => ("{", "}", Some(field_names)),
+        };
+
+        self.expect(open)?;
+
+        let mut fields = vec![];
+        for (i, field) in ty.fields.iter().enumerate() {
+            if i > 0 {
+                self.expect(",")?;
+            }
+
+            if let Some(names) = names {
+                self.field_name(&names[i])?;
+            }
+
+            fields.push(self.value(field)?);
+        }
+
+        self.eat(",");
+        if !self.eat(close) {
+            return Err(self.error(format!(
+                "expected `{close}` after the {} fields of `{}`",
+                ty.fields.len(),
+                ty.name()
+            )));
+        }
+
+        Ok(fields)
+    }
+
+    /// Skips over `name:` before the field of a struct, if it's there.
+    fn field_name(&mut self, name: &str) -> Result<()> {
+        let start = self.position;
+
+        match self.identifier() {
+            Ok(found) if self.eat(":") => {
+                if found != name {
+                    self.position = start;
+                    self.skip_whitespace();
+                    return Err(self.error(format!("expected the field `{name}`")));
+                }
+            }
+            _ => self.position = start,
+        }
+
+        Ok(())
+    }
+
+    fn tree_node(&mut self) -> Result<Option<i32>> {
+        if self.word_is("null") {
+            Ok(None)
+        } else {
+            self.number("an int or `null`").map(Some)
+        }
+    }
+
+    /// Consumes `word` if it comes next.
+    fn word_is(&mut self, word: &str) -> bool {
+        let start = self.position;
+        if self.word() == word {
+            true
+        } else {
+            self.position = start;
+            false
+        }
+    }
+
+    fn value(&mut self, ty: &FunctionType) -> Result<FunctionValue> {
+        Ok(match ty {
+            FunctionType::String(c) => {
+                FunctionValue::String(self.container(c, &mut |p| p.string())?)
+            }
+            FunctionType::Int(c) => {
+                FunctionValue::Int(self.container(c, &mut |p| p.number("an int"))?)
+            }
+            FunctionType::Long(c) => {
+                FunctionValue::Long(self.container(c, &mut |p| p.number("a long"))?)
+            }
+            FunctionType::Float(c) => {
+                FunctionValue::Float(self.container(c, &mut |p| p.number("a float"))?)
+            }
+            FunctionType::Double(c) => {
+                FunctionValue::Double(self.container(c, &mut |p| p.number("a double"))?)
+            }
+            FunctionType::Char(c) => FunctionValue::Char(self.container(c, &mut |p| p.char())?),
+            FunctionType::Bool(c) => FunctionValue::Bool(self.container(c, &mut |p| p.bool())?),
+            FunctionType::UInt(c) => {
+                FunctionValue::UInt(self.container(c, &mut |p| p.number("an unsigned int"))?)
+            }
+            FunctionType::ULong(c) => {
+                FunctionValue::ULong(self.container(c, &mut |p| p.number("an unsigned long"))?)
+            }
+            FunctionType::Short(c) => {
+                FunctionValue::Short(self.container(c, &mut |p| p.number("a short"))?)
+            }
+            FunctionType::Byte(c) => {
+                FunctionValue::Byte(self.container(c, &mut |p| p.number("a byte"))?)
+            }
+            FunctionType::Int128(c) => {
+                FunctionValue::Int128(self.container(c, &mut |p| p.number("an __int128"))?)
+            }
+            FunctionType::Struct(ty, c) => {
+                FunctionValue::Struct(ty.clone(), self.container(c, &mut |p| p.fields(ty))?)
+            }
+            FunctionType::Set(ty, c) => FunctionValue::Set(
+                ty.clone(),
+                self.container(c, &mut |p| p.list("{", "}", &mut |p| p.value(&ty.key)))?,
+            ),
+            FunctionType::Map(ty, c) => FunctionValue::Map(
+                ty.clone(),
+                self.container(c, &mut |p| {
+                    p.list("{", "}", &mut |p| {
+                        let key = p.value(&ty.key)?;
+                        p.expect(":")?;
+                        Ok((key, p.value(&ty.value)?))
+                    })
+                })?,
+            ),
+            FunctionType::LinkedList(c) => {
+                FunctionValue::LinkedList(self.container(c, &mut |p| {
+                    Ok(LinkedList(p.list("[", "]", &mut |p| p.number("an int"))?))
+                })?)
+            }
+            FunctionType::BinaryTree(c) => {
+                FunctionValue::BinaryTree(self.container(c, &mut |p| {
+                    Ok(BinaryTree(p.list("[", "]", &mut |p| p.tree_node())?))
+                })?)
+            }
+        })
+    }
+
+    fn test(&mut self, signature: &FunctionSignature) -> Result<TextTest> {
+        let name = self.identifier()?;
+        if name != signature.name {
+            return Err(self.error(format!(
+                "the test calls `{name}`, but expected `{}`",
+                signature.name
+            )));
+        }
+
+        self.expect("(")?;
+
+        let mut arguments = vec![];
+        for (i, argument) in signature.arguments.iter().enumerate() {
+            if i > 0 {
+                self.expect(",")?;
+            }
+            arguments.push(self.value(argument)?);
+        }
+
+        self.eat(",");
+        if !self.eat(")") {
+            return Err(self.error(format!(
+                "expected `)` after the {} arguments of `{name}`",
+                signature.arguments.len()
+            )));
+        }
+
+        let expected_output = if self.eat("->") {
+            Some(self.value(&signature.return_type)?)
+        } else {
+            None
+        };
+
+        Ok(TextTest {
+            input: WasmFunctionCall::new(name, arguments, signature.return_type.clone()),
+            expected_output,
+        })
+    }
+}
+
+fn write_list<T>(
+    f: &mut Formatter,
+    open: &str,
+    close: &str,
+    elements: &[T],
+    element: &dyn Fn(&mut Formatter, &T) -> fmt::Result,
+) -> fmt::Result {
+    f.write_str(open)?;
+    for (i, e) in elements.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        element(f, e)?;
+    }
+    f.write_str(close)
+}
+
+fn write_container<T>(
+    f: &mut Formatter,
+    container: &ContainerVariant<T>,
+    element: &dyn Fn(&mut Formatter, &T) -> fmt::Result,
+) -> fmt::Result {
+    match container {
+        ContainerVariant::Single(s) => element(f, s),
+        ContainerVariant::Graph(g) => write_list(f, "[", "]", g, &|f, row| {
+            write_list(f, "[", "]", row, element)
+        }),
+        ContainerVariant::Vector(_, v) => {
+            write_list(f, "[", "]", v, &|f, c| write_container(f, c, element))
+        }
+    }
+}
+
+fn write_quoted(f: &mut Formatter, text: &str, quote: char) -> fmt::Result {
+    write!(f, "{quote}")?;
+    for c in text.chars() {
+        match c {
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            '\r' => f.write_str("\\r")?,
+            '\0' => f.write_str("\\0")?,
+            '\\' => f.write_str("\\\\")?,
+            c if c == quote => write!(f, "\\{c}")?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "{quote}")
+}
+
+fn write_display<T: fmt::Display>(f: &mut Formatter, value: &T) -> fmt::Result {
+    write!(f, "{value}")
+}
+
+fn write_fields(f: &mut Formatter, ty: &StructType, fields: &[FunctionValue]) -> fmt::Result {
+    match &ty.kind {
+        StructKind::Pair | StructKind::Tuple => write_list(f, "(", ")", fields, &write_display),
+        StructKind::Named { field_names, .. } => {
+            let named: Vec<_> = field_names.iter().zip(fields).collect();
+            write_list(f, "{", "}", &named, &|f, (name, value)| {
+                write!(f, "{name}: {value}")
+            })
+        }
+    }
+}
+
+/// Writes the value in the format read by [`FunctionType::parse_value`].
+impl fmt::Display for FunctionValue {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            FunctionValue::String(v) => write_container(f, v, &|f, s| write_quoted(f, s, '"')),
+            FunctionValue::Int(v) => write_container(f, v, &write_display),
+            FunctionValue::Long(v) => write_container(f, v, &write_display),
+            FunctionValue::Float(v) => write_container(f, v, &write_display),
+            FunctionValue::Double(v) => write_container(f, v, &write_display),
+            FunctionValue::Char(v) => {
+                write_container(f, v, &|f, c| write_quoted(f, &c.to_string(), '\''))
+            }
+            FunctionValue::Bool(v) => write_container(f, v, &write_display),
+            FunctionValue::UInt(v) => write_container(f, v, &write_display),
+            FunctionValue::ULong(v) => write_container(f, v, &write_display),
+            FunctionValue::Short(v) => write_container(f, v, &write_display),
+            FunctionValue::Byte(v) => write_container(f, v, &write_display),
+            FunctionValue::Int128(v) => write_container(f, v, &write_display),
+            FunctionValue::Struct(ty, v) => {
+                write_container(f, v, &|f, fields| write_fields(f, ty, fields))
+            }
+            FunctionValue::Set(_, v) => write_container(f, v, &|f, keys| {
+                write_list(f, "{", "}", keys, &write_display)
+            }),
+            FunctionValue::Map(_, v) => write_container(f, v, &|f, entries| {
+                write_list(f, "{", "}", entries, &|f, (key, value)| {
+                    write!(f, "{key}: {value}")
+                })
+            }),
+            FunctionValue::LinkedList(v) => write_container(f, v, &|f, list| {
+                write_list(f, "[", "]", &list.0, &write_display)
+            }),
+            FunctionValue::BinaryTree(v) => write_container(f, v, &|f, tree| {
+                write_list(f, "[", "]", &tree.0, &|f, node| match node {
+                    Some(val) => write!(f, "{val}"),
+                    None => f.write_str("null"),
+                })
+            }),
+        }
+    }
+}
+
+/// Writes the call like `twoSum([2, 7, 11, 15], 9)`.
+impl fmt::Display for WasmFunctionCall {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.name)?;
+        write_list(f, "(", ")", &self.arguments, &write_display)
+    }
+}
+
+/// Writes the test in the format read by [`FunctionSignature::parse_tests`].
+impl fmt::Display for TextTest {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.input)?;
+
+        match &self.expected_output {
+            Some(output) => write!(f, " -> {output}"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MapType, SetType};
+
+    fn signature(arguments: Vec<FunctionType>, return_type: FunctionType) -> FunctionSignature {
+        FunctionSignature {
+            name: "solve".to_string(),
+            argument_names: arguments.iter().map(|_| String::new()).collect(),
+            arguments,
+            return_type,
+            declaration: String::new(),
+            structs: vec![],
+        }
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let point = StructType {
+            kind: StructKind::Named {
+                name: "Point".to_string(),
+                field_names: vec!["x".to_string(), "y".to_string()],
+            },
+            fields: vec![
+                FunctionType::Int(ContainerVariantType::Single),
+                FunctionType::Double(ContainerVariantType::Single),
+            ],
+        };
+        let pair = StructType {
+            kind: StructKind::Pair,
+            fields: vec![
+                FunctionType::Char(ContainerVariantType::Single),
+                FunctionType::Bool(ContainerVariantType::list()),
+            ],
+        };
+        let map = MapType {
+            unordered: false,
+            key: FunctionType::String(ContainerVariantType::Single),
+            value: FunctionType::Long(ContainerVariantType::list()),
+        };
+        let set = SetType {
+            unordered: true,
+            key: FunctionType::Int(ContainerVariantType::Single),
+        };
+
+        let signature = signature(
+            vec![
+                FunctionType::Int(ContainerVariantType::list()),
+                FunctionType::String(ContainerVariantType::Single),
+                FunctionType::Struct(Box::new(point), ContainerVariantType::list()),
+                FunctionType::Struct(Box::new(pair), ContainerVariantType::Single),
+                FunctionType::Map(Box::new(map), ContainerVariantType::Single),
+                FunctionType::Set(Box::new(set), ContainerVariantType::Single),
+                FunctionType::Int(ContainerVariantType::Vector(Box::new(
+                    ContainerVariantType::grid(),
+                ))),
+                FunctionType::BinaryTree(ContainerVariantType::Single),
+                FunctionType::LinkedList(ContainerVariantType::Single),
+            ],
+            FunctionType::Double(ContainerVariantType::grid()),
+        );
+
+        let text = r#"
+            // every kind of value
+            solve(
+                [1, -2, 3,],
+                "a \"quoted\"\n string",
+                [{x: 1, y: 2.5}, {3, -0.5}],
+                ('\'', [true, false]),
+                {"a": [1, 2], "b": []},
+                {3, 1, 2},
+                [[[1, 2], [3]], []],
+                [1, null, 2, 3],
+                [],
+            ) -> [[1.5], []]
+
+            solve([], "", [], ('c', []), {}, {}, [], [], [1])
+        "#;
+
+        let tests = signature.parse_tests(text)?;
+        assert_eq!(tests.len(), 2);
+        assert!(tests[1].expected_output.is_none());
+
+        for test in &tests {
+            signature.check(&test.input)?;
+
+            let printed = test.to_string();
+            assert_eq!(signature.parse_tests(&printed)?, vec![test.clone()]);
+        }
+
+        assert_eq!(
+            tests[0].expected_output.as_ref().unwrap().to_string(),
+            "[[1.5], []]"
+        );
+        assert_eq!(
+            tests[0].input.arguments[2].to_string(),
+            "[{x: 1, y: 2.5}, {x: 3, y: -0.5}]"
+        );
+        assert_eq!(called_function(text)?, "solve");
+
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let signature = signature(
+            vec![
+                FunctionType::Int(ContainerVariantType::list()),
+                FunctionType::Char(ContainerVariantType::Single),
+            ],
+            FunctionType::Int(ContainerVariantType::Single),
+        );
+
+        let error = |text: &str| signature.parse_tests(text).unwrap_err().to_string();
+
+        assert_eq!(
+            error("solve([1, 2 3], 'a') -> 1"),
+            "line 1, column 13: expected `]`"
+        );
+        assert_eq!(
+            error("solve([1],\n  'ab') -> 1"),
+            "line 2, column 3: expected a single character"
+        );
+        assert_eq!(
+            error("solve([1], 'a', 2) -> 1"),
+            "line 1, column 17: expected `)` after the 2 arguments of `solve`"
+        );
+        assert_eq!(
+            error("solve([x], 'a')"),
+            "line 1, column 8: expected an int, found `x`"
+        );
+        assert!(error("other([1], 'a')").contains("expected `solve`"));
+
+        assert!(FunctionType::Int(ContainerVariantType::Single)
+            .parse_value("1 2")
+            .is_err());
+    }
+}
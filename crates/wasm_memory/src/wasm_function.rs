@@ -1,48 +1,23 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use wasmtime::*;
 
-use crate::{AllocatorFunc, WasmMemory};
+use crate::{
+    c_string_from_memory, canonical_map, canonical_set, find_function, AllocatorFunc, BinaryTree,
+    ContainerVariant, ContainerVariantType, LinkedList, MapType, SetType, SizeMetrics, StructType,
+    WasmMemory,
+};
 
-#[derive(thiserror::Error, Debug)]
-enum FunctionError {
-    #[error("Expected a function with name \"{0}\", but it was not found.")]
-    NameNotFound(String),
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-pub enum ContainerVariantType {
-    Graph,
-    Grid,
-    List,
-    Single,
-}
+/// How arguments and return values are laid out in memory, which depends on the language the
+/// called module was written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Abi {
+    /// libc++ containers, e.g. `std::vector<int>`
+    CPlusPlus,
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-pub enum ContainerVariant<T: WasmMemory> {
-    Graph(Vec<Vec<T>>),
-    Grid(Vec<Vec<T>>),
-    List(Vec<T>),
-    Single(T),
-}
-
-impl<T> ContainerVariant<T>
-where
-    T: WasmMemory,
-{
-    fn into_memory<S>(
-        self,
-        store: &mut Store<S>,
-        memory: &Memory,
-        allocator: &AllocatorFunc,
-    ) -> Result<usize> {
-        match self {
-            ContainerVariant::Graph(graph) => graph.into_memory(store, memory, allocator, None),
-            ContainerVariant::Grid(grid) => grid.into_memory(store, memory, allocator, None),
-            ContainerVariant::List(list) => list.into_memory(store, memory, allocator, None),
-            ContainerVariant::Single(single) => single.into_memory(store, memory, allocator, None),
-        }
-    }
+    /// pointer and length pairs, e.g. `int* nums, int numsSize`. See [`crate::CMemory`].
+    C,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -54,87 +29,484 @@ pub enum FunctionValue {
     Double(ContainerVariant<f64>),
     Char(ContainerVariant<char>),
     Bool(ContainerVariant<bool>),
+    UInt(ContainerVariant<u32>),
+    ULong(ContainerVariant<u64>),
+    Short(ContainerVariant<i16>),
+    Byte(ContainerVariant<u8>),
+
+    /// An `__int128`, which is passed to functions as two `i64`s
+    Int128(ContainerVariant<i128>),
+
+    /// A pair, tuple or struct, whose elements are the values of each of its fields, e.g.
+    /// `{"Struct": [{"kind": "Pair", "fields": [{"Int": "Single"}, {"Int": "Single"}]}, {"List": [[{"Int": {"Single": 1}}, {"Int": {"Single": 2}}]]}]}`
+    Struct(Box<StructType>, ContainerVariant<Vec<FunctionValue>>),
+
+    /// A `std::set` or `std::unordered_set`, holding its keys
+    Set(Box<SetType>, ContainerVariant<Vec<FunctionValue>>),
+
+    /// A `std::map` or `std::unordered_map`, holding its key and value pairs
+    Map(
+        Box<MapType>,
+        ContainerVariant<Vec<(FunctionValue, FunctionValue)>>,
+    ),
+
+    /// A `ListNode*`, e.g. `{"LinkedList": {"Single": [1, 2, 3]}}`
+    LinkedList(ContainerVariant<LinkedList>),
+
+    /// A `TreeNode*`, e.g. `{"BinaryTree": {"Single": [1, null, 2]}}`
+    BinaryTree(ContainerVariant<BinaryTree>),
 }
 
 impl FunctionValue {
-    fn into_memory<S>(
+    /// Writes the value to `offset`, or to a new allocation if it's `None`, returning its address
+    pub(crate) fn into_memory<S>(
         self,
         store: &mut Store<S>,
         memory: &Memory,
         allocator: &AllocatorFunc,
+        offset: Option<usize>,
     ) -> Result<usize> {
         let next_offset = match self {
-            FunctionValue::String(s) => s.into_memory(store, memory, allocator)?,
-            FunctionValue::Int(i) => i.into_memory(store, memory, allocator)?,
-            FunctionValue::Float(f) => f.into_memory(store, memory, allocator)?,
-            FunctionValue::Char(c) => c.into_memory(store, memory, allocator)?,
-            FunctionValue::Bool(b) => b.into_memory(store, memory, allocator)?,
-            FunctionValue::Long(l) => l.into_memory(store, memory, allocator)?,
-            FunctionValue::Double(d) => d.into_memory(store, memory, allocator)?,
+            FunctionValue::String(s) => s.into_memory(store, memory, allocator, offset)?,
+            FunctionValue::Int(i) => i.into_memory(store, memory, allocator, offset)?,
+            FunctionValue::Float(f) => f.into_memory(store, memory, allocator, offset)?,
+            FunctionValue::Char(c) => c.into_memory(store, memory, allocator, offset)?,
+            FunctionValue::Bool(b) => b.into_memory(store, memory, allocator, offset)?,
+            FunctionValue::Long(l) => l.into_memory(store, memory, allocator, offset)?,
+            FunctionValue::Double(d) => d.into_memory(store, memory, allocator, offset)?,
+            FunctionValue::UInt(u) => u.into_memory(store, memory, allocator, offset)?,
+            FunctionValue::ULong(u) => u.into_memory(store, memory, allocator, offset)?,
+            FunctionValue::Short(s) => s.into_memory(store, memory, allocator, offset)?,
+            FunctionValue::Byte(b) => b.into_memory(store, memory, allocator, offset)?,
+            FunctionValue::Int128(i) => i.into_memory(store, memory, allocator, offset)?,
+            FunctionValue::Struct(ty, v) => ty.write(v, store, memory, allocator, offset)?,
+            FunctionValue::Set(ty, v) => ty.write(v, store, memory, allocator, offset)?,
+            FunctionValue::Map(ty, v) => ty.write(v, store, memory, allocator, offset)?,
+            FunctionValue::LinkedList(l) => l.into_memory(store, memory, allocator, offset)?,
+            FunctionValue::BinaryTree(t) => t.into_memory(store, memory, allocator, offset)?,
         };
 
         Ok(next_offset)
     }
 
-    pub fn scaling_factor(&self) -> f32 {
+    fn into_c_params<S>(
+        self,
+        store: &mut Store<S>,
+        memory: &Memory,
+        allocator: &AllocatorFunc,
+    ) -> Result<Vec<Val>> {
         match self {
-            FunctionValue::String(ContainerVariant::Single(s)) => s.len() as f32,
-            FunctionValue::String(ContainerVariant::List(l)) => l.len() as f32,
-            FunctionValue::String(ContainerVariant::Grid(g)) => (g[0].len() * g.len()) as f32,
-            FunctionValue::String(ContainerVariant::Graph(g)) => g.len() as f32, // for now we just do number of nodes, should factor edges though
-
-            FunctionValue::Int(ContainerVariant::Single(s)) => s.abs() as f32,
-            FunctionValue::Int(ContainerVariant::List(l)) => l.len() as f32,
-            FunctionValue::Int(ContainerVariant::Grid(g)) => (g[0].len() * g.len()) as f32,
-            FunctionValue::Int(ContainerVariant::Graph(g)) => g.len() as f32,
-
-            FunctionValue::Long(ContainerVariant::Single(s)) => s.abs() as f32,
-            FunctionValue::Long(ContainerVariant::List(l)) => l.len() as f32,
-            FunctionValue::Long(ContainerVariant::Grid(g)) => (g[0].len() * g.len()) as f32,
-            FunctionValue::Long(ContainerVariant::Graph(g)) => g.len() as f32,
-
-            FunctionValue::Float(ContainerVariant::Single(s)) => s.abs() as f32,
-            FunctionValue::Float(ContainerVariant::List(l)) => l.len() as f32,
-            FunctionValue::Float(ContainerVariant::Grid(g)) => (g[0].len() * g.len()) as f32,
-            FunctionValue::Float(ContainerVariant::Graph(g)) => g.len() as f32,
-
-            FunctionValue::Double(ContainerVariant::Single(s)) => s.abs() as f32,
-            FunctionValue::Double(ContainerVariant::List(l)) => l.len() as f32,
-            FunctionValue::Double(ContainerVariant::Grid(g)) => (g[0].len() * g.len()) as f32,
-            FunctionValue::Double(ContainerVariant::Graph(g)) => g.len() as f32,
-
-            FunctionValue::Char(ContainerVariant::Single(_)) => 1.0,
-            FunctionValue::Char(ContainerVariant::List(l)) => l.len() as f32,
-            FunctionValue::Char(ContainerVariant::Grid(g)) => (g[0].len() * g.len()) as f32,
-            FunctionValue::Char(ContainerVariant::Graph(g)) => g.len() as f32,
-
-            FunctionValue::Bool(ContainerVariant::Single(_)) => 1.0,
-            FunctionValue::Bool(ContainerVariant::List(l)) => l.len() as f32,
-            FunctionValue::Bool(ContainerVariant::Grid(g)) => (g[0].len() * g.len()) as f32,
-            FunctionValue::Bool(ContainerVariant::Graph(g)) => g.len() as f32,
+            FunctionValue::String(s) => s.into_c_params(store, memory, allocator),
+            FunctionValue::Int(i) => i.into_c_params(store, memory, allocator),
+            FunctionValue::Float(f) => f.into_c_params(store, memory, allocator),
+            FunctionValue::Char(c) => c.into_c_params(store, memory, allocator),
+            FunctionValue::Bool(b) => b.into_c_params(store, memory, allocator),
+            FunctionValue::Long(l) => l.into_c_params(store, memory, allocator),
+            FunctionValue::Double(d) => d.into_c_params(store, memory, allocator),
+            FunctionValue::UInt(u) => u.into_c_params(store, memory, allocator),
+            FunctionValue::ULong(u) => u.into_c_params(store, memory, allocator),
+            FunctionValue::Short(s) => s.into_c_params(store, memory, allocator),
+            FunctionValue::Byte(b) => b.into_c_params(store, memory, allocator),
+            FunctionValue::Int128(i) => i.into_c_params(store, memory, allocator),
+            FunctionValue::Struct(..) | FunctionValue::Set(..) | FunctionValue::Map(..) => {
+                bail!(UNSUPPORTED_IN_C)
+            }
+
+            // single nodes are passed as pointers before getting here
+            FunctionValue::LinkedList(_) | FunctionValue::BinaryTree(_) => {
+                bail!(NODES_UNSUPPORTED_IN_C)
+            }
+        }
+    }
+
+    /// How large the value is for estimating complexity. Numbers scale with their magnitude, and
+    /// everything else with how many elements it has.
+    pub fn scaling_factor(&self) -> SizeMetrics {
+        match self {
+            FunctionValue::String(v) => v.scaling_factor(|s| s.len() as f32),
+            FunctionValue::Int(v) => v.scaling_factor(|s| s.abs() as f32),
+            FunctionValue::Long(v) => v.scaling_factor(|s| s.abs() as f32),
+            FunctionValue::Float(v) => v.scaling_factor(|s| s.abs()),
+            FunctionValue::Double(v) => v.scaling_factor(|s| s.abs() as f32),
+            FunctionValue::Char(v) => v.scaling_factor(|_| 1.0),
+            FunctionValue::Bool(v) => v.scaling_factor(|_| 1.0),
+            FunctionValue::UInt(v) => v.scaling_factor(|s| *s as f32),
+            FunctionValue::ULong(v) => v.scaling_factor(|s| *s as f32),
+            FunctionValue::Short(v) => v.scaling_factor(|s| s.unsigned_abs() as f32),
+            FunctionValue::Byte(v) => v.scaling_factor(|s| *s as f32),
+            FunctionValue::Int128(v) => v.scaling_factor(|s| s.unsigned_abs() as f32),
+
+            // a struct scales with its largest field, e.g. `n` in `pair<int, vector<int>>`
+            FunctionValue::Struct(_, v) => v.scaling_factor(|fields| {
+                fields
+                    .iter()
+                    .map(FunctionValue::scaling_factor)
+                    .fold(SizeMetrics::default(), SizeMetrics::max)
+            }),
+            FunctionValue::Set(_, v) => v.scaling_factor(|s| s.len() as f32),
+            FunctionValue::Map(_, v) => v.scaling_factor(|m| m.len() as f32),
+            FunctionValue::LinkedList(v) => v.scaling_factor(|l| l.0.len() as f32),
+            FunctionValue::BinaryTree(v) => v.scaling_factor(|t| t.len() as f32),
+        }
+    }
+
+    /// Returns the type of this value, e.g. `Int(List)` for a `FunctionValue::Int(List(..))`
+    pub fn function_type(&self) -> FunctionType {
+        match self {
+            FunctionValue::String(v) => FunctionType::String(v.variant_type()),
+            FunctionValue::Int(v) => FunctionType::Int(v.variant_type()),
+            FunctionValue::Long(v) => FunctionType::Long(v.variant_type()),
+            FunctionValue::Float(v) => FunctionType::Float(v.variant_type()),
+            FunctionValue::Double(v) => FunctionType::Double(v.variant_type()),
+            FunctionValue::Char(v) => FunctionType::Char(v.variant_type()),
+            FunctionValue::Bool(v) => FunctionType::Bool(v.variant_type()),
+            FunctionValue::UInt(v) => FunctionType::UInt(v.variant_type()),
+            FunctionValue::ULong(v) => FunctionType::ULong(v.variant_type()),
+            FunctionValue::Short(v) => FunctionType::Short(v.variant_type()),
+            FunctionValue::Byte(v) => FunctionType::Byte(v.variant_type()),
+            FunctionValue::Int128(v) => FunctionType::Int128(v.variant_type()),
+            FunctionValue::Struct(ty, v) => FunctionType::Struct(ty.clone(), v.variant_type()),
+            FunctionValue::Set(ty, v) => FunctionType::Set(ty.clone(), v.variant_type()),
+            FunctionValue::Map(ty, v) => FunctionType::Map(ty.clone(), v.variant_type()),
+            FunctionValue::LinkedList(v) => FunctionType::LinkedList(v.variant_type()),
+            FunctionValue::BinaryTree(v) => FunctionType::BinaryTree(v.variant_type()),
         }
     }
 }
 
-impl PartialEq for FunctionValue {
-    fn eq(&self, other: &Self) -> bool {
+/// How close floating point values must be to be considered equal. Values are equal if they are
+/// within either the absolute or the relative tolerance of each other.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct FloatTolerance {
+    #[serde(default = "default_absolute_tolerance")]
+    pub absolute: f64,
+    #[serde(default)]
+    pub relative: f64,
+}
+
+fn default_absolute_tolerance() -> f64 {
+    1e-9
+}
+
+impl Default for FloatTolerance {
+    fn default() -> Self {
+        FloatTolerance {
+            absolute: default_absolute_tolerance(),
+            relative: 0.0,
+        }
+    }
+}
+
+impl FloatTolerance {
+    pub fn close(&self, left: f64, right: f64) -> bool {
+        let difference = (left - right).abs();
+
+        left == right
+            || difference <= self.absolute
+            || difference <= self.relative * left.abs().max(right.abs())
+    }
+
+    fn containers_close<T: Copy + Into<f64>>(
+        &self,
+        left: &ContainerVariant<T>,
+        right: &ContainerVariant<T>,
+    ) -> bool {
+        containers_match(left, right, |l, r| self.close((*l).into(), (*r).into()))
+    }
+}
+
+/// Whether two containers have the same shape, and `eq` holds for each pair of elements.
+fn containers_match<T>(
+    left: &ContainerVariant<T>,
+    right: &ContainerVariant<T>,
+    eq: impl Fn(&T, &T) -> bool + Copy,
+) -> bool {
+    match (left, right) {
+        (ContainerVariant::Single(l), ContainerVariant::Single(r)) => eq(l, r),
+        (ContainerVariant::Graph(l), ContainerVariant::Graph(r)) => {
+            all_match(l, r, |l, r| all_match(l, r, eq))
+        }
+        (ContainerVariant::Vector(lt, l), ContainerVariant::Vector(rt, r)) => {
+            lt == rt && all_match(l, r, |l, r| containers_match(l, r, eq))
+        }
+        _ => false,
+    }
+}
+
+/// Whether two slices have the same length, and `eq` holds for each pair of elements.
+fn all_match<T>(left: &[T], right: &[T], eq: impl Fn(&T, &T) -> bool) -> bool {
+    left.len() == right.len() && left.iter().zip(right).all(|(l, r)| eq(l, r))
+}
+
+/// Compares two slices lexicographically with `cmp`.
+fn lexicographic_cmp<T>(left: &[T], right: &[T], cmp: impl Fn(&T, &T) -> Ordering) -> Ordering {
+    left.iter()
+        .zip(right)
+        .map(|(l, r)| cmp(l, r))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| left.len().cmp(&right.len()))
+}
+
+/// Compares two containers of the same shape with `cmp`, with vectors compared lexicographically.
+fn containers_cmp<T>(
+    left: &ContainerVariant<T>,
+    right: &ContainerVariant<T>,
+    cmp: impl Fn(&T, &T) -> Ordering + Copy,
+) -> Ordering {
+    match (left, right) {
+        (ContainerVariant::Single(l), ContainerVariant::Single(r)) => cmp(l, r),
+        (ContainerVariant::Graph(l), ContainerVariant::Graph(r)) => {
+            lexicographic_cmp(l, r, |l, r| lexicographic_cmp(l, r, cmp))
+        }
+        (ContainerVariant::Vector(_, l), ContainerVariant::Vector(_, r)) => {
+            lexicographic_cmp(l, r, |l, r| containers_cmp(l, r, cmp))
+        }
+        // only values of the same type are compared
+        _ => Ordering::Equal,
+    }
+}
+
+impl FunctionValue {
+    /// Compares two values, where floats and doubles in any container are compared with
+    /// `tolerance`.
+    pub fn matches(&self, other: &Self, tolerance: &FloatTolerance) -> bool {
         match (self, other) {
-            // Compare singleton doubles with less precision because there can be annoying
-            // precision issues when solving problems otherwise.
-            (
-                FunctionValue::Double(ContainerVariant::Single(left)),
-                FunctionValue::Double(ContainerVariant::Single(right)),
-            ) => (*left - *right).abs() < 1e-9,
+            (FunctionValue::Float(left), FunctionValue::Float(right)) => {
+                tolerance.containers_close(left, right)
+            }
+            (FunctionValue::Double(left), FunctionValue::Double(right)) => {
+                tolerance.containers_close(left, right)
+            }
 
             (FunctionValue::String(left), FunctionValue::String(right)) => left == right,
             (FunctionValue::Int(left), FunctionValue::Int(right)) => left == right,
             (FunctionValue::Long(left), FunctionValue::Long(right)) => left == right,
             (FunctionValue::Char(left), FunctionValue::Char(right)) => left == right,
             (FunctionValue::Bool(left), FunctionValue::Bool(right)) => left == right,
+            (FunctionValue::UInt(left), FunctionValue::UInt(right)) => left == right,
+            (FunctionValue::ULong(left), FunctionValue::ULong(right)) => left == right,
+            (FunctionValue::Short(left), FunctionValue::Short(right)) => left == right,
+            (FunctionValue::Byte(left), FunctionValue::Byte(right)) => left == right,
+            (FunctionValue::Int128(left), FunctionValue::Int128(right)) => left == right,
+            (FunctionValue::LinkedList(left), FunctionValue::LinkedList(right)) => left == right,
+            (FunctionValue::BinaryTree(left), FunctionValue::BinaryTree(right)) => left == right,
+
+            (FunctionValue::Struct(left_ty, left), FunctionValue::Struct(right_ty, right)) => {
+                left_ty == right_ty
+                    && containers_match(left, right, |l, r| values_match(l, r, tolerance))
+            }
+
+            // sets and maps are compared regardless of the order of their elements
+            (FunctionValue::Set(left_ty, left), FunctionValue::Set(right_ty, right)) => {
+                left_ty == right_ty
+                    && containers_match(left, right, |l, r| {
+                        values_match(
+                            &canonical_set(l.clone()),
+                            &canonical_set(r.clone()),
+                            tolerance,
+                        )
+                    })
+            }
+            (FunctionValue::Map(left_ty, left), FunctionValue::Map(right_ty, right)) => {
+                left_ty == right_ty
+                    && containers_match(left, right, |l, r| {
+                        let (l, r) = (canonical_map(l.clone()), canonical_map(r.clone()));
+
+                        l.len() == r.len()
+                            && l.iter().zip(&r).all(|((lk, lv), (rk, rv))| {
+                                lk.matches(rk, tolerance) && lv.matches(rv, tolerance)
+                            })
+                    })
+            }
 
             _ => false,
         }
     }
+
+    /// A total order over values of the same type, which is used to sort lists of structs by
+    /// their fields, and the keys of sets and maps.
+    pub(crate) fn total_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (FunctionValue::String(l), FunctionValue::String(r)) => {
+                containers_cmp(l, r, String::cmp)
+            }
+            (FunctionValue::Int(l), FunctionValue::Int(r)) => containers_cmp(l, r, i32::cmp),
+            (FunctionValue::Long(l), FunctionValue::Long(r)) => containers_cmp(l, r, i64::cmp),
+            (FunctionValue::Float(l), FunctionValue::Float(r)) => {
+                containers_cmp(l, r, f32::total_cmp)
+            }
+            (FunctionValue::Double(l), FunctionValue::Double(r)) => {
+                containers_cmp(l, r, f64::total_cmp)
+            }
+            (FunctionValue::Char(l), FunctionValue::Char(r)) => containers_cmp(l, r, char::cmp),
+            (FunctionValue::Bool(l), FunctionValue::Bool(r)) => containers_cmp(l, r, bool::cmp),
+            (FunctionValue::UInt(l), FunctionValue::UInt(r)) => containers_cmp(l, r, u32::cmp),
+            (FunctionValue::ULong(l), FunctionValue::ULong(r)) => containers_cmp(l, r, u64::cmp),
+            (FunctionValue::Short(l), FunctionValue::Short(r)) => containers_cmp(l, r, i16::cmp),
+            (FunctionValue::Byte(l), FunctionValue::Byte(r)) => containers_cmp(l, r, u8::cmp),
+            (FunctionValue::Int128(l), FunctionValue::Int128(r)) => containers_cmp(l, r, i128::cmp),
+            (FunctionValue::LinkedList(l), FunctionValue::LinkedList(r)) => {
+                containers_cmp(l, r, LinkedList::cmp)
+            }
+            (FunctionValue::BinaryTree(l), FunctionValue::BinaryTree(r)) => {
+                containers_cmp(l, r, BinaryTree::cmp)
+            }
+            (FunctionValue::Struct(_, l), FunctionValue::Struct(_, r))
+            | (FunctionValue::Set(_, l), FunctionValue::Set(_, r)) => {
+                containers_cmp(l, r, |l, r| fields_cmp(l, r))
+            }
+            (FunctionValue::Map(_, l), FunctionValue::Map(_, r)) => {
+                containers_cmp(l, r, |l, r| entries_cmp(l, r))
+            }
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+fn values_match(
+    left: &[FunctionValue],
+    right: &[FunctionValue],
+    tolerance: &FloatTolerance,
+) -> bool {
+    left.len() == right.len() && left.iter().zip(right).all(|(l, r)| l.matches(r, tolerance))
+}
+
+fn fields_cmp(left: &[FunctionValue], right: &[FunctionValue]) -> Ordering {
+    lexicographic_cmp(left, right, FunctionValue::total_cmp)
+}
+
+fn entries_cmp(
+    left: &[(FunctionValue, FunctionValue)],
+    right: &[(FunctionValue, FunctionValue)],
+) -> Ordering {
+    lexicographic_cmp(left, right, |(lk, lv), (rk, rv)| {
+        lk.total_cmp(rk).then_with(|| lv.total_cmp(rv))
+    })
+}
+
+/// Which orderings of a returned container are accepted.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, sqlx::Type)]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonMode {
+    /// The output must be in the same order as the expected output.
+    #[default]
+    Ordered,
+
+    /// The elements of each row of a grid or graph may be in any order, but the rows must be in
+    /// the same order, e.g. `[[2, 1], [3]]` matches `[[1, 2], [3]]`.
+    UnorderedRows,
+
+    /// The elements of a list, or the rows of a grid or graph, may be in any order, but each row
+    /// must be ordered, e.g. `[[3], [1, 2]]` matches `[[1, 2], [3]]`. A list is compared as a
+    /// multiset.
+    UnorderedList,
+
+    /// Both the rows and the elements of each row may be in any order.
+    Unordered,
+}
+
+impl ComparisonMode {
+    fn rows_unordered(self) -> bool {
+        matches!(
+            self,
+            ComparisonMode::UnorderedRows | ComparisonMode::Unordered
+        )
+    }
+
+    fn list_unordered(self) -> bool {
+        matches!(
+            self,
+            ComparisonMode::UnorderedList | ComparisonMode::Unordered
+        )
+    }
+}
+
+impl<T> ContainerVariant<T> {
+    /// Sorts the parts of the container which `mode` considers unordered with `cmp`. Vectors of
+    /// single elements nested in another container are rows, and every other vector is a list.
+    fn sorted(self, mode: ComparisonMode, cmp: impl Fn(&T, &T) -> Ordering + Copy) -> Self {
+        self.sorted_in(mode, cmp, false)
+    }
+
+    fn sorted_in(
+        self,
+        mode: ComparisonMode,
+        cmp: impl Fn(&T, &T) -> Ordering + Copy,
+        nested: bool,
+    ) -> Self {
+        match self {
+            ContainerVariant::Graph(graph) => {
+                let grid = ContainerVariant::grid(graph).sorted_in(mode, cmp, nested);
+                ContainerVariant::Graph(grid.into_rows().expect("sorting keeps the shape"))
+            }
+            ContainerVariant::Vector(ty, elements) => {
+                let mut elements = elements
+                    .into_iter()
+                    .map(|element| element.sorted_in(mode, cmp, true))
+                    .collect::<Vec<_>>();
+
+                let unordered = match *ty {
+                    ContainerVariantType::Single if nested => mode.rows_unordered(),
+                    _ => mode.list_unordered(),
+                };
+                if unordered {
+                    elements.sort_by(|left, right| containers_cmp(left, right, cmp));
+                }
+
+                ContainerVariant::Vector(ty, elements)
+            }
+            container => container,
+        }
+    }
+}
+
+impl FunctionValue {
+    /// Sorts the value so that outputs accepted by `mode` become equal, e.g. with
+    /// [`ComparisonMode::UnorderedList`], a list is sorted.
+    pub fn sorted(self, mode: ComparisonMode) -> FunctionValue {
+        match self {
+            FunctionValue::String(v) => FunctionValue::String(v.sorted(mode, String::cmp)),
+            FunctionValue::Int(v) => FunctionValue::Int(v.sorted(mode, i32::cmp)),
+            FunctionValue::Long(v) => FunctionValue::Long(v.sorted(mode, i64::cmp)),
+            FunctionValue::Float(v) => FunctionValue::Float(v.sorted(mode, f32::total_cmp)),
+            FunctionValue::Double(v) => FunctionValue::Double(v.sorted(mode, f64::total_cmp)),
+            FunctionValue::Char(v) => FunctionValue::Char(v.sorted(mode, char::cmp)),
+            FunctionValue::Bool(v) => FunctionValue::Bool(v.sorted(mode, bool::cmp)),
+            FunctionValue::UInt(v) => FunctionValue::UInt(v.sorted(mode, u32::cmp)),
+            FunctionValue::ULong(v) => FunctionValue::ULong(v.sorted(mode, u64::cmp)),
+            FunctionValue::Short(v) => FunctionValue::Short(v.sorted(mode, i16::cmp)),
+            FunctionValue::Byte(v) => FunctionValue::Byte(v.sorted(mode, u8::cmp)),
+            FunctionValue::Int128(v) => FunctionValue::Int128(v.sorted(mode, i128::cmp)),
+            FunctionValue::LinkedList(v) => {
+                FunctionValue::LinkedList(v.sorted(mode, LinkedList::cmp))
+            }
+            FunctionValue::BinaryTree(v) => {
+                FunctionValue::BinaryTree(v.sorted(mode, BinaryTree::cmp))
+            }
+            FunctionValue::Struct(ty, v) => {
+                FunctionValue::Struct(ty, v.sorted(mode, |l, r| fields_cmp(l, r)))
+            }
+
+            // the elements of sets and maps are sorted first, so equal sets are sorted together
+            FunctionValue::Set(ty, v) => FunctionValue::Set(
+                ty,
+                v.map(canonical_set).sorted(mode, |l, r| fields_cmp(l, r)),
+            ),
+            FunctionValue::Map(ty, v) => FunctionValue::Map(
+                ty,
+                v.map(canonical_map).sorted(mode, |l, r| entries_cmp(l, r)),
+            ),
+        }
+    }
+}
+
+/// Compares floating point values with the default [`FloatTolerance`], because there can be
+/// annoying precision issues when solving problems otherwise.
+impl PartialEq for FunctionValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.matches(other, &FloatTolerance::default())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -146,110 +518,261 @@ pub enum FunctionType {
     Double(ContainerVariantType),
     Char(ContainerVariantType),
     Bool(ContainerVariantType),
+    UInt(ContainerVariantType),
+    ULong(ContainerVariantType),
+    Short(ContainerVariantType),
+    Byte(ContainerVariantType),
+    Int128(ContainerVariantType),
+    Struct(Box<StructType>, ContainerVariantType),
+    Set(Box<SetType>, ContainerVariantType),
+    Map(Box<MapType>, ContainerVariantType),
+    LinkedList(ContainerVariantType),
+    BinaryTree(ContainerVariantType),
 }
 
 impl FunctionType {
-    fn from_memory<S>(
+    pub fn container(&self) -> &ContainerVariantType {
+        match self {
+            FunctionType::String(v)
+            | FunctionType::Int(v)
+            | FunctionType::Long(v)
+            | FunctionType::Float(v)
+            | FunctionType::Double(v)
+            | FunctionType::Char(v)
+            | FunctionType::Bool(v)
+            | FunctionType::UInt(v)
+            | FunctionType::ULong(v)
+            | FunctionType::Short(v)
+            | FunctionType::Byte(v)
+            | FunctionType::Int128(v)
+            | FunctionType::Struct(_, v)
+            | FunctionType::Set(_, v)
+            | FunctionType::Map(_, v)
+            | FunctionType::LinkedList(v)
+            | FunctionType::BinaryTree(v) => v,
+        }
+    }
+
+    pub(crate) fn container_mut(&mut self) -> &mut ContainerVariantType {
+        match self {
+            FunctionType::String(v)
+            | FunctionType::Int(v)
+            | FunctionType::Long(v)
+            | FunctionType::Float(v)
+            | FunctionType::Double(v)
+            | FunctionType::Char(v)
+            | FunctionType::Bool(v)
+            | FunctionType::UInt(v)
+            | FunctionType::ULong(v)
+            | FunctionType::Short(v)
+            | FunctionType::Byte(v)
+            | FunctionType::Int128(v)
+            | FunctionType::Struct(_, v)
+            | FunctionType::Set(_, v)
+            | FunctionType::Map(_, v)
+            | FunctionType::LinkedList(v)
+            | FunctionType::BinaryTree(v) => v,
+        }
+    }
+
+    /// The size and alignment of this type when it's stored inline, e.g. as the field of a struct
+    pub(crate) fn cpp_layout(&self) -> (usize, usize) {
+        match self {
+            FunctionType::Int(ContainerVariantType::Single) => {
+                (i32::CPP_SIZE_OF, i32::CPP_ALIGN_OF)
+            }
+            FunctionType::Long(ContainerVariantType::Single) => {
+                (i64::CPP_SIZE_OF, i64::CPP_ALIGN_OF)
+            }
+            FunctionType::Float(ContainerVariantType::Single) => {
+                (f32::CPP_SIZE_OF, f32::CPP_ALIGN_OF)
+            }
+            FunctionType::Double(ContainerVariantType::Single) => {
+                (f64::CPP_SIZE_OF, f64::CPP_ALIGN_OF)
+            }
+            FunctionType::Char(ContainerVariantType::Single) => {
+                (char::CPP_SIZE_OF, char::CPP_ALIGN_OF)
+            }
+            FunctionType::Bool(ContainerVariantType::Single) => {
+                (bool::CPP_SIZE_OF, bool::CPP_ALIGN_OF)
+            }
+            FunctionType::UInt(ContainerVariantType::Single) => {
+                (u32::CPP_SIZE_OF, u32::CPP_ALIGN_OF)
+            }
+            FunctionType::ULong(ContainerVariantType::Single) => {
+                (u64::CPP_SIZE_OF, u64::CPP_ALIGN_OF)
+            }
+            FunctionType::Short(ContainerVariantType::Single) => {
+                (i16::CPP_SIZE_OF, i16::CPP_ALIGN_OF)
+            }
+            FunctionType::Byte(ContainerVariantType::Single) => (u8::CPP_SIZE_OF, u8::CPP_ALIGN_OF),
+            FunctionType::Int128(ContainerVariantType::Single) => {
+                (i128::CPP_SIZE_OF, i128::CPP_ALIGN_OF)
+            }
+            FunctionType::Struct(ty, ContainerVariantType::Single) => {
+                let layout = ty.layout();
+                (layout.size, layout.align)
+            }
+            FunctionType::String(ContainerVariantType::Single) => {
+                (String::CPP_SIZE_OF, String::CPP_ALIGN_OF)
+            }
+            FunctionType::Set(ty, ContainerVariantType::Single) => ty.cpp_layout(),
+            FunctionType::Map(ty, ContainerVariantType::Single) => ty.cpp_layout(),
+            FunctionType::LinkedList(ContainerVariantType::Single) => {
+                (LinkedList::CPP_SIZE_OF, LinkedList::CPP_ALIGN_OF)
+            }
+            FunctionType::BinaryTree(ContainerVariantType::Single) => {
+                (BinaryTree::CPP_SIZE_OF, BinaryTree::CPP_ALIGN_OF)
+            }
+
+            // every other container is a `std::vector`
+            _ => (Vec::<i32>::CPP_SIZE_OF, Vec::<i32>::CPP_ALIGN_OF),
+        }
+    }
+
+    /// Reads a value of this type stored at `offset`, the way C++ lays it out.
+    pub(crate) fn read_from_memory<S>(
         &self,
         store: &mut Store<S>,
         memory: &Memory,
         offset: usize,
     ) -> Result<FunctionValue> {
         let res = match self {
-            FunctionType::String(ContainerVariantType::Graph) => FunctionValue::String(
-                ContainerVariant::Graph(Vec::<Vec<String>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::String(ContainerVariantType::Grid) => FunctionValue::String(
-                ContainerVariant::Grid(Vec::<Vec<String>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::String(ContainerVariantType::List) => FunctionValue::String(
-                ContainerVariant::List(Vec::<String>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::String(ContainerVariantType::Single) => FunctionValue::String(
-                ContainerVariant::Single(String::from_memory(store, memory, offset)?),
-            ),
+            FunctionType::String(v) => {
+                FunctionValue::String(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+            FunctionType::Int(v) => {
+                FunctionValue::Int(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+            FunctionType::Long(v) => {
+                FunctionValue::Long(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+            FunctionType::Float(v) => {
+                FunctionValue::Float(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+            FunctionType::Double(v) => {
+                FunctionValue::Double(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+            FunctionType::Char(v) => {
+                FunctionValue::Char(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+            FunctionType::Bool(v) => {
+                FunctionValue::Bool(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+            FunctionType::UInt(v) => {
+                FunctionValue::UInt(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+            FunctionType::ULong(v) => {
+                FunctionValue::ULong(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+            FunctionType::Short(v) => {
+                FunctionValue::Short(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+            FunctionType::Byte(v) => {
+                FunctionValue::Byte(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+            FunctionType::Int128(v) => {
+                FunctionValue::Int128(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+            FunctionType::Struct(ty, v) => {
+                FunctionValue::Struct(ty.clone(), ty.read(v, store, memory, offset)?)
+            }
+            FunctionType::Set(ty, v) => {
+                FunctionValue::Set(ty.clone(), ty.read(v, store, memory, offset)?)
+            }
+            FunctionType::Map(ty, v) => {
+                FunctionValue::Map(ty.clone(), ty.read(v, store, memory, offset)?)
+            }
+            FunctionType::LinkedList(v) => {
+                FunctionValue::LinkedList(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+            FunctionType::BinaryTree(v) => {
+                FunctionValue::BinaryTree(ContainerVariant::from_memory(v, store, memory, offset)?)
+            }
+        };
 
-            FunctionType::Int(ContainerVariantType::Graph) => FunctionValue::Int(
-                ContainerVariant::Graph(Vec::<Vec<i32>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Int(ContainerVariantType::Grid) => FunctionValue::Int(
-                ContainerVariant::Grid(Vec::<Vec<i32>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Int(ContainerVariantType::List) => FunctionValue::Int(
-                ContainerVariant::List(Vec::<i32>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Int(ContainerVariantType::Single) => FunctionValue::Int(
-                ContainerVariant::Single(i32::from_memory(store, memory, offset)?),
-            ),
+        Ok(res)
+    }
 
-            FunctionType::Long(ContainerVariantType::Graph) => FunctionValue::Long(
-                ContainerVariant::Graph(Vec::<Vec<i64>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Long(ContainerVariantType::Grid) => FunctionValue::Long(
-                ContainerVariant::Grid(Vec::<Vec<i64>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Long(ContainerVariantType::List) => FunctionValue::Long(
-                ContainerVariant::List(Vec::<i64>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Long(ContainerVariantType::Single) => FunctionValue::Long(
-                ContainerVariant::Single(i64::from_memory(store, memory, offset)?),
-            ),
+    /// Reads a value of this type returned from a C function, see
+    /// [`ContainerVariant::from_c_memory`].
+    fn read_c_return<S>(
+        &self,
+        store: &mut Store<S>,
+        memory: &Memory,
+        pointer: usize,
+        out_params: &[usize],
+    ) -> Result<FunctionValue> {
+        let res = match self {
+            FunctionType::String(v) => FunctionValue::String(ContainerVariant::from_c_memory(
+                v, store, memory, pointer, out_params,
+            )?),
+            FunctionType::Int(v) => FunctionValue::Int(ContainerVariant::from_c_memory(
+                v, store, memory, pointer, out_params,
+            )?),
+            FunctionType::Long(v) => FunctionValue::Long(ContainerVariant::from_c_memory(
+                v, store, memory, pointer, out_params,
+            )?),
+            FunctionType::Float(v) => FunctionValue::Float(ContainerVariant::from_c_memory(
+                v, store, memory, pointer, out_params,
+            )?),
+            FunctionType::Double(v) => FunctionValue::Double(ContainerVariant::from_c_memory(
+                v, store, memory, pointer, out_params,
+            )?),
+            FunctionType::Char(v) => FunctionValue::Char(ContainerVariant::from_c_memory(
+                v, store, memory, pointer, out_params,
+            )?),
+            FunctionType::Bool(v) => FunctionValue::Bool(ContainerVariant::from_c_memory(
+                v, store, memory, pointer, out_params,
+            )?),
+            FunctionType::UInt(v) => FunctionValue::UInt(ContainerVariant::from_c_memory(
+                v, store, memory, pointer, out_params,
+            )?),
+            FunctionType::ULong(v) => FunctionValue::ULong(ContainerVariant::from_c_memory(
+                v, store, memory, pointer, out_params,
+            )?),
+            FunctionType::Short(v) => FunctionValue::Short(ContainerVariant::from_c_memory(
+                v, store, memory, pointer, out_params,
+            )?),
+            FunctionType::Byte(v) => FunctionValue::Byte(ContainerVariant::from_c_memory(
+                v, store, memory, pointer, out_params,
+            )?),
+            FunctionType::Int128(v) => FunctionValue::Int128(ContainerVariant::from_c_memory(
+                v, store, memory, pointer, out_params,
+            )?),
+            FunctionType::Struct(..) | FunctionType::Set(..) | FunctionType::Map(..) => {
+                bail!(UNSUPPORTED_IN_C)
+            }
+            FunctionType::LinkedList(_) | FunctionType::BinaryTree(_) => {
+                bail!(NODES_UNSUPPORTED_IN_C)
+            }
+        };
 
-            FunctionType::Float(ContainerVariantType::Graph) => FunctionValue::Float(
-                ContainerVariant::Graph(Vec::<Vec<f32>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Float(ContainerVariantType::Grid) => FunctionValue::Float(
-                ContainerVariant::Grid(Vec::<Vec<f32>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Float(ContainerVariantType::List) => FunctionValue::Float(
-                ContainerVariant::List(Vec::<f32>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Float(ContainerVariantType::Single) => FunctionValue::Float(
-                ContainerVariant::Single(f32::from_memory(store, memory, offset)?),
-            ),
+        Ok(res)
+    }
 
-            FunctionType::Double(ContainerVariantType::Graph) => FunctionValue::Double(
-                ContainerVariant::Graph(Vec::<Vec<f64>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Double(ContainerVariantType::Grid) => FunctionValue::Double(
-                ContainerVariant::Grid(Vec::<Vec<f64>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Double(ContainerVariantType::List) => FunctionValue::Double(
-                ContainerVariant::List(Vec::<f64>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Double(ContainerVariantType::Single) => FunctionValue::Double(
-                ContainerVariant::Single(f64::from_memory(store, memory, offset)?),
-            ),
+    /// The number of size out parameters (e.g. `int* returnSize`) a C function returning this
+    /// type takes.
+    fn c_out_params(&self) -> usize {
+        // vectors of grids are rejected before the call
+        self.container().dimensions()
+    }
+}
 
-            FunctionType::Char(ContainerVariantType::Graph) => FunctionValue::Char(
-                ContainerVariant::Graph(Vec::<Vec<char>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Char(ContainerVariantType::Grid) => FunctionValue::Char(
-                ContainerVariant::Grid(Vec::<Vec<char>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Char(ContainerVariantType::List) => FunctionValue::Char(
-                ContainerVariant::List(Vec::<char>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Char(ContainerVariantType::Single) => FunctionValue::Char(
-                ContainerVariant::Single(char::from_memory(store, memory, offset)?),
-            ),
+const UNSUPPORTED_IN_C: &str =
+    "Pairs, tuples, structs, sets and maps can't be passed to or returned from C";
 
-            FunctionType::Bool(ContainerVariantType::Graph) => FunctionValue::Bool(
-                ContainerVariant::Graph(Vec::<Vec<bool>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Bool(ContainerVariantType::Grid) => FunctionValue::Bool(
-                ContainerVariant::Grid(Vec::<Vec<bool>>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Bool(ContainerVariantType::List) => FunctionValue::Bool(
-                ContainerVariant::List(Vec::<bool>::from_memory(store, memory, offset)?),
-            ),
-            FunctionType::Bool(ContainerVariantType::Single) => FunctionValue::Bool(
-                ContainerVariant::Single(bool::from_memory(store, memory, offset)?),
-            ),
-        };
+const NODES_UNSUPPORTED_IN_C: &str =
+    "Only single `ListNode*`s and `TreeNode*`s can be passed to or returned from C";
 
-        Ok(res)
-    }
+pub(crate) const VECTORS_UNSUPPORTED_IN_C: &str =
+    "Vectors of grids and other nested vectors can't be passed to or returned from C";
+
+fn passed_directly(ty: &StructType) -> String {
+    format!(
+        "`{}` only contains a number, so it's passed as that number, which isn't supported",
+        ty.name()
+    )
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -270,13 +793,65 @@ impl WasmFunctionCall {
         }
     }
 
+    /// The input of a test for a problem judged through stdin/stdout, which is the entire stdin
+    /// given to the program's `main`. Its expected output is a single string.
+    pub fn stdio(stdin: impl Into<String>) -> Self {
+        WasmFunctionCall::new(
+            "main",
+            vec![FunctionValue::String(ContainerVariant::Single(
+                stdin.into(),
+            ))],
+            FunctionType::String(ContainerVariantType::Single),
+        )
+    }
+
+    /// Returns the stdin of a call created by [`WasmFunctionCall::stdio`].
+    pub fn stdin(&self) -> Option<&str> {
+        match self.arguments.as_slice() {
+            [FunctionValue::String(ContainerVariant::Single(stdin))] => Some(stdin),
+            _ => None,
+        }
+    }
+
     // Returns the return value of the function, along with the fuel consumed *purely* by the
     // invocation of that funcion, not the memory allocation of passing the arguments.
     pub fn call<S>(
         self,
-        mut store: &mut Store<S>,
+        store: &mut Store<S>,
         instance: &Instance,
+        abi: Abi,
     ) -> Result<(FunctionValue, u64)> {
+        self.call_with(store, instance, abi, |_| {})
+    }
+
+    /// Same as [`WasmFunctionCall::call`], but runs `before_call` once the arguments are in
+    /// memory, right before the function is called, e.g. to measure memory from there on.
+    pub fn call_with<S>(
+        self,
+        store: &mut Store<S>,
+        instance: &Instance,
+        abi: Abi,
+        before_call: impl FnOnce(&mut Store<S>),
+    ) -> Result<(FunctionValue, u64)> {
+        let (return_value, fuel) = self.invoke(store, instance, abi, true, before_call)?;
+        Ok((return_value.expect("non-void calls return a value"), fuel))
+    }
+
+    /// Calls a function returning `void`, ignoring the return type of the call. Returns the fuel
+    /// consumed the same way as [`WasmFunctionCall::call`].
+    pub fn call_void<S>(self, store: &mut Store<S>, instance: &Instance, abi: Abi) -> Result<u64> {
+        let (_, fuel) = self.invoke(store, instance, abi, false, |_| {})?;
+        Ok(fuel)
+    }
+
+    fn invoke<S>(
+        self,
+        mut store: &mut Store<S>,
+        instance: &Instance,
+        abi: Abi,
+        returns: bool,
+        before_call: impl FnOnce(&mut Store<S>),
+    ) -> Result<(Option<FunctionValue>, u64)> {
         let allocator: AllocatorFunc = instance
             .get_typed_func(&mut store, "malloc")
             .expect("Failed to get allocator");
@@ -292,16 +867,28 @@ impl WasmFunctionCall {
         // If the return value is not a simple singleton, we instead put the address for the
         // return value to be written to as the first argument
         match self.return_type {
+            _ if !returns => {}
+
+            // nodes are returned as pointers to them
             FunctionType::Int(ContainerVariantType::Single)
             | FunctionType::Char(ContainerVariantType::Single)
-            | FunctionType::Bool(ContainerVariantType::Single) => {
+            | FunctionType::Bool(ContainerVariantType::Single)
+            | FunctionType::UInt(ContainerVariantType::Single)
+            | FunctionType::Short(ContainerVariantType::Single)
+            | FunctionType::Byte(ContainerVariantType::Single)
+            | FunctionType::LinkedList(ContainerVariantType::Single)
+            | FunctionType::BinaryTree(ContainerVariantType::Single) => {
                 results.push(Val::I32(0));
             }
 
-            FunctionType::Long(ContainerVariantType::Single) => {
+            FunctionType::Long(ContainerVariantType::Single)
+            | FunctionType::ULong(ContainerVariantType::Single) => {
                 results.push(Val::I64(0));
             }
 
+            // `__int128`s are too large to return directly in any language
+            FunctionType::Int128(ContainerVariantType::Single) => params.push(Val::I32(0)),
+
             FunctionType::Float(ContainerVariantType::Single) => {
                 results.push(Val::F32(0));
             }
@@ -310,6 +897,21 @@ impl WasmFunctionCall {
                 results.push(Val::F64(0));
             }
 
+            FunctionType::Struct(..) | FunctionType::Set(..) | FunctionType::Map(..)
+                if abi == Abi::C =>
+            {
+                bail!(UNSUPPORTED_IN_C)
+            }
+            _ if abi == Abi::C && self.return_type.container().dimensions() > 2 => {
+                bail!(VECTORS_UNSUPPORTED_IN_C)
+            }
+            FunctionType::Struct(ref ty, ContainerVariantType::Single) if ty.passed_directly() => {
+                bail!(passed_directly(ty))
+            }
+
+            // C functions return a pointer to the result instead
+            _ if abi == Abi::C => results.push(Val::I32(0)),
+
             _ => params.push(Val::I32(0 as i32)),
         }
 
@@ -318,8 +920,17 @@ impl WasmFunctionCall {
         store.add_fuel(ARG_ALLOC_FUEL_DEFAULT)?;
         let start_fuel = store.fuel_consumed().unwrap_or(0);
 
-        let init = instance.get_typed_func(&mut store, "_initialize")?;
-        init.call(&mut store, ())?;
+        // Reactor modules built by clang export `_initialize` to run static constructors, but not
+        // every toolchain emits one (e.g. rustc cdylibs), so it is optional.
+        if let Ok(init) = instance.get_typed_func::<(), ()>(&mut store, "_initialize") {
+            init.call(&mut store, ())?;
+        }
+
+        let argument_types = self
+            .arguments
+            .iter()
+            .map(FunctionValue::function_type)
+            .collect::<Vec<_>>();
 
         for arg in self.arguments {
             match arg {
@@ -333,57 +944,77 @@ impl WasmFunctionCall {
                 }
                 FunctionValue::Char(ContainerVariant::Single(c)) => params.push(Val::I32(c as i32)),
                 FunctionValue::Bool(ContainerVariant::Single(b)) => params.push(Val::I32(b as i32)),
-
-                _ => {
-                    let address = arg.into_memory(store, &memory, &allocator)?;
-                    params.push(Val::I32(address as i32));
+                FunctionValue::UInt(ContainerVariant::Single(u)) => params.push(Val::I32(u as i32)),
+                FunctionValue::ULong(ContainerVariant::Single(u)) => {
+                    params.push(Val::I64(u as i64))
+                }
+                FunctionValue::Short(ContainerVariant::Single(s)) => {
+                    params.push(Val::I32(s as i32))
+                }
+                FunctionValue::Byte(ContainerVariant::Single(b)) => params.push(Val::I32(b as i32)),
+                FunctionValue::Int128(ContainerVariant::Single(i)) => {
+                    params.push(Val::I64(i as i64));
+                    params.push(Val::I64((i >> 64) as i64));
+                }
+                FunctionValue::Struct(ty, ContainerVariant::Single(_)) if ty.passed_directly() => {
+                    bail!(passed_directly(&ty))
+                }
+                FunctionValue::LinkedList(ContainerVariant::Single(list)) => {
+                    let head = list.write_nodes(store, &memory, &allocator)?;
+                    params.push(Val::I32(head as i32));
                 }
+                FunctionValue::BinaryTree(ContainerVariant::Single(tree)) => {
+                    let root = tree.write_nodes(store, &memory, &allocator)?;
+                    params.push(Val::I32(root as i32));
+                }
+
+                _ => match abi {
+                    Abi::CPlusPlus => {
+                        let address = arg.into_memory(store, &memory, &allocator, None)?;
+                        params.push(Val::I32(address as i32));
+                    }
+                    Abi::C => params.extend(arg.into_c_params(store, &memory, &allocator)?),
+                },
             }
         }
 
-        let consumed_for_args = store.fuel_consumed().unwrap_or(0) - start_fuel;
-        let initial_fuel = store.consume_fuel(ARG_ALLOC_FUEL_DEFAULT - consumed_for_args)?;
-
+        // C functions returning arrays write their sizes to trailing out parameters, e.g.
+        // `int* returnSize, int** returnColumnSizes`
         let mut out_params = vec![];
-        match self.return_type {
-            FunctionType::Int(ContainerVariantType::Single) => out_params.push(ValType::I32),
-            FunctionType::Long(ContainerVariantType::Single) => out_params.push(ValType::I64),
-            FunctionType::Float(ContainerVariantType::Single) => out_params.push(ValType::F32),
-            FunctionType::Double(ContainerVariantType::Single) => out_params.push(ValType::F64),
-            FunctionType::Char(ContainerVariantType::Single) => out_params.push(ValType::I32),
-            FunctionType::Bool(ContainerVariantType::Single) => out_params.push(ValType::I32),
-            _ => {}
-        }
+        if abi == Abi::C && returns {
+            for _ in 0..self.return_type.c_out_params() {
+                let address = allocator.call(&mut store, 4)? as usize;
+                memory.write(&mut store, address, &[0; 4])?;
 
-        // find name, should match in mangled string
-        let externs = instance
-            .exports(&mut store)
-            .filter(|e| e.name().contains(&self.name))
-            .map(Export::into_extern)
-            .collect::<Vec<_>>();
+                params.push(Val::I32(address as i32));
+                out_params.push(address);
+            }
+        }
 
-        let mut found_func = false;
+        let consumed_for_args = store.fuel_consumed().unwrap_or(0) - start_fuel;
+        let initial_fuel = store.consume_fuel(ARG_ALLOC_FUEL_DEFAULT - consumed_for_args)?;
 
-        for ext in &externs {
-            if let Extern::Func(func) = ext {
-                let ty = func.ty(&store);
+        let result_types = results.iter().map(Val::ty).collect::<Vec<_>>();
 
-                if ty.params().eq(params.iter().map(Val::ty))
-                    && ty.results().eq(out_params.iter().cloned())
-                {
-                    func.call(&mut store, &params, &mut results)?;
-                    found_func = true;
-                    break;
-                }
-            }
-        }
+        let param_types = params.iter().map(Val::ty).collect::<Vec<_>>();
+        let func = find_function(
+            store,
+            instance,
+            &self.name,
+            &argument_types,
+            &param_types,
+            &result_types,
+        )?;
 
-        if !found_func {
-            return Err(FunctionError::NameNotFound(self.name.clone()).into());
-        }
+        before_call(store);
+        func.call(&mut store, &params, &mut results)?;
 
         let remaining_fuel = store.consume_fuel(0)?;
 
+        if !returns {
+            return Ok((None, initial_fuel - remaining_fuel));
+        }
+
         // If the return type is a simple singleton, we can simply take the value directly from the
         // return value. Otherwise, we must read it from memory, with the address given by the
         // first parameter.
@@ -412,11 +1043,245 @@ impl WasmFunctionCall {
                 FunctionValue::Double(ContainerVariant::Single(results[0].unwrap_f64()))
             }
 
-            _ => self
-                .return_type
-                .from_memory(store, &memory, params[0].unwrap_i32() as usize)?,
+            FunctionType::UInt(ContainerVariantType::Single) => {
+                FunctionValue::UInt(ContainerVariant::Single(results[0].unwrap_i32() as u32))
+            }
+
+            FunctionType::ULong(ContainerVariantType::Single) => {
+                FunctionValue::ULong(ContainerVariant::Single(results[0].unwrap_i64() as u64))
+            }
+
+            FunctionType::Short(ContainerVariantType::Single) => {
+                FunctionValue::Short(ContainerVariant::Single(results[0].unwrap_i32() as i16))
+            }
+
+            FunctionType::Byte(ContainerVariantType::Single) => {
+                FunctionValue::Byte(ContainerVariant::Single(results[0].unwrap_i32() as u8))
+            }
+
+            FunctionType::Int128(ContainerVariantType::Single) => {
+                FunctionValue::Int128(ContainerVariant::Single(i128::from_memory(
+                    store,
+                    &memory,
+                    params[0].unwrap_i32() as usize,
+                )?))
+            }
+
+            FunctionType::LinkedList(ContainerVariantType::Single) => {
+                FunctionValue::LinkedList(ContainerVariant::Single(LinkedList::read_nodes(
+                    store,
+                    &memory,
+                    results[0].unwrap_i32() as usize,
+                )?))
+            }
+
+            FunctionType::BinaryTree(ContainerVariantType::Single) => {
+                FunctionValue::BinaryTree(ContainerVariant::Single(BinaryTree::read_nodes(
+                    store,
+                    &memory,
+                    results[0].unwrap_i32() as usize,
+                )?))
+            }
+
+            // A returned string is the `char*` itself
+            FunctionType::String(ContainerVariantType::Single) if abi == Abi::C => {
+                FunctionValue::String(ContainerVariant::Single(c_string_from_memory(
+                    store,
+                    &memory,
+                    results[0].unwrap_i32() as usize,
+                )?))
+            }
+
+            _ if abi == Abi::C => self.return_type.read_c_return(
+                store,
+                &memory,
+                results[0].unwrap_i32() as usize,
+                &out_params,
+            )?,
+
+            _ => self.return_type.read_from_memory(
+                store,
+                &memory,
+                params[0].unwrap_i32() as usize,
+            )?,
+        };
+
+        Ok((Some(return_value), initial_fuel - remaining_fuel))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn before_call_runs_once_arguments_are_in_memory() -> Result<()> {
+        let engine = Engine::new(Config::new().consume_fuel(true))?;
+        let mut store = Store::new(&engine, ());
+        store.add_fuel(1_000_000)?;
+        let module = Module::new(
+            &engine,
+            r#"(module
+                (memory (export "memory") 1)
+                (global $heap (export "heap") (mut i32) (i32.const 1024))
+                (global $called (export "called") (mut i32) (i32.const 0))
+                (func (export "malloc") (param i32) (result i32)
+                    global.get $heap
+                    global.get $heap
+                    local.get 0
+                    i32.add
+                    global.set $heap)
+                (func (export "solve") (param i32) (result i32)
+                    i32.const 1
+                    global.set $called
+                    i32.const 7))"#,
+        )?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        let memory = instance.get_memory(&mut store, "memory").unwrap();
+
+        let call = WasmFunctionCall::new(
+            "solve",
+            vec![FunctionValue::Int(ContainerVariant::list(vec![1, 2, 3]))],
+            FunctionType::Int(ContainerVariantType::Single),
+        );
+
+        let mut seen = None;
+        let (result, _) = call.call_with(&mut store, &instance, Abi::CPlusPlus, |store| {
+            let global = |store: &mut Store<()>, name| {
+                instance
+                    .get_global(&mut *store, name)
+                    .unwrap()
+                    .get(store)
+                    .unwrap_i32()
+            };
+            seen = Some((
+                memory.size(&*store),
+                global(store, "heap"),
+                global(store, "called"),
+            ));
+        })?;
+
+        let (pages, heap, called) = seen.unwrap();
+        assert_eq!(pages, 1 + WasmFunctionCall::PAGE_OFFSET as u64);
+        assert!(heap > 1024);
+        assert_eq!(called, 0);
+        assert_eq!(result, FunctionValue::Int(ContainerVariant::Single(7)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn tolerance_applies_to_containers() {
+        let tolerance = FloatTolerance {
+            absolute: 1e-6,
+            relative: 0.0,
         };
 
-        Ok((return_value, initial_fuel - remaining_fuel))
+        let expected = FunctionValue::Double(ContainerVariant::grid(vec![vec![1.0, 2.0]]));
+        let close = FunctionValue::Double(ContainerVariant::grid(vec![vec![1.0, 2.0000001]]));
+        let far = FunctionValue::Double(ContainerVariant::grid(vec![vec![1.0, 2.001]]));
+        let short = FunctionValue::Double(ContainerVariant::grid(vec![vec![1.0]]));
+
+        assert!(expected.matches(&close, &tolerance));
+        assert!(!expected.matches(&far, &tolerance));
+        assert!(!expected.matches(&short, &tolerance));
+
+        let expected = FunctionValue::Float(ContainerVariant::list(vec![0.1, 0.2]));
+        let close = FunctionValue::Float(ContainerVariant::list(vec![0.1, 0.2000001]));
+
+        assert!(expected.matches(&close, &tolerance));
+    }
+
+    #[test]
+    fn unordered_comparisons() {
+        let expected = FunctionValue::Int(ContainerVariant::grid(vec![vec![1, 2], vec![3]]));
+        let rows_swapped = FunctionValue::Int(ContainerVariant::grid(vec![vec![3], vec![1, 2]]));
+        let row_reversed = FunctionValue::Int(ContainerVariant::grid(vec![vec![2, 1], vec![3]]));
+        let both = FunctionValue::Int(ContainerVariant::grid(vec![vec![3], vec![2, 1]]));
+
+        let matches = |output: &FunctionValue, mode| {
+            expected.clone().sorted(mode) == output.clone().sorted(mode)
+        };
+
+        assert!(!matches(&rows_swapped, ComparisonMode::Ordered));
+        assert!(matches(&rows_swapped, ComparisonMode::UnorderedList));
+        assert!(!matches(&rows_swapped, ComparisonMode::UnorderedRows));
+        assert!(matches(&row_reversed, ComparisonMode::UnorderedRows));
+        assert!(!matches(&row_reversed, ComparisonMode::UnorderedList));
+        assert!(matches(&both, ComparisonMode::Unordered));
+
+        let expected = FunctionValue::Int(ContainerVariant::list(vec![1, 1, 2]));
+        let permuted = FunctionValue::Int(ContainerVariant::list(vec![2, 1, 1]));
+        let different = FunctionValue::Int(ContainerVariant::list(vec![2, 2, 1]));
+
+        assert_eq!(
+            expected.clone().sorted(ComparisonMode::UnorderedList),
+            permuted.clone().sorted(ComparisonMode::UnorderedList)
+        );
+        assert_ne!(
+            expected.clone().sorted(ComparisonMode::UnorderedList),
+            different.sorted(ComparisonMode::UnorderedList)
+        );
+        assert_ne!(
+            expected.sorted(ComparisonMode::UnorderedRows),
+            permuted.sorted(ComparisonMode::UnorderedRows)
+        );
+    }
+
+    #[test]
+    fn unordered_pairs() {
+        let pair = Box::new(StructType {
+            kind: crate::StructKind::Pair,
+            fields: vec![
+                FunctionType::Int(ContainerVariantType::Single),
+                FunctionType::Double(ContainerVariantType::Single),
+            ],
+        });
+        let pairs = |values: &[(i32, f64)]| {
+            FunctionValue::Struct(
+                pair.clone(),
+                ContainerVariant::list(
+                    values
+                        .iter()
+                        .map(|(i, d)| {
+                            vec![
+                                FunctionValue::Int(ContainerVariant::Single(*i)),
+                                FunctionValue::Double(ContainerVariant::Single(*d)),
+                            ]
+                        })
+                        .collect(),
+                ),
+            )
+        };
+
+        let expected = pairs(&[(1, 0.5), (2, 0.25), (1, 0.1)]);
+        let permuted = pairs(&[(1, 0.1), (1, 0.5), (2, 0.25)]);
+        let close = pairs(&[(1, 0.5), (2, 0.2500000000001), (1, 0.1)]);
+
+        assert_ne!(expected, permuted);
+        assert_eq!(
+            expected.clone().sorted(ComparisonMode::UnorderedList),
+            permuted.clone().sorted(ComparisonMode::UnorderedList)
+        );
+        assert_eq!(expected, close);
+    }
+
+    #[test]
+    fn relative_tolerance() {
+        let tolerance = FloatTolerance {
+            absolute: 0.0,
+            relative: 1e-6,
+        };
+
+        let expected = FunctionValue::Double(ContainerVariant::Single(1e12));
+
+        assert!(expected.matches(
+            &FunctionValue::Double(ContainerVariant::Single(1e12 + 1e5)),
+            &tolerance
+        ));
+        assert!(!expected.matches(
+            &FunctionValue::Double(ContainerVariant::Single(1e12 + 1e7)),
+            &tolerance
+        ));
     }
 }
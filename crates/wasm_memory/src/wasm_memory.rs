@@ -4,8 +4,23 @@ use wasmtime::*;
 
 pub type AllocatorFunc = TypedFunc<i32, i32>;
 
+/// Rounds `offset` up to the next multiple of `align`, which is where C++ places a field with that
+/// alignment.
+pub const fn align_to(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+const fn max(a: usize, b: usize) -> usize {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
 pub trait WasmMemory: Sized {
     const CPP_SIZE_OF: usize;
+    const CPP_ALIGN_OF: usize;
 
     /// returns the address of the item allocated
     fn into_memory<S>(
@@ -30,6 +45,7 @@ where
     T: WasmMemory,
 {
     const CPP_SIZE_OF: usize = 12;
+    const CPP_ALIGN_OF: usize = 4;
 
     fn into_memory<S>(
         self,
@@ -99,6 +115,7 @@ where
 /// capacity (length) - u32
 impl WasmMemory for String {
     const CPP_SIZE_OF: usize = 12;
+    const CPP_ALIGN_OF: usize = 4;
 
     fn into_memory<S>(
         self,
@@ -171,13 +188,20 @@ impl WasmMemory for String {
     }
 }
 
-/// maps to `std::pair<A, B>`
+/// The offset of `second` in a `std::pair<A, B>`
+const fn second_offset<A: WasmMemory, B: WasmMemory>() -> usize {
+    align_to(A::CPP_SIZE_OF, B::CPP_ALIGN_OF)
+}
+
+/// maps to `std::pair<A, B>`, where `second` is padded to its alignment
 impl<A, B> WasmMemory for (A, B)
 where
     A: WasmMemory,
     B: WasmMemory,
 {
-    const CPP_SIZE_OF: usize = A::CPP_SIZE_OF + B::CPP_SIZE_OF;
+    const CPP_SIZE_OF: usize =
+        align_to(second_offset::<A, B>() + B::CPP_SIZE_OF, Self::CPP_ALIGN_OF);
+    const CPP_ALIGN_OF: usize = max(A::CPP_ALIGN_OF, B::CPP_ALIGN_OF);
 
     #[rustfmt::skip]
     fn into_memory<S>(
@@ -194,14 +218,14 @@ where
         });
 
         self.0.into_memory(&mut store, memory, allocator, Some(address))?;
-        self.1.into_memory(&mut store, memory, allocator, Some(address + A::CPP_SIZE_OF))?;
+        self.1.into_memory(&mut store, memory, allocator, Some(address + second_offset::<A, B>()))?;
 
         Ok(address)
     }
 
     fn from_memory<S>(mut store: &mut Store<S>, memory: &Memory, offset: usize) -> Result<Self> {
         let a = A::from_memory(&mut store, &memory, offset)?;
-        let b = B::from_memory(&mut store, &memory, offset + A::CPP_SIZE_OF)?;
+        let b = B::from_memory(&mut store, &memory, offset + second_offset::<A, B>())?;
 
         Ok((a, b))
     }
@@ -209,6 +233,7 @@ where
 
 impl WasmMemory for f32 {
     const CPP_SIZE_OF: usize = 4;
+    const CPP_ALIGN_OF: usize = 4;
 
     fn into_memory<S>(
         self,
@@ -243,6 +268,7 @@ impl WasmMemory for f32 {
 /// maps to `int32_t` or `int`
 impl WasmMemory for i32 {
     const CPP_SIZE_OF: usize = 4;
+    const CPP_ALIGN_OF: usize = 4;
 
     fn into_memory<S>(
         self,
@@ -277,6 +303,7 @@ impl WasmMemory for i32 {
 /// maps to `int64_t` or `long`
 impl WasmMemory for i64 {
     const CPP_SIZE_OF: usize = 8;
+    const CPP_ALIGN_OF: usize = 8;
 
     fn into_memory<S>(
         self,
@@ -311,6 +338,7 @@ impl WasmMemory for i64 {
 /// maps to `double`
 impl WasmMemory for f64 {
     const CPP_SIZE_OF: usize = 8;
+    const CPP_ALIGN_OF: usize = 8;
 
     fn into_memory<S>(
         self,
@@ -345,6 +373,7 @@ impl WasmMemory for f64 {
 /// maps to `char`
 impl WasmMemory for char {
     const CPP_SIZE_OF: usize = 1;
+    const CPP_ALIGN_OF: usize = 1;
 
     fn into_memory<S>(
         self,
@@ -375,8 +404,55 @@ impl WasmMemory for char {
     }
 }
 
+/// Implements [`WasmMemory`] for integers, which are stored little endian and aligned to their
+/// size, e.g. `__int128` has an alignment of 16.
+macro_rules! impl_wasm_memory_int {
+    ($($t:ty => $cpp:literal),*) => {
+        $(
+            #[doc = concat!("maps to `", $cpp, "`")]
+            impl WasmMemory for $t {
+                const CPP_SIZE_OF: usize = std::mem::size_of::<$t>();
+                const CPP_ALIGN_OF: usize = std::mem::size_of::<$t>();
+
+                fn into_memory<S>(
+                    self,
+                    store: &mut Store<S>,
+                    memory: &Memory,
+                    allocator: &AllocatorFunc,
+                    offset: Option<usize>,
+                ) -> Result<usize> {
+                    let address = match offset {
+                        Some(offset) => offset,
+                        None => allocator.call(&mut *store, Self::CPP_SIZE_OF as i32)? as usize,
+                    };
+
+                    memory.write(store, address, &self.to_le_bytes())?;
+
+                    Ok(address)
+                }
+
+                fn from_memory<S>(store: &mut Store<S>, memory: &Memory, offset: usize) -> Result<Self> {
+                    let mut buf = [0; std::mem::size_of::<$t>()];
+                    memory.read(store, offset, &mut buf)?;
+
+                    Ok(<$t>::from_le_bytes(buf))
+                }
+            }
+        )*
+    };
+}
+
+impl_wasm_memory_int!(
+    u32 => "uint32_t",
+    u64 => "uint64_t",
+    i16 => "short",
+    u8 => "uint8_t",
+    i128 => "__int128"
+);
+
 impl WasmMemory for bool {
     const CPP_SIZE_OF: usize = 1;
+    const CPP_ALIGN_OF: usize = 1;
 
     fn into_memory<S>(
         self,
@@ -406,23 +482,24 @@ impl WasmMemory for bool {
     }
 }
 
+/// Instantiates the test module, returning its memory and allocator.
+#[cfg(test)]
+pub(crate) fn test_setup() -> Result<(Store<usize>, Memory, AllocatorFunc)> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, 0);
+    let linker = Linker::new(&engine);
+    let module = Module::new(&engine, include_bytes!("../test.wasm"))?;
+    let instance = linker.instantiate(&mut store, &module)?;
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    let allocator: AllocatorFunc = instance.get_typed_func(&mut store, "alloc")?;
+
+    Ok((store, memory, allocator))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn test_setup() -> Result<(Store<usize>, Memory, AllocatorFunc)> {
-        let engine = Engine::default();
-        let mut store = Store::new(&engine, 0);
-        let memory = Memory::new(&mut store, MemoryType::new(1, None))?;
-        memory.grow(&mut store, 2)?;
-        let linker = Linker::new(&engine);
-        let module = Module::new(&engine, include_bytes!("../test.wasm"))?;
-        let instance = linker.instantiate(&mut store, &module)?;
-        let allocator: AllocatorFunc = instance.get_typed_func(&mut store, "alloc")?;
-
-        Ok((store, memory, allocator))
-    }
-
     #[test]
     fn vec_i32() -> Result<()> {
         let (mut store, memory, allocator) = test_setup()?;
@@ -508,6 +585,56 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn pair_padding() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        assert_eq!(<(char, i64)>::CPP_SIZE_OF, 16);
+        assert_eq!(<(i64, char)>::CPP_SIZE_OF, 16);
+        assert_eq!(<(char, (bool, i32))>::CPP_SIZE_OF, 12);
+
+        let initial = ('x', 1 << 40);
+        let address = initial.into_memory(&mut store, &memory, &allocator, None)?;
+
+        assert_eq!(i64::from_memory(&mut store, &memory, address + 8)?, 1 << 40);
+        assert_eq!(
+            <(char, i64)>::from_memory(&mut store, &memory, address)?,
+            initial
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn small_and_wide_ints() -> Result<()> {
+        let (mut store, memory, allocator) = test_setup()?;
+
+        assert_eq!(<(u8, i16)>::CPP_SIZE_OF, 4);
+        assert_eq!(<(u32, i128)>::CPP_SIZE_OF, 32);
+
+        let initial = (vec![u8::MAX, 0, 7], (i16::MIN, i128::MIN + 1));
+        let address = initial
+            .clone()
+            .into_memory(&mut store, &memory, &allocator, None)?;
+
+        assert_eq!(
+            <(Vec<u8>, (i16, i128))>::from_memory(&mut store, &memory, address)?,
+            initial
+        );
+
+        let initial = vec![u64::MAX, 1 << 63];
+        let address = initial
+            .clone()
+            .into_memory(&mut store, &memory, &allocator, None)?;
+
+        assert_eq!(
+            Vec::<u64>::from_memory(&mut store, &memory, address)?,
+            initial
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn vec_pair_vec() -> Result<()> {
         let (mut store, memory, allocator) = test_setup()?;
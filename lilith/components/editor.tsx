@@ -6,7 +6,7 @@ import { initVimMode } from "monaco-vim";
 import { useStore } from "../utils/state";
 
 type EditorProps = {
-    language: "cpp" | "markdown";
+    language: "cpp" | "c" | "rust" | "python" | "markdown";
     onChange: (
         text: string,
         event: monaco.editor.IModelContentChangedEvent
@@ -87,7 +87,7 @@ export default function Editor({
                 subscription.dispose();
             }
         };
-    }, [vimEnabled, editorTheme, editorFontSize]);
+    }, [vimEnabled, editorTheme, editorFontSize, language]);
 
     return (
         <div className="h-full grid grid-rows-full-min grid-cols-full">
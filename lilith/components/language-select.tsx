@@ -0,0 +1,28 @@
+import { Language, LANGUAGES } from "../utils/state";
+
+// the language monaco highlights submissions in
+export function editorLanguage(language: Language): "cpp" | "c" | "rust" | "python" {
+    return language == "c++" ? "cpp" : language;
+}
+
+export default function LanguageSelect({
+    value,
+    onChange,
+    className,
+}: {
+    value: Language,
+    onChange: (language: Language) => void,
+    className?: string,
+}): JSX.Element {
+    return (
+        <select
+            className={`border-neutral-300 dark:border-neutral-700 border rounded p-2 bg-neutral-50 dark:bg-neutral-900 outline-0 transition-shadow focus:ring dark:ring-neutral-700 ring-neutral-300 ${className ?? ""}`}
+            value={value}
+            onChange={event => onChange(event.currentTarget.value as Language)}
+        >
+            {Object.entries(LANGUAGES).map(([language, name]) =>
+                <option key={language} value={language}>{name}</option>
+            )}
+        </select>
+    );
+}
@@ -111,6 +111,7 @@ function TestsEditorList(): JSX.Element {
     );
     const [loading, setLoading] = useState(false);
     const [queuePosition, setQueuePosition] = useState(0);
+    const [testsText, setTestsText] = useState("");
 
     const { data: user, error: _error } = useSWR<User>(
         api_url("/user/me"),
@@ -123,6 +124,9 @@ function TestsEditorList(): JSX.Element {
         const {
             problemReference: reference,
             problemTests: tests,
+            problemKind: kind,
+            problemChecker: checker,
+            problemInteractor: interactor,
         } = useAdminStore.getState();
 
         try {
@@ -135,7 +139,11 @@ function TestsEditorList(): JSX.Element {
                 body: JSON.stringify({
                     reference,
                     user_id: user!.id,
+                    kind,
+                    checker: checker.trim() ? checker : undefined,
+                    interactor: interactor.trim() ? interactor : undefined,
                     inputs: tests.map((test) => test.input),
+                    inputs_text: testsText.trim() ? testsText : undefined,
                 })
             });
 
@@ -144,6 +152,7 @@ function TestsEditorList(): JSX.Element {
 
             if (data) {
                 setTests(data);
+                setTestsText("");
             }
 
             if (err) {
@@ -199,6 +208,14 @@ function TestsEditorList(): JSX.Element {
                 </LoadingButton>
             </div>
 
+            <textarea
+                value={testsText}
+                onChange={(e) => setTestsText(e.target.value)}
+                placeholder={'Paste more tests to populate, e.g.\ntwoSum([2, 7, 11, 15], 9)'}
+                className="p-2 font-mono text-sm bg-white border border-neutral-300 rounded-md"
+                rows={4}
+            />
+
             {loading && <QueueStatus className="mx-auto" queuePosition={queuePosition} />}
 
             <AdvancedSettings />
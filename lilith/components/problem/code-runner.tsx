@@ -2,9 +2,10 @@ import { useContext, useState } from "react";
 import { useSWRConfig } from "swr";
 import { ProblemIDContext } from ".";
 import { api_url } from "../../utils/fetcher";
-import { JobStatus, monitorJob } from "../../utils/job";
+import { cancelJob, JobStatus, monitorJob } from "../../utils/job";
 import { Submission, useSession, useStore } from "../../utils/state";
 import EditorPreferences from "../editor-preferences";
+import LanguageSelect from "../language-select";
 import LoadingButton from "../loading-button";
 import Modal from "../modal";
 import QueueStatus from "../queue-status";
@@ -14,15 +15,19 @@ export default function CodeRunner(): JSX.Element {
     const [dockerShown, setDockerShown] = useState(false);
     const [settingsShown, setSettingsShown] = useState(false);
     const setSubmissionShown = useSession((session) => session.setSubmissionShown);
+    const [language, setLanguage] = useStore((state) => [state.language, state.setLanguage]);
 
     function SubmitButton(): JSX.Element {
         const id = useContext(ProblemIDContext)!;
         const implementation = useStore(
             (state) => id && state.problemImpls[id]
         );
+        const language = useStore((state) => state.language);
         const setError = useSession((session) => session.setError);
         const [loading, setLoading] = useState(false);
         const [queuePosition, setQueuePosition] = useState(0);
+        const [progress, setProgress] = useState<[number, number] | undefined>(undefined);
+        const [jobId, setJobId] = useState<number | undefined>(undefined);
         const { mutate } = useSWRConfig();
 
         const submitProblem = async () => {
@@ -32,6 +37,7 @@ export default function CodeRunner(): JSX.Element {
             }
 
             setLoading(true);
+            setProgress(undefined);
 
             try {
                 let res = await fetch(api_url("/run/submit"), {
@@ -43,12 +49,18 @@ export default function CodeRunner(): JSX.Element {
                     body: JSON.stringify({
                         problem_id: id,
                         implementation,
+                        language,
                     }),
                 });
 
                 let job: JobStatus<Submission, string> = await res.json();
                 console.log(job);
-                let [data, err] = await monitorJob(job, (n) => setQueuePosition(n));
+                setJobId(job.id);
+                let [data, err] = await monitorJob(
+                    job,
+                    (n) => setQueuePosition(n),
+                    (completed, total) => setProgress([completed, total])
+                );
 
                 if (data) {
                     setTimeout(() => {
@@ -67,13 +79,22 @@ export default function CodeRunner(): JSX.Element {
             }
             finally {
                 setLoading(false);
+                setJobId(undefined);
             }
         };
 
         return (
             <div>
                 {loading &&
-                    <QueueStatus className="mr-4" queuePosition={queuePosition} />
+                    <QueueStatus className="mr-4" queuePosition={queuePosition} progress={progress} />
+                }
+                {loading && jobId !== undefined &&
+                    <button
+                        className="p-4 border-l h-full border-neutral-300 dark:border-neutral-700 hover:bg-neutral-200 dark:hover:bg-neutral-700 transition-colors"
+                        onClick={() => cancelJob(jobId)}
+                    >
+                        Cancel
+                    </button>
                 }
                 <LoadingButton
                     onClick={() => {
@@ -104,12 +125,14 @@ export default function CodeRunner(): JSX.Element {
                 </button>
 
                 <button
-                    className="mr-auto p-4 border-r border-neutral-300 dark:border-neutral-700 hover:bg-neutral-200 dark:hover:bg-neutral-700 transition-colors"
+                    className="p-4 border-r border-neutral-300 dark:border-neutral-700 hover:bg-neutral-200 dark:hover:bg-neutral-700 transition-colors"
                     onClick={() => setSettingsShown(!settingsShown)}
                 >
                     Settings
                 </button>
 
+                <LanguageSelect className="mr-auto m-2" value={language} onChange={setLanguage} />
+
                 <SubmitButton />
             </div>
         </div>
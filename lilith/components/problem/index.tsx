@@ -7,6 +7,7 @@ import useSWR from "swr";
 import { Competition } from "../../pages/competitions";
 import { api_url, fetcher } from "../../utils/fetcher";
 import { useStore } from "../../utils/state";
+import { editorLanguage } from "../language-select";
 import Tabbed from "../tabbed";
 import CodeRunner from "./code-runner";
 import Description from "./description";
@@ -47,11 +48,12 @@ export default function ProblemView({ id }: ProblemViewProps): JSX.Element {
             "";
 
         const setProblemImpl = useStore((state) => state.setProblemImpl);
+        const language = useStore((state) => state.language);
 
         return (
             <div className="bg-white dark:bg-neutral-900 h-full ring-1 md:ring-0 ring-neutral-300 dark:ring-neutral-700">
                 <Editor
-                    language="cpp"
+                    language={editorLanguage(language)}
                     value={content}
                     onChange={(text, _event) => {
                         if (id) setProblemImpl(id, text);
@@ -32,6 +32,7 @@ export default function InputTester() {
     const implementation = useStore((state) =>
         problem_id ? state.problemImpls[problem_id] : undefined
     );
+    const language = useStore((state) => state.language);
 
     let { data, error } = useSWR<Test>(problem_id ? api_url(`/problems/${problem_id}/tests/0`) : null, fetcher);
 
@@ -57,6 +58,7 @@ export default function InputTester() {
                     problem_id,
                     input,
                     implementation,
+                    language,
                 }),
             });
 
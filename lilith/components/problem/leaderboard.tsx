@@ -1,25 +1,33 @@
 import Link from "next/link";
-import { useContext } from "react";
+import { useContext, useState } from "react";
 import useSWR from "swr";
 import { ProblemIDContext } from ".";
 import { api_url, fetcher } from "../../utils/fetcher";
+import { memoryFormat, memoryFormatLong } from "../../utils/memory";
+import { Language, useStore } from "../../utils/state";
 import { timeFormat } from "../../utils/time";
+import LanguageSelect from "../language-select";
 import Tabbed from "../tabbed";
 
 type ProblemLeaderboardItem = {
     submission_id: number,
     runtime: number,
+    // only given by the memory leaderboard, which is ranked by it instead of fuel
+    memory?: number,
     name: String,
     username: String,
     time: string,
+    language: string,
 }
 
 function ProblemLeaderboardElement({
     submission_id,
     runtime,
+    memory,
     username,
     name,
     time,
+    language,
     index
 }: ProblemLeaderboardItem & { index: number }): JSX.Element {
     let bgColor = "bg-white dark:bg-neutral-800 border-neutral-300 hover:bg-neutral-50 hover:dark:bg-neutral-700 dark:border-neutral-700";
@@ -30,6 +38,9 @@ function ProblemLeaderboardElement({
 
     const fuelCompact = Intl.NumberFormat('en', { notation: "compact" }).format(runtime) + " fuel";
     const fuelLong = Intl.NumberFormat('en', { notation: "standard" }).format(runtime) + " fuel";
+    const [scoreCompact, scoreLong] = memory == undefined
+        ? [fuelCompact, fuelLong]
+        : [memoryFormat(memory), `${memoryFormatLong(memory)}, ${fuelLong}`];
     const timeShort = new Date(time + 'Z').toLocaleDateString("en-us", {
         month: "short",
         day: "numeric",
@@ -42,10 +53,10 @@ function ProblemLeaderboardElement({
             <a className={`transition-colors p-2 bg-silver border-b ${bgColor} flex`}>
                 <div className="flex flex-col">
                     <span className="font-extrabold">{name}</span>
-                    <span>{username}</span>
+                    <span>{username} · {language}</span>
                 </div>
                 <div className="flex flex-col ml-auto">
-                    <span title={fuelLong} className="font-bold my-auto ml-auto">{fuelCompact}</span>
+                    <span title={scoreLong} className="font-bold my-auto ml-auto">{scoreCompact}</span>
                     <span title={timeLong}>{timeShort}</span>
                 </div>
             </a>
@@ -53,11 +64,15 @@ function ProblemLeaderboardElement({
     );
 }
 
-function ProblemLeaderboardUsers() {
+function leaderboardUrl(problemId: number, board: string, language: Language): string {
+    return api_url(`/problems/${problemId}/leaderboard/${board}?language=${encodeURIComponent(language)}`);
+}
+
+function ProblemLeaderboardUsers({ language }: { language: Language }) {
     const problemId = useContext(ProblemIDContext);
 
     const { data } = useSWR<ProblemLeaderboardItem[]>(
-        problemId ? api_url(`/problems/${problemId}/leaderboard/users`) : null,
+        problemId ? leaderboardUrl(problemId, "users", language) : null,
         fetcher
     );
 
@@ -71,11 +86,28 @@ function ProblemLeaderboardUsers() {
 
 }
 
-function ProblemLeaderboardSubmissions() {
+function ProblemLeaderboardSubmissions({ language }: { language: Language }) {
+    const problemId = useContext(ProblemIDContext);
+
+    const { data } = useSWR<ProblemLeaderboardItem[]>(
+        problemId ? leaderboardUrl(problemId, "submissions", language) : null,
+        fetcher
+    );
+
+    if (!data) return <></>;
+
+    return (
+        <div className="flex flex-col">
+            {data.map((item, i) => <ProblemLeaderboardElement {...item} index={i} key={i} />)}
+        </div>
+    );
+}
+
+function ProblemLeaderboardSlowSubmissions({ language }: { language: Language }) {
     const problemId = useContext(ProblemIDContext);
 
     const { data } = useSWR<ProblemLeaderboardItem[]>(
-        problemId ? api_url(`/problems/${problemId}/leaderboard/submissions`) : null,
+        problemId ? leaderboardUrl(problemId, "slow-submissions", language) : null,
         fetcher
     );
 
@@ -88,11 +120,11 @@ function ProblemLeaderboardSubmissions() {
     );
 }
 
-function ProblemLeaderboardSlowSubmissions() {
+function ProblemLeaderboardMemory({ language }: { language: Language }) {
     const problemId = useContext(ProblemIDContext);
 
     const { data } = useSWR<ProblemLeaderboardItem[]>(
-        problemId ? api_url(`/problems/${problemId}/leaderboard/slow-submissions`) : null,
+        problemId ? leaderboardUrl(problemId, "memory", language) : null,
         fetcher
     );
 
@@ -106,13 +138,21 @@ function ProblemLeaderboardSlowSubmissions() {
 }
 
 export default function ProblemLeaderboard(): JSX.Element {
+    // submissions are only ranked against others in the same language
+    const defaultLanguage = useStore((state) => state.language);
+    const [language, setLanguage] = useState(defaultLanguage);
+
     return (
-        <Tabbed
-            titles={["Users", "Submissions", "🐌"]}
-        >
-            <ProblemLeaderboardUsers />
-            <ProblemLeaderboardSubmissions />
-            <ProblemLeaderboardSlowSubmissions />
-        </Tabbed>
+        <div className="flex flex-col">
+            <LanguageSelect className="m-2" value={language} onChange={setLanguage} />
+            <Tabbed
+                titles={["Users", "Submissions", "Memory", "🐌"]}
+            >
+                <ProblemLeaderboardUsers language={language} />
+                <ProblemLeaderboardSubmissions language={language} />
+                <ProblemLeaderboardMemory language={language} />
+                <ProblemLeaderboardSlowSubmissions language={language} />
+            </Tabbed>
+        </div>
     );
 }
@@ -1,5 +1,6 @@
 import { useEffect, useRef, useState } from "react";
 import renderLatex from "../../../utils/latex";
+import { memoryFormat, memoryFormatLong } from "../../../utils/memory";
 import { AsymptoticComplexity, Submission, useSession } from "../../../utils/state";
 
 export function ShareButton({
@@ -57,6 +58,12 @@ function AsymptoticComplexityDisplay({ complexity }: { complexity: AsymptoticCom
         content = "$\\mathrm{O}(n^2)$";
     } else if (complexity == "LOG_LINEAR") {
         content = "$\\mathrm{O}(n \\log n)$";
+    } else if (complexity == "PRODUCT") {
+        content = "$\\mathrm{O}(n \\cdot m)$";
+    } else if (complexity == "VERTICES_PLUS_EDGES") {
+        content = "$\\mathrm{O}(V + E)$";
+    } else if (complexity == "EDGES_LOG_VERTICES") {
+        content = "$\\mathrm{O}(E \\log V)$";
     } else if (complexity == "EXPONENTIAL") {
         content = "$\\mathrm{O}(2^n)$";
     } else {
@@ -161,7 +168,9 @@ export default function SubmissionFeedback({
     error,
     success,
     runtime,
-    complexity
+    complexity,
+    complexity_r_squared,
+    memory
 }: Submission & { inProblemView: boolean }): JSX.Element {
     if (error) {
         return <SubmissionFeedbackError id={id} inProblemView={inProblemView} error={error} />
@@ -195,8 +204,16 @@ export default function SubmissionFeedback({
                 <span>The code passed all of the supplied tests.</span>
                 {complexity && <span>
                     Estimated Time Complexity: <AsymptoticComplexityDisplay complexity={complexity} />
+                    {complexity_r_squared != undefined &&
+                        <span title="How closely the fuel of each test follows this complexity">
+                            , R² = {complexity_r_squared.toFixed(2)}
+                        </span>
+                    }
                 </span>}
                 <span>{fuel}</span>
+                {memory != undefined && <span>
+                    Peak memory: <span title={memoryFormatLong(memory)}>{memoryFormat(memory)}</span>
+                </span>}
             </div>
         );
     } else {
@@ -28,7 +28,17 @@ export type FunctionValue =
     { Long: ContainerVariant<number> } |
     { Float: ContainerVariant<number> } |
     { Double: ContainerVariant<number> } |
-    { Bool: ContainerVariant<boolean> }
+    { Bool: ContainerVariant<boolean> } |
+    { UInt: ContainerVariant<number> } |
+    { ULong: ContainerVariant<number> } |
+    { Short: ContainerVariant<number> } |
+    { Byte: ContainerVariant<number> } |
+    { Int128: ContainerVariant<number> } |
+    { Struct: [StructType, ContainerVariant<FunctionValue[]>] } |
+    { Set: [SetType, ContainerVariant<FunctionValue[]>] } |
+    { Map: [MapType, ContainerVariant<[FunctionValue, FunctionValue][]>] } |
+    { LinkedList: ContainerVariant<number[]> } |
+    { BinaryTree: ContainerVariant<(number | null)[]> }
 
 export type FunctionType =
     { String: ContainerVariantType } |
@@ -37,57 +47,239 @@ export type FunctionType =
     { Long: ContainerVariantType } |
     { Float: ContainerVariantType } |
     { Double: ContainerVariantType } |
-    { Bool: ContainerVariantType }
+    { Bool: ContainerVariantType } |
+    { UInt: ContainerVariantType } |
+    { ULong: ContainerVariantType } |
+    { Short: ContainerVariantType } |
+    { Byte: ContainerVariantType } |
+    { Int128: ContainerVariantType } |
+    { Struct: [StructType, ContainerVariantType] } |
+    { Set: [SetType, ContainerVariantType] } |
+    { Map: [MapType, ContainerVariantType] } |
+    { LinkedList: ContainerVariantType } |
+    { BinaryTree: ContainerVariantType }
+
+export type StructKind =
+    "Pair" |
+    "Tuple" |
+    { Named: { name: string, field_names: string[] } };
+
+export type StructType = {
+    kind: StructKind;
+    fields: FunctionType[];
+}
+
+export type SetType = {
+    unordered?: boolean;
+    key: FunctionType;
+}
+
+export type MapType = {
+    unordered?: boolean;
+    key: FunctionType;
+    value: FunctionType;
+}
 
 export type ContainerVariant<T> =
     { Grid: T[][] } |
     { Graph: T[][] } |
     { List: T[] } |
-    { Single: T }
+    { Single: T } |
+    { Vector: [ContainerVariantType, ContainerVariant<T>[]] }
 
-export type ContainerVariantType = "Grid" | "Graph" | "List" | "Single";
+// nested vectors hold the type of their elements, e.g. `{ Vector: "Grid" }` for a 3D grid
+export type ContainerVariantType = "Grid" | "Graph" | "List" | "Single" | { Vector: ContainerVariantType };
 
 export interface TestResult extends Test {
     output: FunctionValue;
     success: boolean;
     fuel: number;
+    queries?: number;
+    interactor_fuel?: number;
+    memory?: number;
+    heap?: number;
 }
 
-export function FunctionTypeDisplay({ data }: { data: FunctionValue }): JSX.Element {
-    // KILL JAVASCRIPT I SWEAR TO GOD
+export function structName(structType: StructType): string {
+    if (structType.kind === "Pair" || structType.kind === "Tuple") {
+        return structType.kind;
+    } else {
+        return structType.kind.Named.name;
+    }
+}
+
+// formats a struct like `(1, "a")` or `Point { x: 1, y: 2 }`
+function structToString(structType: StructType, fields: FunctionValue[]): string {
+    let values = fields.map(valueToString);
+
+    if (structType.kind === "Pair" || structType.kind === "Tuple") {
+        return `(${values.join(", ")})`;
+    } else {
+        let names = structType.kind.Named.field_names;
+        return `${structType.kind.Named.name} { ${values.map((value, i) => `${names[i]}: ${value}`).join(", ")} }`;
+    }
+}
+
+// formats sets like `{1, 2}` and maps like `{"a": 1}`
+function setToString(keys: FunctionValue[]): string {
+    return `{${keys.map(valueToString).join(", ")}}`;
+}
+
+function mapToString(entries: [FunctionValue, FunctionValue][]): string {
+    return `{${entries.map(([key, value]) => `${valueToString(key)}: ${valueToString(value)}`).join(", ")}}`;
+}
+
+function valueToString(value: FunctionValue): string {
+    let [_type, variant] = displayable(value);
+
+    // nested structs, sets, maps and nodes are already formatted
+    if (("Struct" in value || "Set" in value || "Map" in value || "LinkedList" in value || "BinaryTree" in value) && "Single" in variant) {
+        return variant.Single;
+    }
+
+    return JSON.stringify(containerToPlain(variant));
+}
+
+export function mapContainer<T, U>(variant: ContainerVariant<T>, f: (element: T) => U): ContainerVariant<U> {
+    if ("Grid" in variant) {
+        return { Grid: variant.Grid.map((row) => row.map(f)) };
+    } else if ("Graph" in variant) {
+        return { Graph: variant.Graph.map((row) => row.map(f)) };
+    } else if ("List" in variant) {
+        return { List: variant.List.map(f) };
+    } else if ("Vector" in variant) {
+        let [type, elements] = variant.Vector;
+        return { Vector: [type, elements.map((element) => mapContainer(element, f))] };
+    } else {
+        return { Single: f(variant.Single) };
+    }
+}
+
+export function variantType<T>(variant: ContainerVariant<T>): ContainerVariantType {
+    if ("Vector" in variant) {
+        return { Vector: variant.Vector[0] };
+    }
+
+    return Object.keys(variant)[0] as ContainerVariantType;
+}
+
+export function containerName(container: ContainerVariantType): string {
+    return typeof container == "string" ? container : `Vector of ${containerName(container.Vector)}`;
+}
+
+// the elements of a container as nested arrays, e.g. `[[[1]], [[2, 3]]]` for a vector of grids
+export function containerToPlain<T>(variant: ContainerVariant<T>): any {
+    if ("Vector" in variant) {
+        return variant.Vector[1].map(containerToPlain);
+    }
+
+    return Object.values(variant)[0];
+}
+
+export function containerFromPlain<T>(container: ContainerVariantType, data: any): ContainerVariant<T> {
+    if (typeof container != "string") {
+        return { Vector: [container.Vector, data.map((element: any) => containerFromPlain(container.Vector, element))] };
+    }
+
+    return { [container]: data } as ContainerVariant<T>;
+}
+
+export function collectionName(type: { Set: [SetType, any] } | { Map: [MapType, any] }): string {
+    if ("Set" in type) {
+        return type.Set[0].unordered ? "UnorderedSet" : "Set";
+    } else {
+        return type.Map[0].unordered ? "UnorderedMap" : "Map";
+    }
+}
+
+// formats linked lists like `1 -> 2 -> 3`, and trees in level order like `[1, null, 2]`
+function listToString(values: number[]): string {
+    return values.length == 0 ? "null" : values.join(" -> ");
+}
+
+function treeToString(values: (number | null)[]): string {
+    return JSON.stringify(values);
+}
+
+// structs, sets, maps and nodes are displayed as strings in the same containers as everything else
+function displayable(data: FunctionValue): [string, ContainerVariant<any>] {
+    if ("Struct" in data) {
+        let [structType, variant] = data.Struct;
+        return [structName(structType), mapContainer(variant, (fields) => structToString(structType, fields))];
+    } else if ("Set" in data) {
+        return [collectionName(data), mapContainer(data.Set[1], setToString)];
+    } else if ("Map" in data) {
+        return [collectionName(data), mapContainer(data.Map[1], mapToString)];
+    } else if ("LinkedList" in data) {
+        return ["LinkedList", mapContainer(data.LinkedList, listToString)];
+    } else if ("BinaryTree" in data) {
+        return ["BinaryTree", mapContainer(data.BinaryTree, treeToString)];
+    }
+
     let [[type, variant]] = Object.entries(data);
+    return [type, variant];
+}
 
+function VariantDisplay({ dataType, variant }: { dataType: string, variant: ContainerVariant<any> }): JSX.Element {
     if ("Grid" in variant) {
-        return <GridDisplay dataType={type} data={variant.Grid} />;
+        return <GridDisplay dataType={dataType} data={variant.Grid} />;
     } else if ("Graph" in variant) {
-        return <GraphDisplay dataType={type} data={variant.Graph} />;
+        return <GraphDisplay dataType={dataType} data={variant.Graph} />;
     } else if ("List" in variant) {
-        return <ListDisplay dataType={type} data={variant.List} />;
+        return <ListDisplay dataType={dataType} data={variant.List} />;
+    } else if ("Vector" in variant) {
+        return (
+            <div className="flex flex-col gap-2">
+                {variant.Vector[1].map((element, i) => <VariantDisplay key={i} dataType={dataType} variant={element} />)}
+            </div>
+        );
     } else if ("Single" in variant) {
-        return <SingleDisplay dataType={type} data={variant.Single.toString()} />;
+        return <SingleDisplay dataType={dataType} data={variant.Single.toString()} />;
     }
 
     return <></>;
 }
 
-export function FunctionTypeDiffDisplay({ output, expected }: { output: FunctionValue, expected: FunctionValue }): JSX.Element {
-    let [[type, outputVariant]] = Object.entries(output);
-    let [[_type, expectedVariant]] = Object.entries(expected);
+export function FunctionTypeDisplay({ data }: { data: FunctionValue }): JSX.Element {
+    // KILL JAVASCRIPT I SWEAR TO GOD
+    let [type, variant] = displayable(data);
+
+    return <VariantDisplay dataType={type} variant={variant} />;
+}
 
-    if ("Grid" in outputVariant && "Grid" in expectedVariant) {
-        return <GridDiffDisplay dataType={type} output={outputVariant.Grid} expected={expectedVariant.Grid} />;
+function VariantDiffDisplay({ dataType, output, expected }: { dataType: string, output: ContainerVariant<any>, expected: ContainerVariant<any> }): JSX.Element {
+    if ("Grid" in output && "Grid" in expected) {
+        return <GridDiffDisplay dataType={dataType} output={output.Grid} expected={expected.Grid} />;
     }
-    else if ("Graph" in outputVariant && "Graph" in expectedVariant) {
+    else if ("Graph" in output && "Graph" in expected) {
         // currently we just display the output graph normally
-        return <GraphDisplay dataType={type} data={outputVariant.Graph} />
+        return <GraphDisplay dataType={dataType} data={output.Graph} />
+    }
+    else if ("List" in output && "List" in expected) {
+        return <ListDiffDisplay dataType={dataType} output={output.List} expected={expected.List} />;
     }
-    else if ("List" in outputVariant && "List" in expectedVariant) {
-        return <ListDiffDisplay dataType={type} output={outputVariant.List} expected={expectedVariant.List} />;
+    else if ("Vector" in output && "Vector" in expected) {
+        // elements without a counterpart are displayed normally
+        let expectedElements = expected.Vector[1];
+
+        return (
+            <div className="flex flex-col gap-2">
+                {output.Vector[1].map((element, i) => i < expectedElements.length
+                    ? <VariantDiffDisplay key={i} dataType={dataType} output={element} expected={expectedElements[i]} />
+                    : <VariantDisplay key={i} dataType={dataType} variant={element} />)}
+            </div>
+        );
     }
-    else if ("Single" in outputVariant && "Single" in expectedVariant) {
-        return <SingleDiffDisplay dataType={type} output={outputVariant.Single.toString()} expected={expectedVariant.Single.toString()} />;
+    else if ("Single" in output && "Single" in expected) {
+        return <SingleDiffDisplay dataType={dataType} output={output.Single.toString()} expected={expected.Single.toString()} />;
     }
 
     return <></>;
 }
 
+export function FunctionTypeDiffDisplay({ output, expected }: { output: FunctionValue, expected: FunctionValue }): JSX.Element {
+    let [type, outputVariant] = displayable(output);
+    let [_type, expectedVariant] = displayable(expected);
+
+    return <VariantDiffDisplay dataType={type} output={outputVariant} expected={expectedVariant} />;
+}
@@ -2,6 +2,7 @@ import { ReactNode, useState } from "react";
 import useSWR from "swr";
 import { FunctionTypeDiffDisplay, FunctionTypeDisplay, TestResult } from ".";
 import { api_url, fetcher } from "../../../../utils/fetcher";
+import { memoryFormat, memoryFormatLong } from "../../../../utils/memory";
 import ErrorBox from "../../../error-box";
 import Modal from "../../../modal";
 import { GridDiffDisplay, GridDisplay } from "./grid";
@@ -75,7 +76,7 @@ function TestResultView({ index, success, problemId }: TestResultDescription): J
     return <TestResultInner {...data} />;
 }
 
-export function TestResultInner({ index, input, output, expected_output, error, fuel, max_fuel, success }: TestResult) {
+export function TestResultInner({ index, input, output, expected_output, error, fuel, max_fuel, queries, interactor_fuel, memory, heap, success }: TestResult) {
 
     let compact = Intl.NumberFormat('en', { notation: "compact" }).format(fuel) + " fuel";
     let long = Intl.NumberFormat('en', { notation: "standard" }).format(fuel) + " fuel";
@@ -119,6 +120,18 @@ export function TestResultInner({ index, input, output, expected_output, error,
                     <div>
                         <span>Consumed </span><span title={long}>{compact}.</span>
                     </div>
+
+                    {queries == null || <div>
+                        <span>Made {queries} {queries == 1 ? "query" : "queries"}.</span>
+                        {interactor_fuel == null || <span title={Intl.NumberFormat('en', { notation: "standard" }).format(interactor_fuel) + " fuel"}>
+                            {" "}The interactor consumed {Intl.NumberFormat('en', { notation: "compact" }).format(interactor_fuel)} fuel.
+                        </span>}
+                    </div>}
+
+                    {memory == null || <div>
+                        <span>Peak memory: </span><span title={memoryFormatLong(memory)}>{memoryFormat(memory)}</span>
+                        {heap == null || <span title={memoryFormatLong(heap)}> ({memoryFormat(heap)} heap)</span>}
+                    </div>}
                 </>}
             </div>
 
@@ -1,15 +1,21 @@
 type QueueStatusProps = {
   queuePosition: number
+  progress?: [number, number],
   className?: string,
 };
 
 export default function QueueStatus({
   queuePosition,
+  progress,
   className
 }: QueueStatusProps): JSX.Element {
   return <>
     {queuePosition != 0 && <span className={`animate-pulse ${className}`}>
       Position in queue: {queuePosition}
     </span>}
+    {queuePosition == 0 && progress && <span className={`inline-flex items-center gap-2 ${className}`}>
+      <progress value={progress[0]} max={progress[1]} />
+      {progress[0]}/{progress[1]} tests
+    </span>}
   </>;
 }
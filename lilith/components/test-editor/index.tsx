@@ -1,6 +1,6 @@
 import produce from "immer";
 import { useEffect, useState } from "react";
-import { FunctionValue } from "../problem/submission/tests";
+import { FunctionType, FunctionValue, collectionName, containerFromPlain, containerName, containerToPlain, mapContainer, structName, variantType } from "../problem/submission/tests";
 
 // Any is the easiest way out of this :(
 // Give me rust enums please
@@ -149,6 +149,86 @@ function SingleEditor({ type, value, onChange }: SingleEditorProps) {
     }
 }
 
+// Structs are written as arrays of their fields, e.g. `[1, "a"]` for a `pair<int, string>`,
+// sets as arrays of their keys and maps as arrays of `[key, value]` entries
+function toPlain(value: FunctionValue): any {
+    if ("Struct" in value) {
+        let [_structType, variant] = value.Struct;
+        return containerToPlain(mapContainer(variant, (fields) => fields.map(toPlain)));
+    } else if ("Set" in value) {
+        let [_setType, variant] = value.Set;
+        return containerToPlain(mapContainer(variant, (keys) => keys.map(toPlain)));
+    } else if ("Map" in value) {
+        let [_mapType, variant] = value.Map;
+        return containerToPlain(mapContainer(variant, (entries) =>
+            entries.map(([key, value]) => [toPlain(key), toPlain(value)])));
+    }
+
+    let [[_type, variant]] = Object.entries(value);
+    return containerToPlain(variant);
+}
+
+function fromPlain(type: FunctionType, data: any): FunctionValue {
+    if ("Struct" in type) {
+        let [structType, container] = type.Struct;
+        let variant = containerFromPlain<any[]>(container, data);
+
+        return {
+            Struct: [structType, mapContainer(variant, (fields) =>
+                structType.fields.map((field, i) => fromPlain(field, fields[i])))]
+        };
+    } else if ("Set" in type) {
+        let [setType, container] = type.Set;
+        let variant = containerFromPlain<any[]>(container, data);
+
+        return { Set: [setType, mapContainer(variant, (keys) => keys.map((key) => fromPlain(setType.key, key)))] };
+    } else if ("Map" in type) {
+        let [mapType, container] = type.Map;
+        let variant = containerFromPlain<any[][]>(container, data);
+
+        return {
+            Map: [mapType, mapContainer(variant, (entries) =>
+                entries.map(([key, value]) => [fromPlain(mapType.key, key), fromPlain(mapType.value, value)] as [FunctionValue, FunctionValue]))]
+        };
+    }
+
+    let [[kind, container]] = Object.entries(type);
+    return { [kind]: containerFromPlain(container, data) } as FunctionValue;
+}
+
+type StructEditorProps = {
+    data: any,
+    label: string,
+
+    onChange: (data: any) => void,
+};
+
+function StructEditor({ label, data, onChange }: StructEditorProps) {
+    const [structString, setStructString] = useState(JSON.stringify(data));
+    const [isValid, setIsValid] = useState(false);
+
+    useEffect(() => {
+        try {
+            let data = JSON.parse(structString);
+            setIsValid(true);
+            onChange(data);
+        }
+        catch (_e) {
+            setIsValid(false);
+        }
+
+    }, [structString, onChange]);
+
+    return (
+        <div>
+            <span className="text-sm">{label} (fields in order):</span>
+            <textarea value={structString} className={`p-2 outline w-full font-mono focus:outline-4 rounded-md dark:bg-neutral-900 ${isValid ? "outline-green-500" : "outline-red-500"}`} onChange={(e) => {
+                setStructString(e.currentTarget.value);
+            }} />
+        </div>
+    );
+}
+
 type FunctionArgumentEditorProps = {
     arg: FunctionValue,
     onChange: (data: FunctionValue) => void,
@@ -156,9 +236,43 @@ type FunctionArgumentEditorProps = {
 
 // edits a single field of a function argument
 function FunctionArgumentEditor({ arg, onChange }: FunctionArgumentEditorProps) {
+    if ("Struct" in arg) {
+        let [structType, variant] = arg.Struct;
+        let container = variantType(variant);
+        let type = { Struct: [structType, container] } as FunctionType;
+
+        return <StructEditor label={`${structName(structType)} ${containerName(container)}`} data={toPlain(arg)} onChange={(data) => {
+            onChange(fromPlain(type, data));
+        }} />
+    } else if ("Set" in arg || "Map" in arg) {
+        let [collectionType, variant] = "Set" in arg ? arg.Set : arg.Map;
+        let container = variantType(variant);
+        let type = ("Set" in arg ? { Set: [collectionType, container] } : { Map: [collectionType, container] }) as FunctionType;
+
+        return <StructEditor label={`${collectionName(arg)} ${containerName(container)}`} data={toPlain(arg)} onChange={(data) => {
+            onChange(fromPlain(type, data));
+        }} />
+    } else if ("LinkedList" in arg || "BinaryTree" in arg) {
+        // lists are written as their values, and trees in level order like `[1, null, 2]`
+        let [[kind, variant]] = Object.entries(arg);
+        let container = variantType(variant);
+        let type = { [kind]: container } as FunctionType;
+
+        return <StructEditor label={`${kind} ${containerName(container)}`} data={toPlain(arg)} onChange={(data) => {
+            onChange(fromPlain(type, data));
+        }} />
+    }
+
     let [[type, variant]] = Object.entries(arg);
 
-    if ("Grid" in variant) {
+    if ("Vector" in variant) {
+        // nested vectors are written as nested arrays, e.g. `[[[1, 2]], [[3]]]` for a vector of grids
+        let container = variantType(variant);
+
+        return <StructEditor label={`${type} ${containerName(container)}`} data={toPlain(arg)} onChange={(data) => {
+            onChange(fromPlain({ [type]: container } as FunctionType, data));
+        }} />
+    } else if ("Grid" in variant) {
         return (
             <GridEditor type={type} data={variant.Grid} onChange={(grid) => {
                 // typescript: trust me bro
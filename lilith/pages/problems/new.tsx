@@ -10,13 +10,61 @@ import LoadingButton from "../../components/loading-button";
 import Navbar from "../../components/navbar";
 import DescriptionEditor from "../../components/problem-editor/description-editor";
 import TestsEditor from "../../components/problem-editor/tests-editor";
-import { FunctionValue, FunctionTypeDisplay, FunctionType } from "../../components/problem/submission/tests";
+import { ContainerVariantType, FunctionValue, FunctionTypeDisplay, FunctionType, MapType, SetType, StructType, containerFromPlain } from "../../components/problem/submission/tests";
 import Tabbed from "../../components/tabbed";
 import { api_url, fetcher } from "../../utils/fetcher";
-import { useAdminStore, useSession, useStore } from "../../utils/state";
+import { ComparisonMode, OutputNormalization, ProblemKind, useAdminStore, useSession, useStore } from "../../utils/state";
 import { Competition } from "../competitions";
 const Editor = dynamic(import("../../components/editor"), { ssr: false });
 
+function defaultValue(type: FunctionType): FunctionValue {
+    let [[dataType, containerType]] = Object.entries(type);
+
+    if ("Struct" in type) {
+        let [structType, container] = type.Struct;
+        let fields = structType.fields.map(defaultValue);
+
+        return { Struct: [structType, container == "Single" ? { Single: fields } : containerFromPlain(container, [])] };
+    } else if ("Set" in type) {
+        let [setType, container] = type.Set;
+        return { Set: [setType, container == "Single" ? { Single: [] } : containerFromPlain(container, [])] };
+    } else if ("Map" in type) {
+        let [mapType, container] = type.Map;
+        return { Map: [mapType, container == "Single" ? { Single: [] } : containerFromPlain(container, [])] };
+    }
+
+    let value: any;
+    if (["Int", "Long", "UInt", "ULong", "Short", "Byte", "Int128", "Float", "Double"].includes(dataType)) {
+        value = 0;
+    } else if (dataType == "String" || dataType == "Char") {
+        value = "a";
+    } else if (dataType == "Bool") {
+        value = false;
+    } else if (dataType == "LinkedList" || dataType == "BinaryTree") {
+        value = [];
+    }
+
+    let item = containerType == "Single" ? { Single: value } : containerFromPlain(containerType as ContainerVariantType, []);
+
+    return { [dataType]: item } as FunctionValue;
+}
+
+// parses fields like `Int, String List` for pairs and tuples, or `x: Int, y: Int` for structs
+function parseFields(fields: string): [string[], FunctionType[]] {
+    let names: string[] = [];
+    let types: FunctionType[] = [];
+
+    for (let field of fields.split(",")) {
+        let [name, type] = field.includes(":") ? field.split(":") : ["", field];
+        let [dataType, container] = type.trim().split(/\s+/);
+
+        names.push(name.trim());
+        types.push({ [dataType]: container ?? "Single" } as FunctionType);
+    }
+
+    return [names, types];
+}
+
 function ArgumentsEditor(): JSX.Element {
     const [testFormat, setTestFormat] = useAdminStore(
         (state) => [state.problemTestFormat, state.setProblemTestFormat],
@@ -25,36 +73,69 @@ function ArgumentsEditor(): JSX.Element {
 
     const [containerType, setContainerType] = useState("Single");
     const [dataType, setDataType] = useState("Int");
+    const [structName, setStructName] = useState("");
+    const [structFields, setStructFields] = useState("Int, Int");
+    const [unordered, setUnordered] = useState(false);
+    const setError = useSession((state) => state.setError);
 
-    const getItem = () => {
-        let defaultValue: any;
-        if (dataType == "Int" || dataType == "Long" || dataType == "Float" || dataType == "Double") {
-            defaultValue = 0;
-        } else if (dataType == "String" || dataType == "Char") {
-            defaultValue = "a";
-        }
-
-        let item: any = {};
-        if (containerType == "Grid" || containerType == "Graph" || containerType == "List") {
-            item[containerType] = [];
-        }
-        else {
-            item[containerType] = defaultValue;
-        }
+    // reads the arguments and return type from the function of the same name in the reference
+    const inferSignature = async () => {
+        const reference = useAdminStore.getState().problemReference;
 
-        let outerItem: any = {};
-        outerItem[dataType] = item;
+        try {
+            const res = await (await fetch(api_url("/problems/signature"), {
+                method: "POST",
+                headers: {
+                    "Content-Type": "application/json"
+                },
+                credentials: "include",
+                body: JSON.stringify({ reference, name: testFormat.name }),
+            })).json();
 
-        return outerItem as FunctionValue;
-    }
+            if (res.error) {
+                setError(res.error, true);
+            } else {
+                setTestFormat({
+                    name: res.name,
+                    arguments: res.arguments.map(defaultValue),
+                    return_type: res.return_type,
+                });
+            }
+        } catch (e) {
+            console.log(e);
+            setError("Network error.", true);
+        }
+    };
 
     const getItemType = () => {
-        let item: any = {};
-        item[dataType] = containerType;
+        // the only nested vector offered here is a vector of grids, e.g. a 3D DP table
+        let container: ContainerVariantType = containerType == "Vector" ? { Vector: "Grid" } : containerType as ContainerVariantType;
+
+        if (dataType == "Pair" || dataType == "Tuple" || dataType == "Struct") {
+            let [names, fields] = parseFields(structFields);
+            let structType: StructType = {
+                kind: dataType == "Struct" ? { Named: { name: structName, field_names: names } } : dataType,
+                fields,
+            };
+
+            return { Struct: [structType, container] } as FunctionType;
+        } else if (dataType == "Set") {
+            let [_names, [key]] = parseFields(structFields);
+            let setType: SetType = { unordered, key };
+
+            return { Set: [setType, container] };
+        } else if (dataType == "Map") {
+            let [_names, [key, value]] = parseFields(structFields);
+            let mapType: MapType = { unordered, key, value };
+
+            return { Map: [mapType, container] };
+        }
 
-        return item as FunctionType;
+        return { [dataType]: container } as FunctionType;
     }
 
+    const getItem = () => defaultValue(getItemType());
+
     const addItem = () => {
         setTestFormat(produce(testFormat, (format) => {
             format.arguments.push(getItem());
@@ -84,6 +165,9 @@ function ArgumentsEditor(): JSX.Element {
                         }));
                     }}
                     className="p-2 bg-white border border-neutral-300 rounded-md"></input>
+                <button
+                    onClick={inferSignature}
+                    className="border-blue-300 py-1 px-6 text-sm bg-blue-700 hover:bg-blue-600 transition-colors text-blue-50 rounded-full border">Infer from reference</button>
             </div>
 
             <div className="grid grid-cols-2 border gap-2 bg-white p-4 rounded-md border-neutral-300 w-auto">
@@ -96,6 +180,7 @@ function ArgumentsEditor(): JSX.Element {
                     <option>Graph</option>
                     <option>List</option>
                     <option>Single</option>
+                    <option value="Vector">Vector of Grids</option>
                 </select>
 
                 <span>Data Type:</span>
@@ -105,12 +190,48 @@ function ArgumentsEditor(): JSX.Element {
                 >
                     <option>Int</option>
                     <option>Long</option>
+                    <option>UInt</option>
+                    <option>ULong</option>
+                    <option>Short</option>
+                    <option>Byte</option>
+                    <option>Int128</option>
                     <option>Float</option>
                     <option>Double</option>
                     <option>String</option>
                     <option>Char</option>
+                    <option>Pair</option>
+                    <option>Tuple</option>
+                    <option>Struct</option>
+                    <option>Set</option>
+                    <option>Map</option>
+                    <option>LinkedList</option>
+                    <option>BinaryTree</option>
                 </select>
 
+                {dataType == "Struct" && <>
+                    <span>Struct Name:</span>
+                    <input value={structName} onChange={e => setStructName(e.currentTarget.value)} />
+                </>}
+
+                {(dataType == "Pair" || dataType == "Tuple" || dataType == "Struct") && <>
+                    <span>Fields:</span>
+                    <input
+                        value={structFields}
+                        placeholder={dataType == "Struct" ? "x: Int, y: Int" : "Int, String List"}
+                        onChange={e => setStructFields(e.currentTarget.value)} />
+                </>}
+
+                {(dataType == "Set" || dataType == "Map") && <>
+                    <span>{dataType == "Set" ? "Key:" : "Key, Value:"}</span>
+                    <input
+                        value={structFields}
+                        placeholder={dataType == "Set" ? "Int" : "String, Int List"}
+                        onChange={e => setStructFields(e.currentTarget.value)} />
+
+                    <span>Unordered:</span>
+                    <input type="checkbox" checked={unordered} onChange={e => setUnordered(e.currentTarget.checked)} />
+                </>}
+
                 <div className="col-span-2 flex">
                     <button
                         onClick={addItem}
@@ -159,6 +280,52 @@ function TemplateEditor(): JSX.Element {
     );
 }
 
+function CheckerEditor(): JSX.Element {
+    const [checker, setChecker] = useAdminStore(
+        (state) => [state.problemChecker, state.setProblemChecker],
+        shallow
+    );
+
+    return (
+        <div className="grid grid-rows-min-full grid-cols-full">
+            <p className="p-2 border-b border-neutral-300 dark:border-neutral-700">
+                Optional. For problems with many valid answers, define <code>std::string check(...)</code>,
+                taking the arguments of a test followed by the expected output and the submission&apos;s output.
+                Return an empty string to accept the output, or a message explaining why it is wrong.
+            </p>
+            <Editor
+                language="cpp"
+                value={checker}
+                onChange={(value, _event) => setChecker(value)}
+            />
+        </div>
+    );
+}
+
+function InteractorEditor(): JSX.Element {
+    const [interactor, setInteractor] = useAdminStore(
+        (state) => [state.problemInteractor, state.setProblemInteractor],
+        shallow
+    );
+
+    return (
+        <div className="grid grid-rows-min-full grid-cols-full">
+            <p className="p-2 border-b border-neutral-300 dark:border-neutral-700">
+                Only used by interactive problems. Define <code>void setup(...)</code>, which receives the
+                arguments of each test, along with the functions submissions may call, e.g. <code>int query(int guess)</code>.
+                Submissions declare these functions themselves, and their own function is called without any arguments.
+                Optionally define <code>bool accept()</code>, which is called once the submission returns, to fail tests
+                whose answers shouldn&apos;t be accepted.
+            </p>
+            <Editor
+                language="cpp"
+                value={interactor}
+                onChange={(value, _event) => setInteractor(value)}
+            />
+        </div>
+    );
+}
+
 function TitleEditor(): JSX.Element {
     const [title, setTitle] = useAdminStore((state) => [state.problemTitle, state.setProblemTitle], shallow);
 
@@ -230,6 +397,72 @@ function CompetitionPicker(): JSX.Element {
     );
 }
 
+function KindPicker(): JSX.Element {
+    const [kind, setKind] = useAdminStore((state) => [state.problemKind, state.setProblemKind]);
+    const [normalization, setNormalization] = useAdminStore((state) => [state.problemOutputNormalization, state.setProblemOutputNormalization]);
+    const [comparisonMode, setComparisonMode] = useAdminStore((state) => [state.problemComparisonMode, state.setProblemComparisonMode]);
+
+    return (
+        <div className="px-4 border-neutral-300 border-r h-full flex gap-4 items-center">
+            <label>Judged by: </label>
+            <select
+                value={kind}
+                onChange={(e) => setKind(e.target.value as ProblemKind)}
+                className="border-neutral-300 dark:border-neutral-700 border rounded p-2 bg-neutral-50 dark:bg-neutral-900 outline-0 transition-shadow focus:ring dark:ring-neutral-700 ring-neutral-300"
+            >
+                <option value="function">Function (default)</option>
+                <option value="stdio">Stdin/stdout</option>
+                <option value="interactive">Interactive</option>
+            </select>
+            {kind == "stdio" || <select
+                value={comparisonMode}
+                onChange={(e) => setComparisonMode(e.target.value as ComparisonMode)}
+                className="border-neutral-300 dark:border-neutral-700 border rounded p-2 bg-neutral-50 dark:bg-neutral-900 outline-0 transition-shadow focus:ring dark:ring-neutral-700 ring-neutral-300"
+            >
+                <option value="ordered">Ordered output</option>
+                <option value="unordered_list">Any order of the list/rows</option>
+                <option value="unordered_rows">Any order within rows</option>
+                <option value="unordered">Any order</option>
+            </select>}
+            {kind == "stdio" || <ToleranceEditor />}
+            {kind == "stdio" && <select
+                value={normalization}
+                onChange={(e) => setNormalization(e.target.value as OutputNormalization)}
+                className="border-neutral-300 dark:border-neutral-700 border rounded p-2 bg-neutral-50 dark:bg-neutral-900 outline-0 transition-shadow focus:ring dark:ring-neutral-700 ring-neutral-300"
+            >
+                <option value="lines">Ignore trailing whitespace</option>
+                <option value="tokens">Ignore all whitespace</option>
+                <option value="exact">Exact</option>
+            </select>}
+        </div>
+    );
+}
+
+function ToleranceEditor(): JSX.Element {
+    const [tolerance, setTolerance] = useAdminStore((state) => [state.problemFloatTolerance, state.setProblemFloatTolerance]);
+    const inputClasses = "w-24 border-neutral-300 dark:border-neutral-700 border rounded p-2 bg-neutral-50 dark:bg-neutral-900 outline-0 transition-shadow focus:ring dark:ring-neutral-700 ring-neutral-300";
+
+    return (
+        <>
+            <label title="Floating point outputs are accepted within either tolerance">Float tolerance: </label>
+            <input
+                type="number"
+                title="Absolute"
+                value={tolerance.absolute}
+                onChange={(e) => setTolerance({ absolute: parseFloat(e.target.value) || 0 })}
+                className={inputClasses}
+            />
+            <input
+                type="number"
+                title="Relative"
+                value={tolerance.relative}
+                onChange={(e) => setTolerance({ relative: parseFloat(e.target.value) || 0 })}
+                className={inputClasses}
+            />
+        </>
+    );
+}
+
 function SubmitButton(): JSX.Element {
     const setError = useSession((state) => state.setError);
     const [loading, setLoading] = useState(false);
@@ -245,11 +478,18 @@ function SubmitButton(): JSX.Element {
             problemTests: tests,
             problemPublishTime: publish_time,
             problemRuntimeMultiplier: runtime_multiplier,
-            problemCompetitionId: competition_id
+            problemCompetitionId: competition_id,
+            problemKind: kind,
+            problemOutputNormalization: output_normalization,
+            problemFloatTolerance: float_tolerance,
+            problemComparisonMode: comparison_mode,
+            problemChecker: checker,
+            problemInteractor: interactor
         } = useAdminStore.getState();
 
         // TODO: Look into zod validator
-        if (!title || !description || !reference || !template || tests.length === 0) {
+        // function problems without a template get one generated from the reference
+        if (!title || !description || !reference || (!template && kind != "function") || tests.length === 0) {
             setError("One or more required fields is empty.", true);
             return;
         }
@@ -270,7 +510,13 @@ function SubmitButton(): JSX.Element {
                     tests,
                     publish_time: publish_time ? new Date(publish_time).toISOString().slice(0, -1) : undefined,
                     runtime_multiplier,
-                    competition_id
+                    competition_id,
+                    kind,
+                    output_normalization,
+                    float_tolerance,
+                    comparison_mode,
+                    checker: checker.trim() ? checker : undefined,
+                    interactor: interactor.trim() ? interactor : undefined
                 }),
             })).json();
 
@@ -316,15 +562,18 @@ const ProblemEditorPage: NextPage = () => {
 
                 <Tabbed
                     className="border-y border-neutral-300 dark:border-neutral-700 lg:border-0"
-                    titles={["Template", "Tests"]}
+                    titles={["Template", "Tests", "Checker", "Interactor"]}
                 >
                     <TemplateEditor />
                     <TestsEditor />
+                    <CheckerEditor />
+                    <InteractorEditor />
                 </Tabbed>
 
                 <div className="border-t border-neutral-300 dark:border-neutral-700 flex flex-col items-center lg:bg-white dark:lg:bg-black lg:flex-row">
                     <Scheduler />
                     <CompetitionPicker />
+                    <KindPicker />
                     <SubmitButton />
                 </div>
             </div>
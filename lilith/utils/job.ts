@@ -4,12 +4,20 @@ export type JobStatus<T, E> = {
     id: number,
     queue_position: number,
 
+    tests_completed?: number,
+    tests_total?: number,
+
     response?: T,
     error?: E,
 };
 
-export async function monitorJob<T, E>(job: JobStatus<T, E>, updateQueuePosition: (pos: number) => void): Promise<[T?, E?]> {
+export async function monitorJob<T, E>(
+    job: JobStatus<T, E>,
+    updateQueuePosition: (pos: number) => void,
+    updateProgress?: (completed: number, total: number) => void
+): Promise<[T?, E?]> {
     let oldQueuePosition = 0;
+    let oldTestsCompleted = 0;
 
     if (job.error) {
         return [undefined, job.error];
@@ -40,9 +48,25 @@ export async function monitorJob<T, E>(job: JobStatus<T, E>, updateQueuePosition
 
         updateQueuePosition(job_status.queue_position);
 
+        if (job_status.tests_total) {
+            // Reset timeout while tests are still completing
+            if (oldTestsCompleted != job_status.tests_completed)
+                i = 0;
+
+            oldTestsCompleted = job_status.tests_completed ?? 0;
+            updateProgress?.(oldTestsCompleted, job_status.tests_total);
+        }
+
         // wait 1s before the next iteration
         await new Promise(resolve => setTimeout(resolve, 500));
     }
 
     throw Error("Test took too long");
 }
+
+export async function cancelJob(id: number): Promise<void> {
+    await fetch(api_url(`/run/cancel/${id}`), {
+        method: "POST",
+        credentials: "include",
+    });
+}
@@ -0,0 +1,16 @@
+const UNITS = ["B", "KiB", "MiB", "GiB"];
+
+export function memoryFormat(bytes: number): string {
+    let unit = 0;
+
+    while (bytes >= 1024 && unit < UNITS.length - 1) {
+        bytes /= 1024;
+        unit += 1;
+    }
+
+    return `${Number(bytes.toFixed(1))} ${UNITS[unit]}`;
+}
+
+export function memoryFormatLong(bytes: number): string {
+    return Intl.NumberFormat('en', { notation: "standard" }).format(bytes) + " bytes";
+}
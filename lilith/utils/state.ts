@@ -6,6 +6,15 @@ import { Activity } from "../pages/meetings/new";
 
 type EditorThemeType = "light" | "dark" | "system";
 
+export type Language = "c++" | "c" | "rust" | "python";
+
+export const LANGUAGES: { [key in Language]: string } = {
+    "c++": "C++",
+    "c": "C",
+    "rust": "Rust",
+    "python": "Python",
+};
+
 type Auth = "ADMIN" | "OFFICER" | "MEMBER";
 
 export interface User {
@@ -19,7 +28,10 @@ export interface User {
 export type AsymptoticComplexity =
     "EXPONENTIAL" |
     "QUADRATIC" |
+    "PRODUCT" |
+    "EDGES_LOG_VERTICES" |
     "LOG_LINEAR" |
+    "VERTICES_PLUS_EDGES" |
     "LINEAR" |
     "SQRT" |
     "LOG" |
@@ -35,6 +47,10 @@ export type Submission = {
     runtime: number;
     time: string;
     complexity?: AsymptoticComplexity;
+    complexity_r_squared?: number;
+    complexity_constant?: number;
+    memory?: number;
+    heap?: number;
 }
 
 
@@ -42,12 +58,14 @@ export interface Store {
     vimEnabled: boolean;
     editorTheme: EditorThemeType;
     editorFontSize: number;
+    language: Language;
 
     problemImpls: { [key: number]: string };
 
     setVimEnabled: (vimEnabled: boolean) => void;
     setEditorTheme: (editorTheme: EditorThemeType) => void;
     setEditorFontSize: (fontSize: number) => void;
+    setLanguage: (language: Language) => void;
 
     setProblemImpl: (id: number, impl: string) => void;
 }
@@ -58,6 +76,7 @@ export const useStore = create<Store>()(
             vimEnabled: false,
             editorTheme: "system",
             editorFontSize: 18,
+            language: "c++",
             problemImpls: {},
 
             setProblemImpl: (id, impl) =>
@@ -87,6 +106,13 @@ export const useStore = create<Store>()(
                         state.editorFontSize = fontSize;
                     })
                 ),
+
+            setLanguage: (language) =>
+                set(
+                    produce((state: Store) => {
+                        state.language = language;
+                    })
+                ),
         }),
         {
             name: "data",
@@ -124,6 +150,16 @@ export const useSession = create<Session>()((set) => ({
         ),
 }));
 
+export type ProblemKind = "function" | "stdio" | "interactive";
+export type OutputNormalization = "exact" | "lines" | "tokens";
+
+export type ComparisonMode = "ordered" | "unordered_rows" | "unordered_list" | "unordered";
+
+export interface FloatTolerance {
+    absolute: number;
+    relative: number;
+}
+
 // TODO: restructure this to use nested classes instead.
 export interface AdminState {
     problemTitle: string;
@@ -131,17 +167,25 @@ export interface AdminState {
     problemDescription: string;
     problemReference: string;
     problemTemplate: string;
+    problemChecker: string;
+    problemInteractor: string;
     problemPublishTime?: string;
     problemTests: Test[];
     problemDateShown: boolean;
     problemRuntimeMultiplier: number;
     problemCompetitionId?: number;
+    problemKind: ProblemKind;
+    problemOutputNormalization: OutputNormalization;
+    problemFloatTolerance: FloatTolerance;
+    problemComparisonMode: ComparisonMode;
 
     setProblemTitle: (title: string) => void;
     setProblemTestFormat: (type: WasmFunctionCall) => void;
     setProblemDescription: (description: string) => void;
     setProlbemReference: (reference: string) => void;
     setProblemTemplate: (template: string) => void;
+    setProblemChecker: (checker: string) => void;
+    setProblemInteractor: (interactor: string) => void;
     setProblemDateShown: (shown: boolean) => void;
 
     updateProblemTest: (index: number, test: Partial<Test>) => void;
@@ -159,6 +203,10 @@ export interface AdminState {
     setProblemPublishTime: (time?: string) => void;
     setProblemCompetitionId: (competitionId?: number) => void;
     setProblemRuntimeMultiplier: (multiplier: number) => void;
+    setProblemKind: (kind: ProblemKind) => void;
+    setProblemOutputNormalization: (normalization: OutputNormalization) => void;
+    setProblemFloatTolerance: (tolerance: Partial<FloatTolerance>) => void;
+    setProblemComparisonMode: (mode: ComparisonMode) => void;
 
     setMeetingTitle: (title: string) => void;
     setMeetingDescription: (description: string) => void;
@@ -176,6 +224,8 @@ export const useAdminStore = create<AdminState>()(
             problemDescription: "",
             problemReference: "",
             problemTemplate: "",
+            problemChecker: "",
+            problemInteractor: "",
             problemTestFormat: {
                 name: "",
                 arguments: [],
@@ -184,6 +234,10 @@ export const useAdminStore = create<AdminState>()(
             problemTests: [],
             problemRuntimeMultiplier: 1.1,
             problemDateShown: false,
+            problemKind: "function",
+            problemOutputNormalization: "lines",
+            problemFloatTolerance: { absolute: 1e-9, relative: 0 },
+            problemComparisonMode: "ordered",
 
             meetingTitle: "",
             meetingDescription: "",
@@ -225,6 +279,20 @@ export const useAdminStore = create<AdminState>()(
                     })
                 ),
 
+            setProblemChecker: (checker: string) =>
+                set(
+                    produce((state: AdminState) => {
+                        state.problemChecker = checker;
+                    })
+                ),
+
+            setProblemInteractor: (interactor: string) =>
+                set(
+                    produce((state: AdminState) => {
+                        state.problemInteractor = interactor;
+                    })
+                ),
+
             setProblemDateShown: (shown: boolean) =>
                 set(
                     produce((state: AdminState) => {
@@ -286,6 +354,12 @@ export const useAdminStore = create<AdminState>()(
                         state.problemDescription = "";
                         state.problemReference = "";
                         state.problemTemplate = "";
+                        state.problemChecker = "";
+                        state.problemInteractor = "";
+                        state.problemKind = "function";
+                        state.problemOutputNormalization = "lines";
+                        state.problemFloatTolerance = { absolute: 1e-9, relative: 0 };
+                        state.problemComparisonMode = "ordered";
                     })
                 ),
 
@@ -345,6 +419,37 @@ export const useAdminStore = create<AdminState>()(
                     produce((state: AdminState) => {
                         state.problemRuntimeMultiplier = multiplier;
                     })
+                ),
+
+            setProblemKind: (kind: ProblemKind) =>
+                set(
+                    produce((state: AdminState) => {
+                        state.problemKind = kind;
+                    })
+                ),
+
+            setProblemOutputNormalization: (normalization: OutputNormalization) =>
+                set(
+                    produce((state: AdminState) => {
+                        state.problemOutputNormalization = normalization;
+                    })
+                ),
+
+            setProblemFloatTolerance: (tolerance: Partial<FloatTolerance>) =>
+                set(
+                    produce((state: AdminState) => {
+                        state.problemFloatTolerance = {
+                            ...state.problemFloatTolerance,
+                            ...tolerance,
+                        };
+                    })
+                ),
+
+            setProblemComparisonMode: (mode: ComparisonMode) =>
+                set(
+                    produce((state: AdminState) => {
+                        state.problemComparisonMode = mode;
+                    })
                 )
 
         }),
@@ -0,0 +1,2 @@
+alter table submissions
+add column language text not null default 'c++';
@@ -0,0 +1,5 @@
+alter table problems
+add column kind text not null default 'function';
+
+alter table problems
+add column output_normalization text not null default 'lines';
@@ -0,0 +1,2 @@
+alter table problems
+add column checker text;
@@ -0,0 +1,5 @@
+alter table problems
+add column interactor text;
+
+alter table test_results
+add column queries integer;
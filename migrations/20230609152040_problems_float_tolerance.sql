@@ -0,0 +1,5 @@
+alter table problems
+add column float_absolute_tolerance real not null default 1e-9;
+
+alter table problems
+add column float_relative_tolerance real not null default 0;
@@ -0,0 +1,2 @@
+alter table problems
+add column comparison_mode text not null default 'ordered';
@@ -0,0 +1,2 @@
+alter table submissions add complexity_r_squared real;
+alter table submissions add complexity_constant real;
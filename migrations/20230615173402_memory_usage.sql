@@ -0,0 +1,5 @@
+alter table test_results add memory integer;
+alter table test_results add heap integer;
+
+alter table submissions add memory integer;
+alter table submissions add heap integer;
@@ -0,0 +1 @@
+alter table test_results add interactor_fuel integer;